@@ -3,7 +3,7 @@
 use compact_str::CompactString;
 use zwrite::write;
 
-use crate::style::{Color, Style};
+use crate::style::{Color, Style, UnderlineStyle};
 
 impl Style {
     /// Write any bytes that go *before* a piece of text to the given writer.
@@ -40,9 +40,6 @@ impl Style {
             if self.is_italic {
                 write_char('3')
             }
-            if self.is_underline {
-                write_char('4')
-            }
             if self.is_blink {
                 write_char('5')
             }
@@ -57,6 +54,24 @@ impl Style {
             }
         }
 
+        // The underline, unlike the other boolean attributes above, has a
+        // colon sub-parameter form (`4:n`) for shapes beyond a plain single
+        // line, so it can't go through the single-char `write_char` closure.
+        if self.is_underline {
+            if written_anything {
+                write!(f, ";").unwrap();
+            }
+            written_anything = true;
+            #[cfg(feature = "gnu_legacy")]
+            write!(f, "0").unwrap();
+            match self.underline_style {
+                Some(shape) if shape.code() != UnderlineStyle::Single.code() => {
+                    write!(f, "4:{}", shape.code()).unwrap()
+                }
+                _ => write!(f, "4").unwrap(),
+            }
+        }
+
         // The foreground and background colors, if specified, need to be
         // handled specially because the number codes are more complicated.
         // (see `write_background_code` and `write_foreground_code`)
@@ -72,9 +87,17 @@ impl Style {
             if written_anything {
                 write!(f, ";").unwrap();
             }
+            written_anything = true;
             fg.write_foreground_code(f);
         }
 
+        if let Some(underline_color) = self.underline_color {
+            if written_anything {
+                write!(f, ";").unwrap();
+            }
+            underline_color.write_underline_code(f);
+        }
+
         // All the codes end with an `m`, because reasons.
         write!(f, "m").unwrap();
     }
@@ -142,6 +165,43 @@ impl Color {
             Color::LightGray => write!(f, "107").unwrap(),
         }
     }
+
+    /// Writes the `58`-prefixed underline-color code. Unlike `30..=37`/
+    /// `90..=97` for foreground and `40..=47`/`100..=107` for background,
+    /// there's no dedicated SGR family for named colors here, so they're
+    /// written via their xterm 256-color palette index instead.
+    fn write_underline_code(&self, f: &mut CompactString) {
+        match self {
+            Color::Fixed(num) => write!(f, "58;5;{}", num).unwrap(),
+            Color::Rgb(r, g, b) => write!(f, "58;2;{};{};{}", r, g, b).unwrap(),
+            Color::Default => write!(f, "59").unwrap(),
+            _ => write!(f, "58;5;{}", self.basic_palette_index()).unwrap(),
+        }
+    }
+
+    /// The xterm 256-color palette index (0..=15) a basic named color maps
+    /// to, for [`Color::write_underline_code`].
+    pub(crate) fn basic_palette_index(&self) -> u8 {
+        match self {
+            Color::Black => 0,
+            Color::Red => 1,
+            Color::Green => 2,
+            Color::Yellow => 3,
+            Color::Blue => 4,
+            Color::Purple | Color::Magenta => 5,
+            Color::Cyan => 6,
+            Color::White => 7,
+            Color::DarkGray => 8,
+            Color::LightRed => 9,
+            Color::LightGreen => 10,
+            Color::LightYellow => 11,
+            Color::LightBlue => 12,
+            Color::LightPurple | Color::LightMagenta => 13,
+            Color::LightCyan => 14,
+            Color::LightGray => 15,
+            Color::Fixed(_) | Color::Rgb(..) | Color::Default => 0,
+        }
+    }
 }
 
 impl Style {
@@ -185,7 +245,7 @@ macro_rules! test {
 #[cfg(not(feature = "gnu_legacy"))]
 mod test {
     use crate::style::Color::*;
-    use crate::style::Style;
+    use crate::style::{Style, UnderlineStyle};
     use compact_str::format_compact;
 
     test!(plain:                 Style::default();                  "text/plain" => "text/plain");
@@ -223,6 +283,12 @@ mod test {
     test!(hidden:                Style::new().hidden();             "hi" => "\x1B[8mhi\x1B[0m");
     test!(stricken:              Style::new().strikethrough();      "hi" => "\x1B[9mhi\x1B[0m");
     test!(lr_on_lr:              LightRed.on(LightRed);             "hi" => "\x1B[101;91mhi\x1B[0m");
+    test!(curly_underline:       Style::new().underline_style(UnderlineStyle::Curly); "hi" => "\x1B[4:3mhi\x1B[0m");
+    test!(double_underline:      Style::new().underline_style(UnderlineStyle::Double); "hi" => "\x1B[4:2mhi\x1B[0m");
+    test!(single_underline_style: Style::new().underline_style(UnderlineStyle::Single); "hi" => "\x1B[4mhi\x1B[0m");
+    test!(underline_color_rgb:   Style::new().underline().underline_color(Rgb(255,0,0)); "hi" => "\x1B[4;58;2;255;0;0mhi\x1B[0m");
+    test!(underline_color_fixed: Style::new().underline().underline_color(Fixed(9));     "hi" => "\x1B[4;58;5;9mhi\x1B[0m");
+    test!(curly_colored_underline: Red.bold().underline_style(UnderlineStyle::Curly).underline_color(Yellow); "hi" => "\x1B[1;4:3;31;58;5;3mhi\x1B[0m");
 }
 
 #[cfg(test)]
@@ -0,0 +1,108 @@
+use crate::style::Style;
+
+/// The minimal escape-code transition needed to move the terminal from one
+/// [`Style`] to another, for rendering a sequence of adjacent styled spans
+/// without a full `RESET` + prefix between every one of them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// The styles are equal: nothing needs to be written.
+    NoDifference,
+
+    /// `next` is reachable from `first` by only *adding* SGR codes (no
+    /// attribute needs clearing and no color needs changing), so only the
+    /// attributes `next` has that `first` lacks need to be written.
+    ExtraStyles(Style),
+
+    /// Some attribute must be cleared or some color changed; since a single
+    /// SGR code can't remove just one attribute, the only way there is a
+    /// full `RESET` followed by `next`'s complete prefix.
+    Reset,
+}
+
+impl Difference {
+    /// Computes the transition from `first` to `next`.
+    pub fn between(first: Style, next: Style) -> Difference {
+        if first == next {
+            return Difference::NoDifference;
+        }
+
+        let needs_reset = (first.is_bold && !next.is_bold)
+            || (first.is_dimmed && !next.is_dimmed)
+            || (first.is_italic && !next.is_italic)
+            || (first.is_underline && !next.is_underline)
+            || (first.is_blink && !next.is_blink)
+            || (first.is_reverse && !next.is_reverse)
+            || (first.is_hidden && !next.is_hidden)
+            || (first.is_strikethrough && !next.is_strikethrough)
+            || (first.foreground.is_some() && first.foreground != next.foreground)
+            || (first.background.is_some() && first.background != next.background)
+            || (first.underline_color.is_some() && first.underline_color != next.underline_color)
+            // The `4:n` shape replaces the `4` code entirely rather than
+            // adding to it, so a shape change while underlining stays on
+            // can't be expressed incrementally either.
+            || (first.is_underline && next.is_underline && first.underline_style != next.underline_style);
+
+        if needs_reset {
+            return Difference::Reset;
+        }
+
+        Difference::ExtraStyles(Style {
+            foreground: next.foreground.filter(|_| first.foreground.is_none()),
+            background: next.background.filter(|_| first.background.is_none()),
+            is_bold: next.is_bold && !first.is_bold,
+            is_dimmed: next.is_dimmed && !first.is_dimmed,
+            is_italic: next.is_italic && !first.is_italic,
+            is_underline: next.is_underline && !first.is_underline,
+            is_blink: next.is_blink && !first.is_blink,
+            is_reverse: next.is_reverse && !first.is_reverse,
+            is_hidden: next.is_hidden && !first.is_hidden,
+            is_strikethrough: next.is_strikethrough && !first.is_strikethrough,
+            // Only meaningful once `is_underline` above is true, which only
+            // happens when `first` had no underline at all (guarded above).
+            underline_style: next.underline_style,
+            underline_color: next.underline_color.filter(|_| first.underline_color.is_none()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Difference;
+    use crate::style::Color::*;
+    use crate::style::Style;
+
+    fn d(first: Style, next: Style) -> Difference {
+        Difference::between(first, next)
+    }
+
+    #[test]
+    fn identical_styles_have_no_difference() {
+        assert_eq!(d(Red.bold(), Red.bold()), Difference::NoDifference);
+    }
+
+    #[test]
+    fn adding_an_attribute_is_extra_styles() {
+        assert_eq!(
+            d(Red.normal(), Red.bold()),
+            Difference::ExtraStyles(Style::new().bold())
+        );
+    }
+
+    #[test]
+    fn adding_a_color_from_none_is_extra_styles() {
+        assert_eq!(
+            d(Style::new().bold(), Red.bold()),
+            Difference::ExtraStyles(Style::new().fg(Red))
+        );
+    }
+
+    #[test]
+    fn removing_an_attribute_requires_reset() {
+        assert_eq!(d(Red.bold(), Red.normal()), Difference::Reset);
+    }
+
+    #[test]
+    fn changing_a_color_requires_reset() {
+        assert_eq!(d(Red.normal(), Blue.normal()), Difference::Reset);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::style::Color;
+
+/// The degree (cubic) of the B-spline [`Gradient`] fits through its control
+/// colors, when there are enough of them; see [`Gradient::new`].
+const DEGREE: usize = 3;
+
+/// A smooth multicolor gradient over an ordered list of control
+/// [`Color`]s, for decorating output -- a rainbow line-number gutter, a
+/// gradient rule or header, and the like.
+///
+/// Control colors are resolved to RGB and fit with a uniform B-spline over
+/// a clamped knot vector (de Boor's recurrence), so [`Gradient::sample`]
+/// always starts and ends exactly on the first and last control colors
+/// while smoothly blending the ones in between. The spline is cubic when
+/// there are at least four control colors, and drops degree for fewer (two
+/// colors give a straight line, three a quadratic curve); a single control
+/// color just repeats.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    control_points: Vec<(f32, f32, f32)>,
+    degree: usize,
+    knots: Vec<f32>,
+}
+
+impl Gradient {
+    /// Builds a gradient over `colors`. Panics if `colors` is empty.
+    pub fn new(colors: &[Color]) -> Gradient {
+        assert!(!colors.is_empty(), "Gradient needs at least one control color");
+
+        let control_points: Vec<(f32, f32, f32)> = colors
+            .iter()
+            .map(|&color| {
+                let (r, g, b) = color.to_rgb_triple();
+                (r as f32, g as f32, b as f32)
+            })
+            .collect();
+
+        let degree = DEGREE.min(control_points.len() - 1);
+        let knots = clamped_knot_vector(control_points.len(), degree);
+
+        Gradient {
+            control_points,
+            degree,
+            knots,
+        }
+    }
+
+    /// Samples the gradient at `n` evenly spaced points (`t = i / (n - 1)`
+    /// for `i in 0..n`), returning `n` [`Color::Rgb`] values -- the first
+    /// and last always exactly matching the first and last control colors.
+    pub fn sample(&self, n: usize) -> Vec<Color> {
+        if n == 0 {
+            return Vec::new();
+        }
+        if self.control_points.len() == 1 || n == 1 {
+            let (r, g, b) = self.control_points[0];
+            return vec![to_rgb_color(r, g, b); n];
+        }
+
+        (0..n)
+            .map(|i| {
+                let t = i as f32 / (n - 1) as f32;
+                let (r, g, b) = self.evaluate(t);
+                to_rgb_color(r, g, b)
+            })
+            .collect()
+    }
+
+    /// Evaluates the spline at parameter `t` (`0.0..=1.0`) via de Boor's
+    /// recurrence: `d_j^r = (1 - a) * d_{j-1}^{r-1} + a * d_j^{r-1}`, with
+    /// `a = (t - u_j) / (u_{j+p-r+1} - u_j)`, iterating `r` from `1` up to
+    /// the spline's degree `p`.
+    fn evaluate(&self, t: f32) -> (f32, f32, f32) {
+        let p = self.degree;
+        let span = self.find_span(t);
+
+        let mut d: Vec<(f32, f32, f32)> =
+            (0..=p).map(|j| self.control_points[span - p + j]).collect();
+
+        for r in 1..=p {
+            for j in (r..=p).rev() {
+                let left = self.knots[span - p + j];
+                let right = self.knots[span + 1 + j - r];
+                let alpha = if right > left {
+                    (t - left) / (right - left)
+                } else {
+                    0.0
+                };
+                d[j] = lerp(d[j - 1], d[j], alpha);
+            }
+        }
+
+        d[p]
+    }
+
+    /// The knot span index `k` (`degree <= k <= control_points.len() - 1`)
+    /// such that `knots[k] <= t < knots[k + 1]`, with `t >= 1.0` (and any
+    /// floating-point overshoot past it) clamped to the last valid span.
+    fn find_span(&self, t: f32) -> usize {
+        let p = self.degree;
+        let last = self.control_points.len() - 1;
+        if t >= self.knots[last + 1] {
+            return last;
+        }
+        (p..=last).find(|&k| t < self.knots[k + 1]).unwrap_or(last)
+    }
+}
+
+/// A clamped uniform knot vector for `n_control_points` control points and
+/// spline `degree`: `degree + 1` repeated `0.0`s, then uniformly spaced
+/// interior knots, then `degree + 1` repeated `1.0`s -- so the curve
+/// interpolates exactly through the first and last control points.
+fn clamped_knot_vector(n_control_points: usize, degree: usize) -> Vec<f32> {
+    let interior = n_control_points.saturating_sub(degree + 1);
+
+    let mut knots = Vec::with_capacity(n_control_points + degree + 1);
+    knots.extend(std::iter::repeat(0.0).take(degree + 1));
+    for i in 1..=interior {
+        knots.push(i as f32 / (interior + 1) as f32);
+    }
+    knots.extend(std::iter::repeat(1.0).take(degree + 1));
+    knots
+}
+
+fn lerp(a: (f32, f32, f32), b: (f32, f32, f32), t: f32) -> (f32, f32, f32) {
+    (
+        a.0 + (b.0 - a.0) * t,
+        a.1 + (b.1 - a.1) * t,
+        a.2 + (b.2 - a.2) * t,
+    )
+}
+
+fn to_rgb_color(r: f32, g: f32, b: f32) -> Color {
+    let channel = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+    Color::Rgb(channel(r), channel(g), channel(b))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sample_starts_and_ends_on_the_control_colors() {
+        let gradient = Gradient::new(&[
+            Color::Rgb(255, 0, 0),
+            Color::Rgb(0, 255, 0),
+            Color::Rgb(0, 0, 255),
+        ]);
+        let samples = gradient.sample(5);
+        assert_eq!(samples.first(), Some(&Color::Rgb(255, 0, 0)));
+        assert_eq!(samples.last(), Some(&Color::Rgb(0, 0, 255)));
+        assert_eq!(samples.len(), 5);
+    }
+
+    #[test]
+    fn two_control_points_interpolate_linearly() {
+        let gradient = Gradient::new(&[Color::Rgb(0, 0, 0), Color::Rgb(100, 0, 0)]);
+        let samples = gradient.sample(3);
+        assert_eq!(
+            samples,
+            vec![
+                Color::Rgb(0, 0, 0),
+                Color::Rgb(50, 0, 0),
+                Color::Rgb(100, 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn single_control_color_repeats() {
+        let gradient = Gradient::new(&[Color::Rgb(10, 20, 30)]);
+        assert_eq!(gradient.sample(4), vec![Color::Rgb(10, 20, 30); 4]);
+    }
+
+    #[test]
+    fn zero_samples_returns_empty() {
+        let gradient = Gradient::new(&[Color::Rgb(10, 20, 30)]);
+        assert!(gradient.sample(0).is_empty());
+    }
+}
@@ -7,8 +7,13 @@
 
 pub mod ansi;
 
+mod difference;
+pub use difference::Difference;
+
+mod parse;
+
 mod style;
-pub use style::{Color, Style};
+pub use style::{Color, ColorLevel, Style};
 
 mod windows;
 pub use crate::windows::*;
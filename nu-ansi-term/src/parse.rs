@@ -0,0 +1,319 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::style::{Color, Style};
+
+impl Style {
+    /// Parses an SGR escape sequence (`"\x1B[1;31m"`, or a bare `;`-separated
+    /// parameter list with or without the leading `\x1B[`/trailing `m`) and
+    /// applies it on top of `self`, returning the resulting style — the
+    /// inverse of [`Style::prefix`], except that colon-form underline shapes
+    /// (`4:n`) are not parsed and are skipped like a malformed number. `58`
+    /// sets the underline color. `0` resets to [`Style::default`] rather
+    /// than leaving `self` untouched, matching how a real terminal treats
+    /// SGR code 0. Parameters this crate has no representation for (or
+    /// malformed numbers) are skipped rather than erroring, since
+    /// real-world escape sequences can carry codes no `Style` models.
+    pub fn apply_sgr(self, sequence: &str) -> Style {
+        let stripped = sequence.strip_prefix("\x1B[").unwrap_or(sequence);
+        let params = stripped.strip_suffix('m').unwrap_or(stripped);
+
+        let mut style = self;
+        let mut tokens = params.split(';');
+        while let Some(token) = tokens.next() {
+            let Ok(code) = token.parse::<u16>() else {
+                continue;
+            };
+            match code {
+                0 => style = Style::default(),
+                1 => style.is_bold = true,
+                2 => style.is_dimmed = true,
+                3 => style.is_italic = true,
+                4 => style.is_underline = true,
+                5 => style.is_blink = true,
+                7 => style.is_reverse = true,
+                8 => style.is_hidden = true,
+                9 => style.is_strikethrough = true,
+                30..=37 => style.foreground = Some(basic_color(code - 30)),
+                90..=97 => style.foreground = Some(basic_color(code - 90 + 8)),
+                39 => style.foreground = None,
+                40..=47 => style.background = Some(basic_color(code - 40)),
+                100..=107 => style.background = Some(basic_color(code - 100 + 8)),
+                49 => style.background = None,
+                38 => {
+                    if let Some(color) = parse_extended_color(&mut tokens) {
+                        style.foreground = Some(color);
+                    }
+                }
+                48 => {
+                    if let Some(color) = parse_extended_color(&mut tokens) {
+                        style.background = Some(color);
+                    }
+                }
+                58 => {
+                    if let Some(color) = parse_extended_color(&mut tokens) {
+                        style.underline_color = Some(color);
+                    }
+                }
+                _ => {}
+            }
+        }
+        style
+    }
+}
+
+impl FromStr for Style {
+    type Err = Infallible;
+
+    /// Reconstructs a [`Style`] from an SGR escape sequence, starting from
+    /// [`Style::default`]. See [`Style::apply_sgr`] for the parameter
+    /// grammar; this never actually fails, since unrecognized codes are
+    /// simply skipped.
+    fn from_str(sequence: &str) -> Result<Style, Infallible> {
+        Ok(Style::default().apply_sgr(sequence))
+    }
+}
+
+/// The basic-palette `Color` at `index` (0..=15), in the same order as the
+/// `30..=37`/`90..=97` (foreground) and `40..=47`/`100..=107` (background)
+/// SGR code families.
+fn basic_color(index: u16) -> Color {
+    match index {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Purple,
+        6 => Color::Cyan,
+        7 => Color::White,
+        8 => Color::DarkGray,
+        9 => Color::LightRed,
+        10 => Color::LightGreen,
+        11 => Color::LightYellow,
+        12 => Color::LightBlue,
+        13 => Color::LightPurple,
+        14 => Color::LightCyan,
+        _ => Color::LightGray,
+    }
+}
+
+/// The error returned when a [`Color`]/[`Style`] spec passed to
+/// [`Color::from_str`]/[`Style::from_spec`] isn't in any recognized format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError(String);
+
+impl ParseColorError {
+    fn new(spec: &str) -> ParseColorError {
+        ParseColorError(spec.to_owned())
+    }
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid color spec '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parses a human-readable color spec, the sort a user would type into a
+    /// config file or `--color` flag rather than an escape sequence: an RGB
+    /// hex code (`#rrggbb`), an `rgb(r, g, b)` call, a bare decimal xterm
+    /// palette index (`0..=255`; `0..=15` resolve to the named basic
+    /// colors), `"default"`, or one of the 16 standard ANSI color names
+    /// (optionally `bright-`-prefixed).
+    fn from_str(spec: &str) -> Result<Color, ParseColorError> {
+        let trimmed = spec.trim();
+
+        if let Some(hex) = trimmed.strip_prefix('#') {
+            let channel = |range: std::ops::Range<usize>| {
+                u8::from_str_radix(hex.get(range).unwrap_or(""), 16)
+                    .map_err(|_| ParseColorError::new(spec))
+            };
+            if hex.len() != 6 {
+                return Err(ParseColorError::new(spec));
+            }
+            return Ok(Color::Rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?));
+        }
+
+        if let Some(args) = trimmed
+            .strip_prefix("rgb(")
+            .and_then(|s| s.strip_suffix(')'))
+        {
+            let mut channels = args.split(',').map(|part| part.trim().parse::<u8>());
+            return match (channels.next(), channels.next(), channels.next(), channels.next()) {
+                (Some(Ok(r)), Some(Ok(g)), Some(Ok(b)), None) => Ok(Color::Rgb(r, g, b)),
+                _ => Err(ParseColorError::new(spec)),
+            };
+        }
+
+        if !trimmed.is_empty() && trimmed.bytes().all(|b| b.is_ascii_digit()) {
+            return trimmed
+                .parse::<u8>()
+                .map(|n| if n < 16 { basic_color(n as u16) } else { Color::Fixed(n) })
+                .map_err(|_| ParseColorError::new(spec));
+        }
+
+        if trimmed.eq_ignore_ascii_case("default") {
+            return Ok(Color::Default);
+        }
+
+        let (name, offset) = match trimmed.strip_prefix("bright-") {
+            Some(name) => (name, 8u16),
+            None => (trimmed, 0u16),
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "black" => Ok(basic_color(offset)),
+            "red" => Ok(basic_color(1 + offset)),
+            "green" => Ok(basic_color(2 + offset)),
+            "yellow" => Ok(basic_color(3 + offset)),
+            "blue" => Ok(basic_color(4 + offset)),
+            "purple" | "magenta" => Ok(basic_color(5 + offset)),
+            "cyan" => Ok(basic_color(6 + offset)),
+            "white" => Ok(basic_color(7 + offset)),
+            _ => Err(ParseColorError::new(spec)),
+        }
+    }
+}
+
+impl Style {
+    /// Parses a human-readable style spec: any number of space/comma
+    /// separated attribute tokens (`bold`, `dimmed`/`dim`, `italic`,
+    /// `underline`, `blink`, `reverse`, `hidden`, `strikethrough`) and color
+    /// tokens (a bare [`Color`] spec sets the foreground; `on <color>` sets
+    /// the background), in any order -- e.g. `"bold red on black"`. Since
+    /// tokens split on commas and whitespace, color tokens with their own
+    /// internal commas (`rgb(r, g, b)`) aren't usable here; use a hex code
+    /// or named/indexed color instead. This is a different grammar than
+    /// [`Style::from_str`]'s SGR-sequence format, so it's exposed as its own
+    /// method rather than overloading [`FromStr`].
+    pub fn from_spec(spec: &str) -> Result<Style, ParseColorError> {
+        let mut style = Style::default();
+        let mut tokens = spec
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty());
+
+        while let Some(token) = tokens.next() {
+            match token.to_ascii_lowercase().as_str() {
+                "bold" => style.is_bold = true,
+                "dimmed" | "dim" => style.is_dimmed = true,
+                "italic" => style.is_italic = true,
+                "underline" => style.is_underline = true,
+                "blink" => style.is_blink = true,
+                "reverse" => style.is_reverse = true,
+                "hidden" => style.is_hidden = true,
+                "strikethrough" => style.is_strikethrough = true,
+                "on" => {
+                    let color = tokens.next().ok_or_else(|| ParseColorError::new(spec))?;
+                    style.background = Some(color.parse()?);
+                }
+                _ => style.foreground = Some(token.parse()?),
+            }
+        }
+
+        Ok(style)
+    }
+}
+
+/// Parses the sub-parameters following a `38`/`48`/`58` extended-color code:
+/// `5;N` for [`Color::Fixed`] or `2;r;g;b` for [`Color::Rgb`]. Returns
+/// `None` (leaving the caller's existing color untouched) on anything else,
+/// including a short or malformed parameter list.
+fn parse_extended_color<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Option<Color> {
+    match tokens.next()?.parse::<u16>().ok()? {
+        5 => Some(Color::Fixed(tokens.next()?.parse().ok()?)),
+        2 => Some(Color::Rgb(
+            tokens.next()?.parse().ok()?,
+            tokens.next()?.parse().ok()?,
+            tokens.next()?.parse().ok()?,
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::style::Color::*;
+
+    #[test]
+    fn parses_plain_attributes_and_basic_colors() {
+        assert_eq!(
+            "\x1B[1;4;31m".parse::<Style>().unwrap(),
+            Red.bold().underline()
+        );
+    }
+
+    #[test]
+    fn parses_bright_and_background_colors() {
+        assert_eq!(
+            "\x1B[91;104m".parse::<Style>().unwrap(),
+            Style::default().fg(LightRed).on(LightBlue)
+        );
+    }
+
+    #[test]
+    fn parses_extended_colors() {
+        assert_eq!(
+            "\x1B[38;5;166;48;2;1;2;3m".parse::<Style>().unwrap(),
+            Style::default().fg(Fixed(166)).on(Rgb(1, 2, 3))
+        );
+    }
+
+    #[test]
+    fn reset_code_discards_prior_state() {
+        assert_eq!(Red.bold().apply_sgr("\x1B[0m"), Style::default());
+    }
+
+    #[test]
+    fn unknown_codes_are_skipped() {
+        assert_eq!(Style::default().apply_sgr("\x1B[59;31m"), Red.normal());
+    }
+
+    #[test]
+    fn malformed_extended_color_leaves_existing_color_untouched() {
+        assert_eq!(Red.normal().apply_sgr("\x1B[38;5m"), Red.normal());
+    }
+
+    #[test]
+    fn parses_without_trailing_m() {
+        assert_eq!(
+            Style::default().apply_sgr("\x1B[38;5;166"),
+            Style::default().fg(Fixed(166))
+        );
+    }
+
+    #[test]
+    fn color_from_str_parses_hex_rgb_index_and_names() {
+        assert_eq!("#ff8800".parse(), Ok(Rgb(0xff, 0x88, 0x00)));
+        assert_eq!("rgb(1, 2, 3)".parse(), Ok(Rgb(1, 2, 3)));
+        assert_eq!("9".parse(), Ok(LightRed));
+        assert_eq!("166".parse(), Ok(Fixed(166)));
+        assert_eq!("bright-red".parse(), Ok(LightRed));
+        assert_eq!("purple".parse(), Ok(Purple));
+        assert_eq!("default".parse(), Ok(Default));
+    }
+
+    #[test]
+    fn color_from_str_rejects_garbage() {
+        assert!("not-a-color".parse::<Color>().is_err());
+        assert!("#ff88".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn style_from_spec_parses_attributes_and_colors() {
+        assert_eq!(
+            Style::from_spec("bold red on black"),
+            Ok(Red.bold().on(Black))
+        );
+        assert_eq!(
+            Style::from_spec("underline, #112233"),
+            Ok(Style::new().underline().fg(Rgb(0x11, 0x22, 0x33)))
+        );
+    }
+}
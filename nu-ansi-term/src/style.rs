@@ -33,6 +33,16 @@ pub struct Style {
 
     /// Whether this style is struckthrough.
     pub is_strikethrough: bool,
+
+    /// The underline's shape, if it's anything other than a plain single
+    /// line; only meaningful when `is_underline` is set. `None` falls back
+    /// to a plain `4` SGR code rather than the `4:n` colon form.
+    pub underline_style: Option<UnderlineStyle>,
+
+    /// The underline's color, if it differs from the text color; written as
+    /// a separate `58`-prefixed SGR code. Only meaningful when
+    /// `is_underline` is set.
+    pub underline_color: Option<Color>,
 }
 
 impl Style {
@@ -68,6 +78,24 @@ impl Style {
         }
     }
 
+    /// Underlines with `style`'s shape (curly, dotted, ...) instead of a
+    /// plain single line, implying [`Style::underline`].
+    pub const fn underline_style(&self, style: UnderlineStyle) -> Style {
+        Style {
+            is_underline: true,
+            underline_style: Some(style),
+            ..*self
+        }
+    }
+
+    /// Gives the underline a color distinct from the text color.
+    pub const fn underline_color(&self, color: Color) -> Style {
+        Style {
+            underline_color: Some(color),
+            ..*self
+        }
+    }
+
     pub const fn blink(&self) -> Style {
         Style {
             is_blink: true,
@@ -128,6 +156,38 @@ impl Default for Style {
             is_reverse: false,
             is_hidden: false,
             is_strikethrough: false,
+            underline_style: None,
+            underline_color: None,
+        }
+    }
+}
+
+/// A non-default underline shape, written as the `4:n` colon sub-parameter
+/// (a Kitty-originated extension now supported by several terminals). `n`
+/// matches the shape's position in the Kitty spec (`1` is a plain single
+/// underline, which is always written as a bare `4`, even when a `Style`
+/// explicitly asks for it via [`Style::underline_style`]).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "derive_serde_style",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub enum UnderlineStyle {
+    Single,
+    Double,
+    Curly,
+    Dotted,
+    Dashed,
+}
+
+impl UnderlineStyle {
+    pub(crate) fn code(self) -> u8 {
+        match self {
+            UnderlineStyle::Single => 1,
+            UnderlineStyle::Double => 2,
+            UnderlineStyle::Curly => 3,
+            UnderlineStyle::Dotted => 4,
+            UnderlineStyle::Dashed => 5,
         }
     }
 }
@@ -312,6 +372,300 @@ impl Color {
     }
 }
 
+/// The standard ANSI palette's RGB values, in `basic_palette_index` order
+/// (`Black` .. `LightGray`), used by [`Color::to_basic16`]/[`Color::to_basic8`]
+/// as the candidate set to match against. These are the common xterm
+/// defaults, not the textbook web-safe values, so a color downgraded here
+/// lands on the same index a real xterm terminal would actually render it
+/// as.
+const BASIC16_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// The `Color` variants matching [`BASIC16_RGB`], in the same order.
+const BASIC16_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::LightGray,
+];
+
+/// The six RGB levels the xterm 256-color cube's per-channel index (0..=5)
+/// expands to.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Expands a `Fixed` palette index into its approximate RGB value, via the
+/// same cube/grayscale-ramp layout xterm itself uses (see [`Color::Fixed`]'s
+/// docs), so indices 16..=255 can be redistanced against an arbitrary target
+/// color.
+pub(crate) fn fixed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => BASIC16_RGB[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            let (r, g, b) = (i / 36, (i % 36) / 6, i % 6);
+            (
+                CUBE_LEVELS[r as usize],
+                CUBE_LEVELS[g as usize],
+                CUBE_LEVELS[b as usize],
+            )
+        }
+        232..=255 => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+fn squared_distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> i32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    dr * dr + dg * dg + db * db
+}
+
+impl Color {
+    /// Downsamples this color to the nearest xterm 256-color palette entry,
+    /// for terminals that support 256 colors but not full truecolor. Named
+    /// colors and `Fixed` values already in that palette pass through
+    /// unchanged; `Rgb` values are matched against both the 6x6x6 color cube
+    /// and the grayscale ramp, keeping whichever candidate is closer.
+    pub fn to_fixed(self) -> Color {
+        let (r, g, b) = match self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            other => return other,
+        };
+
+        let cube_index = |c: u8| -> u8 {
+            (((c as f32 - 55.0) / 40.0).round().clamp(0.0, 5.0)) as u8
+        };
+        let (ri, gi, bi) = (cube_index(r), cube_index(g), cube_index(b));
+        let cube_rgb = (
+            CUBE_LEVELS[ri as usize],
+            CUBE_LEVELS[gi as usize],
+            CUBE_LEVELS[bi as usize],
+        );
+        let cube_code = 16 + 36 * ri + 6 * gi + bi;
+
+        let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+        let gray_index = (((luma - 8.0) / 10.0).round().clamp(0.0, 23.0)) as u8;
+        let gray_level = 8 + 10 * gray_index;
+        let gray_code = 232 + gray_index;
+
+        if squared_distance((r, g, b), cube_rgb) <= squared_distance((r, g, b), (gray_level, gray_level, gray_level))
+        {
+            Color::Fixed(cube_code)
+        } else {
+            Color::Fixed(gray_code)
+        }
+    }
+
+    /// Downsamples this color to the nearest of the 16 basic ANSI colors,
+    /// for terminals without 256-color support. `Fixed` indices outside the
+    /// basic range are first expanded back to RGB via [`fixed_to_rgb`] so
+    /// they can be redistanced the same way as a truecolor `Rgb` value.
+    pub fn to_basic16(self) -> Color {
+        Self::nearest_basic(self, &BASIC16_COLORS)
+    }
+
+    /// Downsamples this color to the nearest of the original 8 ANSI colors
+    /// (no "bright" variants), for terminals that support color but not the
+    /// basic-16 tier.
+    pub fn to_basic8(self) -> Color {
+        Self::nearest_basic(self, &BASIC16_COLORS[..8])
+    }
+
+    /// Shared implementation of [`Color::to_basic16`]/[`Color::to_basic8`]:
+    /// matches this color against whichever slice of [`BASIC16_COLORS`]
+    /// (and the corresponding prefix of [`BASIC16_RGB`]) the caller passes,
+    /// by squared RGB distance.
+    fn nearest_basic(self, candidates: &[Color]) -> Color {
+        let (r, g, b) = match self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            Color::Fixed(n) if n as usize >= candidates.len() => fixed_to_rgb(n),
+            Color::Fixed(_) | Color::Default => return self,
+            named if named.basic_palette_index() as usize >= candidates.len() => {
+                fixed_to_rgb(named.basic_palette_index())
+            }
+            other => return other,
+        };
+
+        candidates
+            .iter()
+            .enumerate()
+            .min_by_key(|&(i, _)| squared_distance((r, g, b), BASIC16_RGB[i]))
+            .map(|(_, &color)| color)
+            .unwrap_or(self)
+    }
+
+    /// Degrades this color to fit within `level`'s color support, via
+    /// [`Color::to_fixed`]/[`Color::to_basic16`]/[`Color::to_basic8`] as
+    /// needed. Already-fitting colors, and anything under
+    /// [`ColorLevel::TrueColor`], pass through unchanged.
+    pub fn downgrade(self, level: ColorLevel) -> Color {
+        match level {
+            ColorLevel::TrueColor => self,
+            ColorLevel::Ansi256 => self.to_fixed(),
+            ColorLevel::Ansi16 => self.to_basic16(),
+            ColorLevel::Ansi8 => self.to_basic8(),
+            ColorLevel::None => Color::Default,
+        }
+    }
+
+    /// Nudges this color's perceptual lightness (HSL `L`, 0.0 = black, 1.0 =
+    /// white) toward `target`, for keeping theme colors legible against a
+    /// detected terminal background. Colors already on the correct side of
+    /// `target` are left untouched, rather than snapped exactly to it, so a
+    /// color that's already dark enough for a light background (or light
+    /// enough for a dark one) isn't needlessly flattened: pass a low
+    /// `target` (e.g. ~0.4) to raise a floor under colors that are too dark,
+    /// or a high `target` (e.g. ~0.6) to cap colors that are too light.
+    /// Named/indexed colors are expanded to RGB first via [`fixed_to_rgb`]
+    /// and returned as `Rgb`.
+    pub fn with_lightness(self, target: f32) -> Color {
+        let (r, g, b) = match self {
+            Color::Default => return self,
+            other => other.to_rgb_triple(),
+        };
+
+        let (h, s, l) = rgb_to_hsl(r, g, b);
+        let target = target.clamp(0.0, 1.0);
+        let adjusted = if target >= 0.5 { l.min(target) } else { l.max(target) };
+        if adjusted == l {
+            return self;
+        }
+
+        let (r, g, b) = hsl_to_rgb(h, s, adjusted);
+        Color::Rgb(r, g, b)
+    }
+
+    /// Resolves this color to its approximate RGB value, regardless of how
+    /// it was originally specified -- named, `Fixed`, or `Rgb` -- for code
+    /// that needs to do arithmetic on colors (interpolation, lightness
+    /// adjustment) rather than just emit them as escape codes.
+    /// [`Color::Default`] has no real color to report, so it resolves to
+    /// black.
+    pub(crate) fn to_rgb_triple(self) -> (u8, u8, u8) {
+        match self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            Color::Fixed(n) => fixed_to_rgb(n),
+            Color::Default => (0, 0, 0),
+            named => fixed_to_rgb(named.basic_palette_index()),
+        }
+    }
+}
+
+/// Converts an RGB triple to HSL (`h`/`s`/`l` each in `0.0..=1.0`), the
+/// inverse of [`hsl_to_rgb`].
+fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+
+    if max == min {
+        return (0.0, 0.0, l);
+    }
+
+    let delta = max - min;
+    let s = if l > 0.5 {
+        delta / (2.0 - max - min)
+    } else {
+        delta / (max + min)
+    };
+    let h = if max == r {
+        (g - b) / delta + if g < b { 6.0 } else { 0.0 }
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+
+    (h / 6.0, s, l)
+}
+
+/// Converts an HSL triple back to RGB, the inverse of [`rgb_to_hsl`].
+fn hsl_to_rgb(h: f32, s: f32, l: f32) -> (u8, u8, u8) {
+    if s == 0.0 {
+        let v = (l * 255.0).round() as u8;
+        return (v, v, v);
+    }
+
+    let q = if l < 0.5 { l * (1.0 + s) } else { l + s - l * s };
+    let p = 2.0 * l - q;
+    let hue_to_channel = |t: f32| -> f32 {
+        let t = t.rem_euclid(1.0);
+        if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 1.0 / 2.0 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        }
+    };
+
+    (
+        (hue_to_channel(h + 1.0 / 3.0) * 255.0).round() as u8,
+        (hue_to_channel(h) * 255.0).round() as u8,
+        (hue_to_channel(h - 1.0 / 3.0) * 255.0).round() as u8,
+    )
+}
+
+/// How many colors a terminal can display, used by [`Color::downgrade`] to
+/// pick which conversion (if any) to apply before a `Color`/`Style` is
+/// written out as escape codes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "derive_serde_style",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub enum ColorLevel {
+    /// No color support; styling should typically be skipped entirely.
+    None,
+
+    /// The original 8 ANSI colors, with no "bright" variants.
+    Ansi8,
+
+    /// The basic 16-color ANSI palette.
+    Ansi16,
+
+    /// The xterm 256-color palette.
+    Ansi256,
+
+    /// 24-bit truecolor.
+    #[default]
+    TrueColor,
+}
+
 impl From<Color> for Style {
     /// You can turn a `Color` into a `Style` with the foreground color set
     /// with the `From` trait.
@@ -3,7 +3,7 @@ use std::error::Error as StdError;
 use std::ffi::OsStr;
 use std::fmt::{self, Display, Write};
 use std::fs::{self, File};
-use std::io::{self, Read};
+use std::io::{self, IsTerminal, Read};
 use std::path::{Component, Path, PathBuf};
 
 use flate2::bufread::GzDecoder;
@@ -15,9 +15,9 @@ use crate::error::*;
 #[cfg(feature = "guesslang")]
 use crate::guesslang::GuessLang;
 use crate::input::{InputReader, OpenedInput};
-use crate::syntax_mapping::MappingTarget;
+use crate::syntax_mapping::{extract_modeline_filetype, MappingTarget};
 #[cfg(feature = "zero-copy")]
-use crate::zero_copy::{create_file_mapped_leaky_slice, create_leaky_slice, LeakySliceReader};
+use crate::input::zero_copy::{create_file_mapped_leaky_slice, create_leaky_slice, LeakySliceReader};
 use crate::SyntaxMapping;
 
 #[cfg(feature = "build-assets")]
@@ -85,6 +85,30 @@ impl Display for SyntaxUndetected {
 
 impl StdError for SyntaxUndetected {}
 
+/// Describes *why* [`HighlightingAssets::detect_syntax`] chose the syntax it
+/// did, for tooling built on top of bat that wants to debug a misdetection
+/// rather than just seeing the resulting syntax name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxDetectionProvenance {
+    /// An explicit `--language`/`language()` override.
+    Explicit,
+    /// An explicit [`SyntaxMapping`] rule matched the path, e.g. a
+    /// `map-syntax` config entry or a builtin glob like `*.conf`.
+    MappingRule { pattern: String },
+    /// The file name (e.g. `Dockerfile`) or its extension (e.g. `.rs`)
+    /// matched a syntax directly, with no explicit mapping rule involved.
+    Extension,
+    /// The first line of the file (a shebang, an editor modeline, or a
+    /// syntax's own first-line regex) determined the syntax.
+    FirstLine,
+    /// [`SyntaxMapping::resolve_by_content_sniff`]'s multi-line
+    /// content-signature heuristics determined the syntax.
+    ContentSniff,
+    /// The guesslang ML model determined the syntax.
+    #[cfg(feature = "guesslang")]
+    Guesslang,
+}
+
 #[derive(Debug)]
 pub struct UnknownTheme {
     pub name: String,
@@ -140,32 +164,59 @@ impl HighlightingAssets {
 
     /// The default theme.
     ///
-    /// ### Windows and Linux
+    /// On Unix-like platforms, when the terminal answers the OSC 11 "query
+    /// background color" escape sequence, the reply is used to pick a theme
+    /// that looks good against that background. This covers most terminal
+    /// emulators on Linux as well as macOS. On other platforms (including
+    /// Windows) the terminal is not queried and the fallback below applies.
     ///
-    /// Windows and most Linux distributions has a dark terminal theme by
-    /// default. On these platforms, this function always returns a theme that
-    /// looks good on a dark background.
+    /// ### Fallback
     ///
-    /// ### macOS
-    ///
-    /// On macOS the default terminal background is light, but it is common that
-    /// Dark Mode is active, which makes the terminal background dark. On this
-    /// platform, the default theme depends on
+    /// If the terminal doesn't answer (or isn't a terminal at all, e.g.
+    /// when piping output), this falls back to the previous behavior: a
+    /// hardcoded dark theme everywhere except macOS, where
     /// ```bash
     /// defaults read -globalDomain AppleInterfaceStyle
     /// ```
-    /// To avoid the overhead of the check on macOS, simply specify a theme
-    /// explicitly via `--theme`, `BAT_THEME`, or `~/.config/bat`.
+    /// is used to detect Dark Mode instead.
+    ///
+    /// To avoid the overhead of either check, specify a theme explicitly via
+    /// `--theme`, `BAT_THEME`, or `~/.config/bat`, or use
+    /// [`get_default_theme_without_terminal_query`](Self::get_default_theme_without_terminal_query).
     ///
     /// See <https://github.com/sharkdp/bat/issues/1746> and
     /// <https://github.com/sharkdp/bat/issues/1928> for more context.
     pub fn get_default_theme(&self) -> &Theme {
+        self.get_default_theme_impl(true)
+    }
+
+    /// Like [`get_default_theme`](Self::get_default_theme), but never probes
+    /// the terminal's background color via OSC 11, going straight to the
+    /// platform-specific fallback. Useful for callers that already know the
+    /// answer, or that don't want to pay for the round-trip to the TTY.
+    pub fn get_default_theme_without_terminal_query(&self) -> &Theme {
+        self.get_default_theme_impl(false)
+    }
+
+    fn get_default_theme_impl(&self, query_terminal: bool) -> &Theme {
         let default_dark_theme = "Monokai Extended";
         let default_light_theme = "Monokai Extended Light";
-        #[cfg(not(target_os = "macos"))]
-        let name = default_dark_theme;
-        #[cfg(target_os = "macos")]
-        let name = if macos_dark_mode_active() {
+
+        let is_dark = query_terminal
+            .then(query_terminal_background)
+            .flatten()
+            .unwrap_or_else(|| {
+                #[cfg(target_os = "macos")]
+                {
+                    macos_dark_mode_active()
+                }
+                #[cfg(not(target_os = "macos"))]
+                {
+                    true
+                }
+            });
+
+        let name = if is_dark {
             default_dark_theme
         } else {
             default_light_theme
@@ -190,6 +241,12 @@ impl HighlightingAssets {
         self.theme_set.themes()
     }
 
+    /// Loads user-provided themes from `dirs` on top of the embedded ones.
+    /// See [`LazyThemeSet::load_user_themes`].
+    pub fn load_user_themes<P: AsRef<Path>>(&mut self, dirs: impl IntoIterator<Item = P>) {
+        self.theme_set.load_user_themes(dirs);
+    }
+
     /// Detect the syntax based on, in order:
     ///  1. Syntax mappings with [MappingTarget::MapTo] and [MappingTarget::MapToUnknown]
     ///     (e.g. `/etc/profile` -> `Bourne Again Shell (bash)`)
@@ -217,6 +274,31 @@ impl HighlightingAssets {
         path: impl AsRef<Path>,
         mapping: &SyntaxMapping,
     ) -> Result<SyntaxReferenceInSet> {
+        self.get_syntax_for_path_impl(path, mapping, None)
+            .map(|(sr, _)| sr)
+    }
+
+    /// Like [`Self::get_syntax_for_path`], but additionally consults
+    /// `mapping`'s first-line rules (shebangs, editor modelines, and
+    /// user-configured first-line regexes) when the path/extension mapping
+    /// defers to content (`MapToUnknown`/`MapExtensionToUnknown`) or doesn't
+    /// match at all. `first_line` is the raw bytes of the file's first line.
+    pub fn get_syntax_for_path_with_first_line(
+        &self,
+        path: impl AsRef<Path>,
+        first_line: &[u8],
+        mapping: &SyntaxMapping,
+    ) -> Result<SyntaxReferenceInSet> {
+        self.get_syntax_for_path_impl(path, mapping, Some(first_line))
+            .map(|(sr, _)| sr)
+    }
+
+    fn get_syntax_for_path_impl(
+        &self,
+        path: impl AsRef<Path>,
+        mapping: &SyntaxMapping,
+        first_line: Option<&[u8]>,
+    ) -> Result<(SyntaxReferenceInSet, SyntaxDetectionProvenance)> {
         let path = path.as_ref();
         let undetected = || {
             SyntaxUndetected {
@@ -224,32 +306,56 @@ impl HighlightingAssets {
             }
             .into()
         };
-        let path: PathBuf = mapping
-            .strip_ignored_suffixes(absolute_path(path)?.into())
-            .into();
-        let syntax_match = mapping.get_syntax_for(&path);
-        match syntax_match {
-            Some(MappingTarget::MapToUnknown) => Err(undetected()),
-            Some(MappingTarget::MapTo(syntax_name)) => {
-                self.find_syntax_by_name(syntax_name).ok_or_else(|| {
+        // Last-resort fallback: the configured first-line rules, tried only
+        // once path/extension-based detection has given up.
+        let by_first_line = || {
+            first_line
+                .and_then(|first_line| mapping.resolve_unknown(first_line))
+                .and_then(|target| match target {
+                    MappingTarget::MapTo(syntax_name) => self.find_syntax_by_name(syntax_name),
+                    MappingTarget::MapToUnknown | MappingTarget::MapExtensionToUnknown => None,
+                })
+                .map(|sr| (sr, SyntaxDetectionProvenance::FirstLine))
+        };
+        let absolute_path = absolute_path(path)?;
+        // `explain_syntax_for` is used instead of `get_syntax_for` so the
+        // specific rule (if any) that decided the mapping is available for
+        // `SyntaxDetectionProvenance::MappingRule`.
+        let resolution = mapping.explain_syntax_for(&absolute_path);
+        let mapping_rule_pattern = resolution.matched_rules.last().map(|rule| rule.pattern.clone());
+        let path: PathBuf = mapping.strip_ignored_suffixes(absolute_path.into()).into();
+        match resolution.target {
+            Some(MappingTarget::MapToUnknown) => by_first_line().ok_or_else(undetected),
+            Some(MappingTarget::MapTo(syntax_name)) => self
+                .find_syntax_by_name(syntax_name)
+                .map(|sr| {
+                    (
+                        sr,
+                        SyntaxDetectionProvenance::MappingRule {
+                            pattern: mapping_rule_pattern.unwrap_or_default(),
+                        },
+                    )
+                })
+                .ok_or_else(|| {
                     UnknownSyntax {
                         name: syntax_name.to_owned(),
                     }
                     .into()
-                })
-            }
+                }),
             _ => {
                 if let Some(sr) = path
                     .file_name()
                     .and_then(|name| self.find_syntax_by_extension(name))
                 {
-                    Ok(sr)
-                } else if let Some(MappingTarget::MapExtensionToUnknown) = syntax_match {
-                    Err(undetected())
+                    Ok((sr, SyntaxDetectionProvenance::Extension))
+                } else if let Some(MappingTarget::MapExtensionToUnknown) = resolution.target {
+                    by_first_line().ok_or_else(undetected)
                 } else {
                     path.extension()
                         .and_then(|name| self.find_syntax_by_extension(name))
-                        .ok_or(undetected())
+                        .map(|sr| (sr, SyntaxDetectionProvenance::Extension))
+                        .or_else(by_first_line)
+                        .ok_or_else(undetected)
                 }
             }
         }
@@ -270,13 +376,32 @@ impl HighlightingAssets {
         input: &mut OpenedInput,
         mapping: &SyntaxMapping,
     ) -> Result<SyntaxReferenceInSet> {
+        self.detect_syntax(language, input, mapping)
+            .map(|(sr, _)| sr)
+    }
+
+    /// Like [`Self::get_syntax`], but additionally returns a
+    /// [`SyntaxDetectionProvenance`] describing *why* that syntax was
+    /// chosen, for tooling that wants to debug a misdetection rather than
+    /// just see the resulting syntax name.
+    pub(crate) fn detect_syntax(
+        &self,
+        language: Option<&str>,
+        input: &mut OpenedInput,
+        mapping: &SyntaxMapping,
+    ) -> Result<(SyntaxReferenceInSet, SyntaxDetectionProvenance)> {
         if let Some(language) = language {
             return self
                 .syntax_set
                 .find_syntax_by_token(language)
-                .map(|syntax| SyntaxReferenceInSet {
-                    syntax,
-                    syntax_set: &self.syntax_set,
+                .map(|syntax| {
+                    (
+                        SyntaxReferenceInSet {
+                            syntax,
+                            syntax_set: &self.syntax_set,
+                        },
+                        SyntaxDetectionProvenance::Explicit,
+                    )
                 })
                 .ok_or_else(|| {
                     UnknownSyntax {
@@ -288,7 +413,13 @@ impl HighlightingAssets {
 
         let path = input.path();
         let path_syntax = if let Some(path) = path {
-            self.get_syntax_for_path(path, mapping)
+            match input.reader.first_read.as_deref() {
+                Some(first_read) => {
+                    let first_line = first_read.split_inclusive('\n').next().unwrap_or(first_read);
+                    self.get_syntax_for_path_impl(path, mapping, Some(first_line.as_bytes()))
+                }
+                None => self.get_syntax_for_path_impl(path, mapping, None),
+            }
         } else {
             Err(SyntaxUndetected {
                 path: "UNKNOWN".into(),
@@ -303,11 +434,14 @@ impl HighlightingAssets {
             .is_some()
         {
             if let Some(sr) = self.get_first_line_syntax(&mut input.reader)? {
-                return Ok(sr);
+                return Ok((sr, SyntaxDetectionProvenance::FirstLine));
+            }
+            if let Some(sr) = self.get_syntax_by_content_sniff(&mut input.reader, mapping)? {
+                return Ok((sr, SyntaxDetectionProvenance::ContentSniff));
             }
             #[cfg(feature = "guesslang")]
             if let Some(sr) = self.get_syntax_by_guesslang(&mut input.reader)? {
-                return Ok(sr);
+                return Ok((sr, SyntaxDetectionProvenance::Guesslang));
             }
         }
 
@@ -345,6 +479,15 @@ impl HighlightingAssets {
         &self,
         reader: &mut InputReader,
     ) -> Result<Option<SyntaxReferenceInSet>> {
+        if let Some(token) = reader.first_read.as_deref().and_then(scan_modeline) {
+            if let Some(syntax) = self.syntax_set.find_syntax_by_token(token) {
+                return Ok(Some(SyntaxReferenceInSet {
+                    syntax,
+                    syntax_set: &self.syntax_set,
+                }));
+            }
+        }
+
         Ok(reader
             .first_read
             .as_ref()
@@ -356,6 +499,25 @@ impl HighlightingAssets {
             }))
     }
 
+    /// Last-resort fallback beyond [`Self::get_first_line_syntax`]: scores
+    /// the buffered content against `mapping`'s content-signature heuristics
+    /// (see [`SyntaxMapping::resolve_by_content_sniff`]) and resolves the
+    /// best-scoring syntax, if any.
+    fn get_syntax_by_content_sniff(
+        &self,
+        reader: &mut InputReader,
+        mapping: &SyntaxMapping,
+    ) -> Result<Option<SyntaxReferenceInSet>> {
+        Ok(reader
+            .first_read
+            .as_deref()
+            .and_then(|content| mapping.resolve_by_content_sniff(content))
+            .and_then(|target| match target {
+                MappingTarget::MapTo(syntax_name) => self.find_syntax_by_name(syntax_name),
+                MappingTarget::MapToUnknown | MappingTarget::MapExtensionToUnknown => None,
+            }))
+    }
+
     #[cfg(feature = "guesslang")]
     fn get_syntax_by_guesslang(
         &self,
@@ -371,6 +533,36 @@ impl HighlightingAssets {
                 syntax_set: &self.syntax_set,
             }))
     }
+
+    /// Guesses syntaxes for a sample of text using the guesslang model,
+    /// returning every candidate the model considered that resolves to a
+    /// known syntax, together with the model's raw confidence in it (in
+    /// `[0, 1]`). The list is ordered by confidence, highest first.
+    ///
+    /// This exposes the full ranked distribution behind [`Self::get_syntax`]'s
+    /// internal single-best-guess fallback, for callers (editors, pagers,
+    /// language servers) that want to apply their own confidence threshold
+    /// or drive their own disambiguation UI.
+    #[cfg(feature = "guesslang")]
+    pub fn guess_syntax(&self, text: impl Into<String>) -> Vec<(SyntaxReferenceInSet, f32)> {
+        self.guesslang
+            .guess_ranked(text.into())
+            .into_iter()
+            .filter_map(|candidate| {
+                self.syntax_set
+                    .find_syntax_by_token(candidate.token)
+                    .map(|syntax| {
+                        (
+                            SyntaxReferenceInSet {
+                                syntax,
+                                syntax_set: &self.syntax_set,
+                            },
+                            candidate.confidence,
+                        )
+                    })
+            })
+            .collect()
+    }
 }
 
 pub fn get_acknowledgements() -> String {
@@ -393,6 +585,80 @@ fn macos_dark_mode_active() -> bool {
     is_dark
 }
 
+/// Asks the controlling terminal for its background color via the OSC 11
+/// escape sequence (`ESC ] 11 ; ? BEL`) and returns whether it looks dark,
+/// or `None` if the terminal didn't answer in time, isn't a terminal, or
+/// this platform has no way to ask.
+#[cfg(unix)]
+pub(crate) fn query_terminal_background() -> Option<bool> {
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+
+    use termios::{Termios, ECHO, ICANON, TCSANOW, VMIN, VTIME};
+
+    if !(io::stdout().is_terminal() || io::stderr().is_terminal()) {
+        return None;
+    }
+
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let fd = tty.as_raw_fd();
+    let original = Termios::from_fd(fd).ok()?;
+    let mut raw = original;
+    raw.c_lflag &= !(ICANON | ECHO);
+    raw.c_cc[VMIN] = 0;
+    raw.c_cc[VTIME] = 2; // deciseconds, i.e. ~200ms
+    termios::tcsetattr(fd, TCSANOW, &raw).ok()?;
+
+    let reply = tty
+        .write_all(b"\x1b]11;?\x07")
+        .and_then(|_| tty.flush())
+        .ok()
+        .and_then(|_| {
+            let mut buf = [0u8; 32];
+            let n = tty.read(&mut buf).ok()?;
+            Some(buf[..n].to_vec())
+        });
+
+    let _ = termios::tcsetattr(fd, TCSANOW, &original);
+
+    parse_osc11_reply(&reply?)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn query_terminal_background() -> Option<bool> {
+    None
+}
+
+/// Parses an OSC 11 reply of the form `rgb:RRRR/GGGG/BBBB` (some terminals
+/// use fewer hex digits per channel) and returns whether the color is dark,
+/// based on perceived (ITU-R BT.601) luminance.
+#[cfg(unix)]
+fn parse_osc11_reply(reply: &[u8]) -> Option<bool> {
+    let reply = std::str::from_utf8(reply).ok()?;
+    let rgb = reply.split("rgb:").nth(1)?;
+    let rgb = rgb.trim_end_matches(['\u{7}', '\\', '\u{1b}']);
+
+    let mut channels = rgb.split('/');
+    let mut next_channel = || -> Option<f64> {
+        let s = channels.next()?;
+        let s = &s[..s.len().min(2)];
+        let value = u8::from_str_radix(s, 16).ok()?;
+        // A single hex digit spans the whole 0..=255 range on its own.
+        Some(f64::from(if s.len() == 1 { value * 17 } else { value }))
+    };
+    let r = next_channel()?;
+    let g = next_channel()?;
+    let b = next_channel()?;
+
+    let luminance = 0.299 * r + 0.587 * g + 0.114 * b;
+    Some(luminance < 128.0)
+}
+
 fn load_asset_bytes(
     asset_path: impl AsRef<Path>,
     data: &[u8],
@@ -497,6 +763,52 @@ fn absolute_path(path: impl AsRef<Path>) -> io::Result<PathBuf> {
     pathbuf.map_or_else(env::current_dir, Ok)
 }
 
+/// How many bytes from the start and end of a first read `scan_modeline`
+/// will search, so a huge first read (e.g. a dense minified file with no
+/// newlines) can't make every lookup pay for a large regex scan.
+const MODELINE_SCAN_BYTES: usize = 4096;
+
+/// How many lines from the start and end of a first read `scan_modeline`
+/// will search.
+const MODELINE_SCAN_LINES: usize = 5;
+
+/// Looks for a Vim/Emacs editor modeline in the first or last few lines of
+/// `content`, tolerating leading comment markers (`//`, `#`, `;`, ...) since
+/// the modeline patterns aren't anchored to the start of the line. Returns
+/// the declared filetype/mode token, if any.
+fn scan_modeline(content: &str) -> Option<&str> {
+    extract_modeline_filetype(head_lines(content, MODELINE_SCAN_LINES, MODELINE_SCAN_BYTES))
+        .or_else(|| extract_modeline_filetype(tail_lines(content, MODELINE_SCAN_LINES, MODELINE_SCAN_BYTES)))
+}
+
+/// The first `max_lines` lines of `content`, capped to `content`'s first
+/// `max_bytes` bytes.
+fn head_lines(content: &str, max_lines: usize, max_bytes: usize) -> &str {
+    let mut end = content.len().min(max_bytes);
+    while end > 0 && !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    let head = &content[..end];
+    match head.match_indices('\n').nth(max_lines.saturating_sub(1)) {
+        Some((i, _)) => &head[..i],
+        None => head,
+    }
+}
+
+/// The last `max_lines` lines of `content`, capped to `content`'s last
+/// `max_bytes` bytes.
+fn tail_lines(content: &str, max_lines: usize, max_bytes: usize) -> &str {
+    let mut start = content.len().saturating_sub(max_bytes);
+    while start < content.len() && !content.is_char_boundary(start) {
+        start += 1;
+    }
+    let tail = &content[start..];
+    match tail.rmatch_indices('\n').nth(max_lines.saturating_sub(1)) {
+        Some((i, _)) => &tail[i + 1..],
+        None => tail,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -548,7 +860,7 @@ mod tests {
             }
 
             let input = Input::from_file(&file_path);
-            let mut opened_input = input.open(None).unwrap();
+            let mut opened_input = input.open(None, None).unwrap();
 
             self.get_syntax_name(None, &mut opened_input, &self.syntax_mapping)
         }
@@ -557,7 +869,7 @@ mod tests {
             let file_path = self.temp_dir.path().join(file_name);
             let mut input = Input::from_reader(io::Cursor::new(Vec::from(first_line.as_bytes())));
             input.description.name = Some(OsString::from(file_path));
-            let mut opened_input = input.open(None).unwrap();
+            let mut opened_input = input.open(None, None).unwrap();
 
             self.get_syntax_name(None, &mut opened_input, &self.syntax_mapping)
         }
@@ -575,11 +887,54 @@ mod tests {
             self.syntax_for_file_with_content(file_name, "")
         }
 
+        /// Like [`Self::syntax_for_file_with_content`], but for content
+        /// whose syntax can only be determined by
+        /// [`SyntaxMapping::resolve_by_content_sniff`]: the file name has no
+        /// extension and isn't otherwise mapped, so path/extension and
+        /// first-line detection both defer to it.
+        fn syntax_for_content(&self, content: &str) -> String {
+            self.syntax_for_file_with_content("unnamed_file_without_extension", content)
+        }
+
+        /// Like [`Self::syntax_for_file_with_content`], but returns the
+        /// [`SyntaxDetectionProvenance`] for `mapping` instead of the
+        /// resulting syntax's name.
+        fn provenance_for_file_with_content_and_mapping(
+            &self,
+            file_name: &str,
+            first_line: &str,
+            mapping: &SyntaxMapping,
+        ) -> Option<SyntaxDetectionProvenance> {
+            let file_path = self.temp_dir.path().join(file_name);
+            let mut input = Input::from_reader(io::Cursor::new(Vec::from(first_line.as_bytes())));
+            input.description.name = Some(OsString::from(file_path));
+            let mut opened_input = input.open(None, None).unwrap();
+
+            self.assets
+                .detect_syntax(None, &mut opened_input, mapping)
+                .ok()
+                .map(|(_, provenance)| provenance)
+        }
+
+        /// Like [`Self::provenance_for_file_with_content_and_mapping`], using
+        /// `self.syntax_mapping`.
+        fn provenance_for_file_with_content(
+            &self,
+            file_name: &str,
+            first_line: &str,
+        ) -> Option<SyntaxDetectionProvenance> {
+            self.provenance_for_file_with_content_and_mapping(
+                file_name,
+                first_line,
+                &self.syntax_mapping,
+            )
+        }
+
         fn syntax_for_stdin_with_content(&self, file_name: &str, content: &[u8]) -> String {
             let mut input = Input::from_stdin();
             input.description.name = Some(OsString::from(file_name));
-            let mut opened_input = input.open(None).unwrap();
-            opened_input.reader = InputReader::new(io::Cursor::new(Vec::from(content)));
+            let mut opened_input = input.open(None, None).unwrap();
+            opened_input.reader = InputReader::new(io::Cursor::new(Vec::from(content)), None);
 
             self.get_syntax_name(None, &mut opened_input, &self.syntax_mapping)
         }
@@ -616,6 +971,48 @@ mod tests {
         assert_eq!(test.syntax_for_file("Makefile"), "Makefile");
     }
 
+    #[test]
+    fn syntax_detection_provenance() {
+        use crate::syntax_mapping::SyntaxMappingBuilder;
+
+        let test = SyntaxDetectionTest::new();
+
+        // `README.MD` is recognized via syntect's own case-insensitive
+        // extension lookup, not any explicit mapping rule -- there's no
+        // `*.MD`/`*.md` glob in the builtin mapping.
+        assert_eq!(
+            test.provenance_for_file_with_content("README.MD", ""),
+            Some(SyntaxDetectionProvenance::Extension)
+        );
+
+        assert_eq!(
+            test.provenance_for_file_with_content("my_script", "#!/bin/bash"),
+            Some(SyntaxDetectionProvenance::FirstLine)
+        );
+
+        assert_eq!(
+            test.provenance_for_file_with_content(
+                "unnamed_file_without_extension",
+                "---\nname: bat\nversion: 1.0\n"
+            ),
+            Some(SyntaxDetectionProvenance::ContentSniff)
+        );
+
+        // An explicit mapping rule reports which pattern matched.
+        let mapping = SyntaxMappingBuilder::new()
+            .with_builtin()
+            .map_syntax("*.myext", MappingTarget::MapTo("C"))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(
+            test.provenance_for_file_with_content_and_mapping("test.myext", "", &mapping),
+            Some(SyntaxDetectionProvenance::MappingRule {
+                pattern: "*.myext".to_owned()
+            })
+        );
+    }
+
     #[cfg(unix)]
     #[test]
     fn syntax_detection_invalid_utf8() {
@@ -747,6 +1144,30 @@ mod tests {
         assert_eq!(test.syntax_for_file("README.MD"), "Markdown");
     }
 
+    #[test]
+    fn syntax_detection_by_content_sniff() {
+        let test = SyntaxDetectionTest::new();
+
+        assert_eq!(
+            test.syntax_for_content("<?xml version=\"1.0\"?>\n<root/>\n"),
+            "XML"
+        );
+        assert_eq!(
+            test.syntax_for_content("---\nname: bat\nversion: 1.0\n"),
+            "YAML"
+        );
+        assert_eq!(
+            test.syntax_for_content("[package]\nname = \"bat\"\nversion = \"1.0\"\n"),
+            "TOML"
+        );
+        // Not structured enough for any content signature to clear the
+        // threshold; syntax stays undetected.
+        assert_eq!(
+            test.syntax_for_content("just some plain prose, nothing special"),
+            "!no syntax!"
+        );
+    }
+
     #[ignore]
     #[test]
     fn syntax_detection_stdin_filename() {
@@ -778,7 +1199,7 @@ mod tests {
         symlink(&file_path, &file_path_symlink).expect("creation of symbolic link succeeds");
 
         let input = Input::from_file(&file_path_symlink);
-        let mut opened_input = input.open(None).unwrap();
+        let mut opened_input = input.open(None, None).unwrap();
 
         assert_eq!(
             test.get_syntax_name(None, &mut opened_input, &test.syntax_mapping),
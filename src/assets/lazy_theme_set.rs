@@ -2,16 +2,25 @@
 use zwrite::{write, writeln};
 
 use std::cell::RefCell;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
 
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use flate2::bufread::GzDecoder as GzReader;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use once_cell::unsync::OnceCell;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_bytes::{ByteBuf, Bytes};
-use syntect::highlighting::Theme;
+use syntect::highlighting::{Theme, ThemeSet};
 
 use super::asset_from_bytes;
-use crate::error::Result;
+use crate::error::{Context, Result};
 
 /// Same structure as a [`syntect::highlighting::ThemeSet`] but with themes
 /// stored in raw serialized form, and deserialized on demand.
@@ -33,28 +42,244 @@ struct LazyTheme {
 
     #[serde(skip, default = "OnceCell::new")]
     deserialized: OnceCell<syntect::highlighting::Theme>,
+
+    /// The name of a parent theme to inherit unset settings and scope rules
+    /// from, if this theme declared one (see [`LazyThemeSet::load_user_themes`]).
+    #[serde(default)]
+    parent: Option<String>,
+
+    /// Whether this theme was loaded from a user theme directory at runtime
+    /// rather than baked in at build time. Embedded themes use the strict
+    /// load path (a deserialization failure is a hard error); user themes
+    /// use the tolerant path, where a failure falls back to
+    /// [`Theme::default`] and is logged instead of losing the theme.
+    #[serde(default)]
+    tolerant: bool,
 }
 
 impl LazyThemeSet {
-    /// Lazily load the given theme
+    /// Lazily load the given theme, resolving any parent theme chain it
+    /// declared (see [`Self::load_user_themes`]).
     pub fn get(&self, name: &str) -> Option<&Theme> {
-        self.themes
-            .get(name)
-            .map(|lazy_theme| lazy_theme.deserialize().unwrap())
+        self.resolve(name, &mut HashSet::new())
+    }
+
+    fn resolve<'a>(&'a self, name: &str, visiting: &mut HashSet<String>) -> Option<&'a Theme> {
+        // A name already being resolved further up the call chain means a
+        // cycle; treat it as if this link in the chain had no parent rather
+        // than recursing forever.
+        if !visiting.insert(name.to_owned()) {
+            return None;
+        }
+        let lazy_theme = self.themes.get(name)?;
+        let parent = lazy_theme
+            .parent
+            .as_deref()
+            .and_then(|parent_name| self.resolve(parent_name, visiting));
+        lazy_theme.deserialize(parent).ok()
     }
 
     /// Returns the name of all themes.
     pub fn themes(&self) -> impl Iterator<Item = &str> {
         self.themes.keys().map(String::as_str)
     }
+
+    /// Scans `dirs` for `.tmTheme`/`.sublime-color-scheme` theme files and
+    /// merges them in, so they show up through [`Self::get`]/[`Self::themes`]
+    /// alongside the embedded ones. `dirs` is given in descending priority: a
+    /// theme name found in an earlier directory shadows the same name found
+    /// in a later one, as well as any embedded theme of that name. Missing
+    /// directories are silently skipped, but a theme file that fails to
+    /// parse is not dropped: it's kept under its file name with default
+    /// settings and a warning is logged, so one broken user theme never
+    /// hides the rest of the list.
+    pub fn load_user_themes<P: AsRef<Path>>(&mut self, dirs: impl IntoIterator<Item = P>) {
+        // Load lowest-priority directories first, so a higher-priority
+        // directory's insert overwrites same-named entries from it and from
+        // the embedded defaults already in `self.themes`.
+        for dir in dirs.into_iter().collect::<Vec<_>>().into_iter().rev() {
+            self.load_user_theme_dir(dir.as_ref());
+        }
+    }
+
+    /// Exports `name` as a compact, shareable token: the resolved theme is
+    /// bincode-serialized, gzip-compressed, and base64-encoded, so it can be
+    /// pasted into a config file or an issue without distributing a
+    /// `.tmTheme` file. Returns `None` if no theme named `name` exists.
+    pub fn export_theme(&self, name: &str) -> Option<Result<String>> {
+        let theme = self.get(name)?;
+        Some((|| {
+            let serialized = bincode::serialize(theme)?;
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&serialized)?;
+            Ok(BASE64.encode(encoder.finish()?))
+        })())
+    }
+
+    /// Imports a theme previously exported with [`Self::export_theme`],
+    /// inserting it under `name` so it's immediately available through
+    /// [`Self::get`]/[`Self::themes`], the same as any other runtime-loaded
+    /// theme (it gets the tolerant load path, see [`LazyTheme::tolerant`]).
+    pub fn import_theme(&mut self, name: &str, token: &str) -> Result<()> {
+        let compressed = BASE64.decode(token).context("invalid theme token")?;
+        let mut serialized = Vec::new();
+        GzReader::new(compressed.as_slice())
+            .read_to_end(&mut serialized)
+            .context("invalid theme token")?;
+        let theme: Theme = bincode::deserialize(&serialized).context("invalid theme token")?;
+        let lazy_theme = LazyTheme::from_theme(&theme, None)?;
+        self.themes.insert(name.to_owned(), lazy_theme);
+        Ok(())
+    }
+
+    fn load_user_theme_dir(&mut self, dir: &Path) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_theme_file = matches!(
+                path.extension().and_then(OsStr::to_str),
+                Some("tmTheme" | "sublime-color-scheme")
+            );
+            if !is_theme_file {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(OsStr::to_str) else {
+                continue;
+            };
+            let theme = match ThemeSet::get_theme(&path) {
+                Ok(theme) => theme,
+                Err(err) => {
+                    eprintln!(
+                        "[bat warning] theme '{name}' ({}) failed to load, falling back to default settings: {err}",
+                        path.display()
+                    );
+                    Theme::default()
+                }
+            };
+            let parent = fs::read_to_string(&path)
+                .ok()
+                .as_deref()
+                .and_then(extract_inherits);
+            let Ok(lazy_theme) = LazyTheme::from_theme(&theme, parent) else {
+                continue;
+            };
+            self.themes.insert(name.to_owned(), lazy_theme);
+        }
+    }
 }
 
 impl LazyTheme {
-    fn deserialize(&self) -> Result<&Theme> {
+    /// Deserializes this theme, merging in `parent` (already resolved by the
+    /// caller) if this theme declared one: any [`syntect::highlighting::ThemeSettings`]
+    /// field this theme left unset is filled from `parent`, and `parent`'s
+    /// scope rules are appended after this theme's own so this theme's rules
+    /// win on equal specificity. Only ever merged once, the first time the
+    /// theme is deserialized; the merged result is what's cached.
+    ///
+    /// Embedded themes take the strict path: a deserialization failure is
+    /// propagated as an error. [`Self::tolerant`] user themes instead fall
+    /// back to [`Theme::default`] and log a warning, so a single corrupt or
+    /// stale-cached user theme can't make it (or everything listed after it)
+    /// disappear.
+    fn deserialize(&self, parent: Option<&Theme>) -> Result<&Theme> {
         self.deserialized
-            .get_or_try_init(|| asset_from_bytes(self.serialized.take()))
+            .get_or_try_init(|| {
+                let mut theme: Theme = match asset_from_bytes(self.serialized.take()) {
+                    Ok(theme) => theme,
+                    Err(err) if self.tolerant => {
+                        eprintln!(
+                            "[bat warning] failed to load theme, falling back to default settings: {err}"
+                        );
+                        Theme::default()
+                    }
+                    Err(err) => return Err(err),
+                };
+                if let Some(parent) = parent {
+                    merge_theme(&mut theme, parent);
+                }
+                Ok(theme)
+            })
             .map_err(|e| e.context("failed to load theme"))
     }
+
+    /// Serializes an already-parsed user theme the same way embedded themes
+    /// are stored, so it can be merged into an existing [`LazyThemeSet`]
+    /// without needing `feature = "build-assets"`.
+    fn from_theme(theme: &Theme, parent: Option<String>) -> Result<Self> {
+        Ok(LazyTheme {
+            serialized: RefCell::new(bincode::serialize(theme)?),
+            deserialized: OnceCell::new(),
+            parent,
+            tolerant: true,
+        })
+    }
+}
+
+/// Fills in `child`'s unset [`syntect::highlighting::ThemeSettings`] fields
+/// from `parent`, and appends `parent`'s scope rules after `child`'s own so
+/// `child`'s rules win on equal specificity (earlier entries take priority).
+fn merge_theme(child: &mut Theme, parent: &Theme) {
+    macro_rules! inherit_settings {
+        ($($field:ident),* $(,)?) => {
+            $(
+                if child.settings.$field.is_none() {
+                    child.settings.$field = parent.settings.$field.clone();
+                }
+            )*
+        };
+    }
+    inherit_settings!(
+        foreground,
+        background,
+        caret,
+        line_highlight,
+        misspelling,
+        minimap_border,
+        accent,
+        popup_css,
+        phantom_css,
+        bracket_contents_foreground,
+        bracket_contents_options,
+        brackets_foreground,
+        brackets_background,
+        brackets_options,
+        tags_foreground,
+        tags_options,
+        highlight,
+        find_highlight,
+        find_highlight_foreground,
+        gutter,
+        gutter_foreground,
+        selection,
+        selection_foreground,
+        selection_border,
+        inactive_selection,
+        inactive_selection_foreground,
+        guide,
+        active_guide,
+        stack_guide,
+        highlight_foreground,
+        shadow,
+    );
+    child.scopes.extend(parent.scopes.iter().cloned());
+}
+
+/// Reads a theme's declared parent theme name, if any. Neither `.tmTheme`
+/// (plist) nor `.sublime-color-scheme` (JSON) defines an "inherits" key
+/// itself — syntect's parser would just ignore it — so this is a plain text
+/// scan over the raw file contents rather than a second structured parse.
+fn extract_inherits(content: &str) -> Option<String> {
+    if let Some(rest) = content.split_once("\"inherits\"").map(|(_, rest)| rest) {
+        let rest = &rest[rest.find('"')? + 1..];
+        return Some(rest[..rest.find('"')?].to_owned());
+    }
+    if let Some(rest) = content.split_once("<key>inherits</key>").map(|(_, rest)| rest) {
+        let rest = &rest[rest.find("<string>")? + "<string>".len()..];
+        return Some(rest[..rest.find("</string>")?].to_owned());
+    }
+    None
 }
 
 fn serialize_refcell_bytes<S>(
@@ -97,6 +322,8 @@ impl TryFrom<syntect::highlighting::ThemeSet> for LazyThemeSet {
                     false,
                 )?),
                 deserialized: OnceCell::new(),
+                parent: None,
+                tolerant: false,
             };
 
             // Ok done, now we can add it
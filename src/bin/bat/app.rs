@@ -6,7 +6,7 @@ use crate::{
     clap_app,
     config::{get_args_from_config_file, get_args_from_env_opts_var, get_args_from_env_vars},
 };
-use bat::assets::syntax_mapping::{MappingTarget, SyntaxMappingBuilder};
+use bat::syntax_mapping::{MappingTarget, SyntaxMappingBuilder};
 use bat::input::InputKind;
 use clap::ArgMatches;
 
@@ -3,20 +3,23 @@ use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 
 use clap::ArgMatches;
+use encoding_rs::Encoding;
 
-use bat::assets::syntax_mapping::MappingTarget;
-use bat::config::{leak_config_string, Config};
+use bat::config::Config;
+use bat::syntax_mapping::MappingTargetConfig;
 use bat::controller::line_range::{HighlightedLineRanges, LineRange, LineRanges, VisibleLines};
 use bat::error::*;
 use bat::input::Input;
 use bat::output::PagingMode;
 use bat::printer::{
-    style::{StyleComponent, StyleComponents},
+    style::{SignedStyleComponent, StyleComponent, StyleComponents},
+    terminal::{parse_color_spec, Mode},
     NonprintableNotation, WrappingMode,
 };
 
 use crate::clap_app;
 use crate::config::parse_config_file;
+use crate::gitconfig::apply_git_config;
 use crate::input::{new_file_input, new_stdin_input};
 
 pub fn get_matches() -> ArgMatches {
@@ -70,7 +73,9 @@ pub fn get_config(matches: &ArgMatches, config_path: &Path) -> Result<Config> {
     let mut config = if matches.get_flag("no-config") {
         Config::default()
     } else {
-        parse_config_file(config_path)?
+        let mut config = parse_config_file(config_path)?;
+        apply_git_config(&mut config);
+        config
     };
 
     if let language @ Some(_) = matches.get_one::<String>("language").cloned().or_else(|| {
@@ -90,6 +95,8 @@ pub fn get_config(matches: &ArgMatches, config_path: &Path) -> Result<Config> {
         (true, None) => Some(NonprintableNotation::Unicode),
         (_, Some("unicode")) => Some(NonprintableNotation::Unicode),
         (_, Some("caret")) => Some(NonprintableNotation::Caret),
+        (_, Some("hex")) => Some(NonprintableNotation::Hex),
+        (_, Some("octal")) => Some(NonprintableNotation::Octal),
         _ => None,
     } {
         config.nonprintable_notation = nonprintable_notation;
@@ -97,6 +104,7 @@ pub fn get_config(matches: &ArgMatches, config_path: &Path) -> Result<Config> {
 
     if let wrapping_mode @ Some(_) = match matches.get_one::<String>("wrap").unwrap().as_str() {
         "character" => Some(WrappingMode::Character),
+        "word" => Some(WrappingMode::Word),
         "never" => Some(WrappingMode::NoWrapping),
         _ => None,
     } {
@@ -147,7 +155,7 @@ pub fn get_config(matches: &ArgMatches, config_path: &Path) -> Result<Config> {
 
     if let Some(visible_lines) = matches
         .get_many::<LineRange>("line-range")
-        .map(|ranges| LineRanges::from(ranges.copied().collect()))
+        .map(|ranges| LineRanges::from(ranges.cloned().collect()))
         .map(VisibleLines)
     {
         config.visible_lines = visible_lines;
@@ -160,10 +168,10 @@ pub fn get_config(matches: &ArgMatches, config_path: &Path) -> Result<Config> {
             if matches.get_count("plain") != 0 {
                 Some(StyleComponents::plain())
             } else if matches.get_flag("number") {
-                Some(StyleComponents::new(vec![StyleComponent::LineNumbers]))
+                Some(StyleComponents::new(vec![StyleComponent::LineNumbers.into()]))
             } else {
                 matches
-                    .get_many::<StyleComponent>("style")
+                    .get_many::<SignedStyleComponent>("style")
                     .map(|components| StyleComponents::new(components.copied().collect()))
             }
         }
@@ -185,11 +193,12 @@ pub fn get_config(matches: &ArgMatches, config_path: &Path) -> Result<Config> {
     }
     if let Some(values) = matches.get_many::<String>("map-syntax") {
         for from_to in values {
-            let mut parts = from_to.split(':');
-            syntax_mapping.map_syntax(
-                parts.next().unwrap(),
-                MappingTarget::MapTo(leak_config_string(parts.next().unwrap().to_owned())),
-            );
+            let (glob, target) = split_map_syntax(from_to).ok_or_else(|| {
+                Error::msg(format!(
+                    "invalid --map-syntax value '{from_to}': expected 'glob:target'"
+                ))
+            })?;
+            syntax_mapping.map_syntax(glob, parse_mapping_target(target));
         }
     }
 
@@ -206,7 +215,7 @@ pub fn get_config(matches: &ArgMatches, config_path: &Path) -> Result<Config> {
 
     if let Some(hightlighted_lines) = matches
         .get_many::<LineRange>("highlight-line")
-        .map(|ranges| LineRanges::from(ranges.copied().collect()))
+        .map(|ranges| LineRanges::from(ranges.cloned().collect()))
         .map(HighlightedLineRanges)
     {
         config.highlighted_lines = hightlighted_lines;
@@ -221,5 +230,76 @@ pub fn get_config(matches: &ArgMatches, config_path: &Path) -> Result<Config> {
         config.no_lessopen = no_lessopen;
     }
 
+    if matches.get_flag("hex-dump") {
+        config.hex_dump = true;
+    }
+
+    if matches.get_flag("diff") {
+        config.diff = true;
+    }
+
+    if matches.get_flag("side-by-side") {
+        config.side_by_side = true;
+    }
+
+    if matches.get_flag("navigate") {
+        config.navigate = true;
+    }
+
+    if let Some(label) = matches.get_one::<String>("encoding") {
+        config.encoding = Some(
+            Encoding::for_label(label.as_bytes())
+                .ok_or_else(|| Error::msg(format!("unknown encoding '{label}'")))?,
+        );
+    }
+
+    if let Some(spec) = matches.get_one::<String>("grid-color") {
+        config.grid_color = Some(parse_color_spec(spec)?);
+    }
+
+    if let Some(spec) = matches.get_one::<String>("line-number-color") {
+        config.line_number_color = Some(parse_color_spec(spec)?);
+    }
+
+    if let Some(spec) = matches.get_one::<String>("header-color") {
+        config.header_color = Some(parse_color_spec(spec)?);
+    }
+
+    if let background_mode @ Some(_) =
+        match (matches.get_flag("light"), matches.get_flag("dark")) {
+            (true, _) => Some(Mode::Light),
+            (_, true) => Some(Mode::Dark),
+            _ => None,
+        }
+    {
+        config.background_mode = background_mode;
+    }
+
     Ok(config)
 }
+
+/// Splits a `--map-syntax` value on its last unescaped `:`, returning the
+/// glob (with any `\:` unescaped to a literal `:`) and the target string.
+/// Returns `None` if `spec` has no unescaped `:` at all.
+fn split_map_syntax(spec: &str) -> Option<(String, &str)> {
+    let mut last_unescaped = None;
+    let mut prev = None;
+    for (i, ch) in spec.char_indices() {
+        if ch == ':' && prev != Some('\\') {
+            last_unescaped = Some(i);
+        }
+        prev = Some(ch);
+    }
+    last_unescaped.map(|i| (spec[..i].replace("\\:", ":"), &spec[i + 1..]))
+}
+
+/// Parses the right-hand side of a `--map-syntax` value, recognizing the
+/// special `MapToUnknown`/`MapExtensionToUnknown` targets in addition to a
+/// concrete syntax name.
+fn parse_mapping_target(target: &str) -> MappingTargetConfig {
+    match target {
+        "MapToUnknown" => MappingTargetConfig::MapToUnknown,
+        "MapExtensionToUnknown" => MappingTargetConfig::MapExtensionToUnknown,
+        name => MappingTargetConfig::MapTo(name.to_owned()),
+    }
+}
@@ -0,0 +1,63 @@
+use std::num::NonZeroUsize;
+use std::process::Command;
+
+use bat::config::Config;
+use bat::printer::style::{SignedStyleComponent, StyleComponents};
+use bat::printer::WrappingMode;
+
+/// Applies the handful of display settings configurable via the `[bat]`
+/// section of `git config` (e.g. `git config --global bat.theme Dracula`)
+/// on top of `config`, so project-local or global git preferences carry
+/// over to `bat` the same way they already do for tools invoked as
+/// `core.pager`. Shells out to `git config --get-regexp`, which already
+/// merges local and global (and system) config with the usual git
+/// precedence, so this needs no merging logic of its own. Outside a git
+/// work tree only the global and system config are read. A no-op when
+/// `git` isn't on `PATH`, or when no config sets anything bat recognizes.
+pub fn apply_git_config(config: &mut Config) {
+    let Ok(output) = Command::new("git")
+        .args(["config", "--get-regexp", r"^bat\."])
+        .output()
+    else {
+        return;
+    };
+    if !output.status.success() {
+        return;
+    }
+    let Ok(entries) = String::from_utf8(output.stdout) else {
+        return;
+    };
+
+    for entry in entries.lines() {
+        let Some((key, value)) = entry.split_once(' ') else {
+            continue;
+        };
+
+        match key.strip_prefix("bat.") {
+            Some("theme") => config.theme = Some(value.to_owned()),
+            Some("pager") => config.pager = Some(value.to_owned()),
+            Some("tab-width") => {
+                if let Ok(width) = value.parse() {
+                    config.tab_width = NonZeroUsize::new(width).into();
+                }
+            }
+            Some("wrap") => {
+                config.wrapping_mode = match value {
+                    "character" => Some(WrappingMode::Character),
+                    "word" => Some(WrappingMode::Word),
+                    "never" => Some(WrappingMode::NoWrapping),
+                    _ => continue,
+                };
+            }
+            Some("style") => {
+                // Accepts the same `+component`/`-component` spec as `--style`.
+                let components: Vec<SignedStyleComponent> =
+                    value.split(',').filter_map(|s| s.parse().ok()).collect();
+                if !components.is_empty() {
+                    config.style_components = StyleComponents::new(components);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,73 @@
+/// Packs `items` into the widest grid of aligned columns that still fits
+/// `term_width`, the way column-major grid file-listers (e.g. `ls`) lay
+/// theirs out: candidate column counts are tried from most (one item per
+/// column) down to one; for each, items are assigned column-major (the
+/// first `rows` items fill column 0, the next `rows` fill column 1, ...),
+/// and the largest count whose column widths plus a `separator`-width gap
+/// between each still fit `term_width` wins.
+///
+/// `item_widths` gives each item's display width separately from `items`
+/// itself, since the two can differ (an item may carry ANSI color codes
+/// that don't occupy any columns). Returns the rendered rows, one per
+/// line including a trailing newline; the last item in each row isn't
+/// padded or followed by `separator`.
+pub fn layout_grid(items: &[String], item_widths: &[usize], separator: &str, term_width: usize) -> String {
+    let columns = optimal_columns(item_widths, separator.len(), term_width);
+    render_grid(items, item_widths, separator, columns)
+}
+
+/// The largest number of columns `item_widths.len()` items fit into
+/// without any row exceeding `term_width`, per [`layout_grid`]'s search.
+fn optimal_columns(item_widths: &[usize], separator_width: usize, term_width: usize) -> usize {
+    let n = item_widths.len();
+    if n == 0 {
+        return 1;
+    }
+    for columns in (1..=n).rev() {
+        let rows = n.div_ceil(columns);
+        let column_maxes: usize = (0..columns)
+            .map(|col| {
+                (0..rows)
+                    .filter_map(|row| item_widths.get(col * rows + row).copied())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .sum();
+        if column_maxes + separator_width * (columns - 1) <= term_width {
+            return columns;
+        }
+    }
+    1
+}
+
+fn render_grid(items: &[String], item_widths: &[usize], separator: &str, columns: usize) -> String {
+    let n = items.len();
+    let rows = n.div_ceil(columns);
+
+    let col_widths: Vec<usize> = (0..columns)
+        .map(|col| {
+            (0..rows)
+                .filter_map(|row| item_widths.get(col * rows + row).copied())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut result = String::new();
+    for row in 0..rows {
+        let row_indices: Vec<usize> = (0..columns)
+            .map(|col| col * rows + row)
+            .filter(|&idx| idx < n)
+            .collect();
+        for (i, &idx) in row_indices.iter().enumerate() {
+            result.push_str(&items[idx]);
+            if i + 1 != row_indices.len() {
+                let col = idx / rows;
+                result.push_str(&" ".repeat(col_widths[col] - item_widths[idx]));
+                result.push_str(separator);
+            }
+        }
+        result.push('\n');
+    }
+    result
+}
@@ -4,7 +4,26 @@ use std::path::Path;
 use bat::input::Input;
 
 pub fn new_file_input(file: &Path, name: Option<&OsStr>) -> Input {
-    named(Input::from_file(file), name.or(Some(file.as_os_str())))
+    let input = match split_archive_member(file) {
+        Some((archive, entry)) => Input::from_archive_member(archive, entry),
+        None => Input::from_file(file),
+    };
+    named(input, name.or(Some(file.as_os_str())))
+}
+
+/// Splits `archive.tar:entry/path` into its archive and entry components, so
+/// `bat archive.tar:src/main.rs` can view a single member without extracting
+/// it. Only paths whose part before the colon names an existing `.tar` file
+/// are treated this way, so plain paths (and absolute Windows-style
+/// `C:\...` paths) are left alone.
+fn split_archive_member(file: &Path) -> Option<(&Path, &Path)> {
+    let text = file.to_str()?;
+    let (archive, entry) = text.split_once(':')?;
+    let archive = Path::new(archive);
+    if archive.extension().and_then(OsStr::to_str) != Some("tar") || !archive.is_file() {
+        return None;
+    }
+    Some((archive, Path::new(entry)))
 }
 
 pub fn new_stdin_input(name: Option<&OsStr>) -> Input {
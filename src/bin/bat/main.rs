@@ -17,12 +17,15 @@ use bat::input::Input;
 use bat::printer::style::StyleComponents;
 
 use crate::config::{config_file_path, generate_config_file};
+use crate::grid::layout_grid;
 // #[cfg(feature = "bugreport")]
 // use crate::config::system_config_file;
 
 mod clap_app;
 mod cli;
 mod config;
+mod gitconfig;
+mod grid;
 mod input;
 
 #[cfg(feature = "build-assets")]
@@ -105,23 +108,20 @@ fn get_languages(config: &Config, cache_dir: &Path) -> Result<String> {
         for lang in languages {
             write!(result, "{:width$}{}", lang.name, separator, width = longest).unwrap();
 
-            // Number of characters on this line so far, wrap before `desired_width`
-            let mut num_chars = 0;
-
-            let mut extension = lang.file_extensions.iter().peekable();
-            while let Some(word) = extension.next() {
-                // If we can't fit this word in, then create a line break and align it in.
-                let new_chars = word.len() + comma_separator.len();
-                if num_chars + new_chars >= desired_width {
-                    num_chars = 0;
-                    write!(result, "\n{:width$}{}", "", separator, width = longest).unwrap();
-                }
-
-                num_chars += new_chars;
-                write!(result, "{}", style.paint(&word[..])).unwrap();
-                if extension.peek().is_some() {
-                    result.push_str(comma_separator);
-                }
+            let items: Vec<String> = lang
+                .file_extensions
+                .iter()
+                .map(|ext| style.paint(&ext[..]).to_string())
+                .collect();
+            let item_widths: Vec<usize> = lang.file_extensions.iter().map(|ext| ext.len()).collect();
+            let grid = layout_grid(&items, &item_widths, comma_separator, desired_width);
+
+            let mut lines = grid.lines();
+            if let Some(first_line) = lines.next() {
+                result.push_str(first_line);
+            }
+            for line in lines {
+                write!(result, "\n{:width$}{}{}", "", separator, line, width = longest).unwrap();
             }
             result.push('\n');
         }
@@ -132,7 +132,7 @@ fn get_languages(config: &Config, cache_dir: &Path) -> Result<String> {
 
 fn list_languages(
     mut config: Config,
-    _config_dir: &Path,
+    config_dir: &Path,
     cache_dir: &Path,
 ) -> Result<ErrorHandling> {
     let languages: String = get_languages(&config, cache_dir)?;
@@ -140,11 +140,12 @@ fn list_languages(
         languages.into(),
     ))];
     config.loop_through = true;
-    run_controller(inputs, &config, cache_dir)
+    run_controller(inputs, &config, config_dir, cache_dir)
 }
 
-fn list_themes(mut config: Config, _config_dir: &Path, cache_dir: &Path) -> Result<ErrorHandling> {
-    let assets = HighlightingAssets::new(cache_dir)?;
+fn list_themes(mut config: Config, config_dir: &Path, cache_dir: &Path) -> Result<ErrorHandling> {
+    let mut assets = HighlightingAssets::new(cache_dir)?;
+    assets.load_user_themes([config_dir.join("themes")]);
     config.language = Some("Rust".to_owned());
     config.style_components = StyleComponents::plain().expand(false).unwrap();
 
@@ -161,16 +162,58 @@ fn list_themes(mut config: Config, _config_dir: &Path, cache_dir: &Path) -> Resu
             println!();
         }
     } else {
-        for theme in assets.themes() {
-            println!("{}", theme);
-        }
+        let items: Vec<String> = assets.themes().map(|theme| theme.to_owned()).collect();
+        let item_widths: Vec<usize> = items.iter().map(|theme| theme.len()).collect();
+        print!(
+            "{}",
+            layout_grid(&items, &item_widths, "  ", config.term_width.into())
+        );
     }
 
     Ok(ErrorHandling::NoError)
 }
 
-fn run_controller(inputs: Vec<Input>, config: &Config, cache_dir: &Path) -> Result<ErrorHandling> {
-    let assets = HighlightingAssets::new(cache_dir)?;
+/// Prints a diagnostic report of how each input's syntax mapping resolved,
+/// for `--diagnostic-syntax`: which glob/path-regex rule(s) matched (ranked
+/// by specificity, least specific first), the file name after
+/// ignored-suffix stripping, and the final chosen target.
+fn diagnose_syntax(inputs: Vec<Input>, config: &Config) -> Result<ErrorHandling> {
+    for input in &inputs {
+        let Some(name) = input.description.name.as_ref() else {
+            println!("{}: no path to resolve a syntax for", input.description.kind);
+            continue;
+        };
+        let path = Path::new(name);
+        let resolution = config.syntax_mapping.explain_syntax_for(path);
+        println!("{}:", path.display());
+        println!(
+            "  stripped file name: {}",
+            resolution.stripped_file_name.to_string_lossy()
+        );
+        for rule in &resolution.matched_rules {
+            println!(
+                "  {:?} rule #{} `{}` (specificity {}){} -> {:?}",
+                rule.kind,
+                rule.rule_index,
+                rule.pattern,
+                rule.specificity,
+                if rule.negated { " (negation)" } else { "" },
+                rule.target
+            );
+        }
+        println!("  resolved target: {:?}", resolution.target);
+    }
+    Ok(ErrorHandling::NoError)
+}
+
+fn run_controller(
+    inputs: Vec<Input>,
+    config: &Config,
+    config_dir: &Path,
+    cache_dir: &Path,
+) -> Result<ErrorHandling> {
+    let mut assets = HighlightingAssets::new(cache_dir)?;
+    assets.load_user_themes([config_dir.join("themes")]);
     let controller = Controller::new(config, &assets);
     controller.run(inputs)
 }
@@ -256,7 +299,7 @@ fn run() -> Result<ErrorHandling> {
                 let inputs = vec![Input::from_file("cache")];
                 let config = app.config(&inputs)?;
 
-                run_controller(inputs, &config, cache_dir)
+                run_controller(inputs, &config, config_dir, cache_dir)
             }
         }
         _ => {
@@ -264,9 +307,12 @@ fn run() -> Result<ErrorHandling> {
             let config = cli::get_config(&matches, &config_file)?;
 
             if matches.get_flag("list-languages") {
-                list_languages(config.consolidate(&inputs), &config_dir, &cache_dir)
+                list_languages(config.consolidate(&inputs)?, &config_dir, &cache_dir)
             } else if matches.get_flag("list-themes") {
-                list_themes(config.consolidate(&inputs), &config_dir, &cache_dir)
+                list_themes(config.consolidate(&inputs)?, &config_dir, &cache_dir)
+            } else if matches.get_flag("diagnostic-syntax") {
+                let config = config.consolidate(&inputs)?;
+                diagnose_syntax(inputs, &config)
             } else if matches.get_flag("config-file") {
                 println!("{}", config_file.display());
                 Ok(ErrorHandling::NoError)
@@ -283,8 +329,8 @@ fn run() -> Result<ErrorHandling> {
                 println!("{}", get_acknowledgements());
                 Ok(ErrorHandling::NoError)
             } else {
-                let config = config.consolidate(&inputs);
-                run_controller(inputs, &config, &cache_dir)
+                let config = config.consolidate(&inputs)?;
+                run_controller(inputs, &config, &config_dir, &cache_dir)
             }
         }
     }
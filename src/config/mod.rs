@@ -1,18 +1,95 @@
 use std::env::{self, VarError};
+use std::fmt;
 use std::io::{self, IsTerminal};
 use std::num::NonZeroUsize;
+use std::ops::{Deref, DerefMut};
 
+use encoding_rs::Encoding;
 use serde::{Deserialize, Serialize};
 
-use crate::assets::syntax_mapping::SyntaxMapping;
-use crate::controller::line_range::{HighlightedLineRanges, VisibleLines};
+use crate::controller::content_filter::ContentFilter;
+use crate::controller::line_range::{HighlightedLineRanges, LineRanges, VisibleLines};
 use crate::error::{Context, Result};
 use crate::input::{Input, InputKind};
 #[cfg(feature = "paging")]
 use crate::output::pager::PagingMode;
+use crate::printer::match_highlight::MatchHighlights;
+use crate::printer::overlay::StyleOverlays;
 use crate::printer::preprocessor::NonprintableNotation;
 use crate::printer::style::{ExpandedStyleComponents, StyleComponents};
+use crate::printer::terminal::{ColorSpec, Mode};
 use crate::printer::{TabWidth, WrappingMode};
+use crate::syntax_mapping::{SyntaxMapping, SyntaxMappingConfig};
+
+/// An owned, growable string, used both for small buffers that are
+/// repeatedly cleared and rebuilt (e.g. the ANSI escape sequences in
+/// [`crate::printer::vscreen`]) and as an owned, (de)serializable stand-in
+/// for config values that are otherwise borrowed at runtime.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ConfigString(String);
+
+impl ConfigString {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0.into_bytes()
+    }
+}
+
+impl Deref for ConfigString {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.0
+    }
+}
+
+impl DerefMut for ConfigString {
+    fn deref_mut(&mut self) -> &mut String {
+        &mut self.0
+    }
+}
+
+impl fmt::Write for ConfigString {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s)
+    }
+}
+
+impl fmt::Display for ConfigString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl From<String> for ConfigString {
+    fn from(s: String) -> Self {
+        ConfigString(s)
+    }
+}
+
+impl From<&str> for ConfigString {
+    fn from(s: &str) -> Self {
+        ConfigString(s.to_owned())
+    }
+}
+
+impl AsRef<str> for ConfigString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Leaks `s`, producing a `&'static str`. Used to wire owned, config-file-
+/// or CLI-sourced strings into the zero-copy runtime [`SyntaxMapping`]
+/// (e.g. its `MapTo` target), which otherwise only ever borrows from
+/// `'static` sources (the embedded syntax mapping tables).
+pub fn leak_config_string(s: String) -> &'static str {
+    Box::leak(s.into_boxed_str())
+}
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -25,6 +102,11 @@ pub struct Config {
     #[serde(default)]
     pub nonprintable_notation: Option<NonprintableNotation>,
 
+    /// Render the input as a canonical hex dump (offset, hex bytes, ASCII
+    /// gutter) instead of decoding it as text, for inspecting binary content.
+    #[serde(default)]
+    pub hex_dump: bool,
+
     /// The character width of the terminal
     #[serde(default)]
     pub term_width: Option<NonZeroUsize>,
@@ -50,6 +132,27 @@ pub struct Config {
     #[serde(default)]
     pub style_components: StyleComponents,
 
+    /// Override for the grid/rule line color, falling back to the
+    /// theme-derived gutter color when unset.
+    #[serde(skip)]
+    pub grid_color: Option<ColorSpec>,
+
+    /// Override for the line number color, falling back to the
+    /// theme-derived gutter color when unset.
+    #[serde(skip)]
+    pub line_number_color: Option<ColorSpec>,
+
+    /// Override for the header value color, falling back to plain bold
+    /// when unset.
+    #[serde(skip)]
+    pub header_color: Option<ColorSpec>,
+
+    /// Explicit `--light`/`--dark` override for the terminal background,
+    /// used to pick a legible fallback gutter color. `None` auto-detects
+    /// from `$COLORFGBG`.
+    #[serde(skip)]
+    pub background_mode: Option<Mode>,
+
     /// If and how text should be wrapped
     #[serde(default)]
     pub wrapping_mode: Option<WrappingMode>,
@@ -63,13 +166,24 @@ pub struct Config {
     #[serde(default)]
     pub visible_lines: VisibleLines,
 
+    /// Line numbers to print windows of context around, like `grep -C`,
+    /// combined with `context_radius`. When non-empty, this takes priority
+    /// over `visible_lines`.
+    #[serde(default)]
+    pub context_lines: Vec<usize>,
+
+    /// How many lines of context to show above and below each of
+    /// `context_lines`.
+    #[serde(default)]
+    pub context_radius: usize,
+
     /// The syntax highlighting theme
     #[serde(default)]
     pub theme: Option<String>,
 
     /// File extension/name mappings
-    #[serde(skip)]
-    pub syntax_mapping: SyntaxMapping,
+    #[serde(default)]
+    pub syntax_mapping: SyntaxMappingConfig,
 
     /// Command to start the pager
     #[serde(default)]
@@ -91,18 +205,66 @@ pub struct Config {
     #[cfg(feature = "lessopen")]
     #[serde(default)]
     pub no_lessopen: bool,
+
+    /// Number of worker threads used to render independent inputs in
+    /// parallel. `1` (the default) keeps the existing strictly sequential
+    /// behavior.
+    #[serde(default)]
+    pub concurrency: Option<NonZeroUsize>,
+
+    /// Grep-style filter that restricts output to lines matching a pattern
+    /// (plus surrounding context), composed with `visible_lines`.
+    #[serde(skip)]
+    pub content_filter: Option<ContentFilter>,
+
+    /// Forces input to be decoded with a specific encoding (e.g. Shift-JIS,
+    /// GB18030) instead of relying on auto-detection. `None` auto-detects
+    /// each input independently, as before.
+    #[serde(skip)]
+    pub encoding: Option<&'static Encoding>,
+
+    /// Regex-driven style rules layered on top of syntax highlighting, keyed
+    /// by syntax name or filename glob.
+    #[serde(skip)]
+    pub style_overlays: StyleOverlays,
+
+    /// Column ranges within specific lines to emphasize with a dedicated
+    /// match style, e.g. the exact span a search pattern matched.
+    #[serde(skip)]
+    pub match_highlights: MatchHighlights,
+
+    /// Treat the input as a unified diff (`git diff`/`diff -u` output):
+    /// render hunk/file headers as section separators, tint added/removed
+    /// lines, and re-detect the syntax at each file marker instead of
+    /// highlighting the whole input as a single patch file.
+    #[serde(default)]
+    pub diff: bool,
+
+    /// Render two inputs (or, combined with `diff`, the removed/added halves
+    /// of one diff input) as two gutter-numbered columns side by side
+    /// instead of one after another.
+    #[serde(default)]
+    pub side_by_side: bool,
+
+    /// When paging through `less`, launch it with an initial search for an
+    /// invisible marker printed before each file header, so the ordinary
+    /// search-repeat keys (`n`/`N`) step between files instead of requiring
+    /// manual scrolling.
+    #[serde(default)]
+    pub navigate: bool,
 }
 
 impl Config {
-    pub fn consolidate(self, inputs: &'_ [Input]) -> ConsolidatedConfig {
+    pub fn consolidate(self, inputs: &'_ [Input]) -> Result<ConsolidatedConfig> {
         let stdout = io::stdout();
         let is_terminal = stdout.is_terminal();
         let interactive = is_terminal || self.always_show_decorations;
         let style = self.style_components.expand(interactive).unwrap();
         let plain = style.plain();
-        ConsolidatedConfig {
+        Ok(ConsolidatedConfig {
             language: self.language,
             nonprintable_notation: self.nonprintable_notation,
+            hex_dump: self.hex_dump,
             term_width: self.term_width.unwrap_or_else(|| {
                 is_terminal
                     .then(|| console::Term::stdout().size().1)
@@ -122,6 +284,10 @@ impl Config {
                     .is_some_and(|colorterm| colorterm == "truecolor" || colorterm == "24bit")
             }),
             style_components: style,
+            grid_color: self.grid_color,
+            line_number_color: self.line_number_color,
+            header_color: self.header_color,
+            background_mode: self.background_mode,
             wrapping_mode: self.wrapping_mode.unwrap_or(if plain {
                 WrappingMode::NoWrapping
             } else {
@@ -140,16 +306,34 @@ impl Config {
                     PagingMode::Never
                 }
             }),
-            visible_lines: self.visible_lines,
+            visible_lines: if self.context_lines.is_empty() {
+                self.visible_lines
+            } else {
+                VisibleLines(LineRanges::context_window(
+                    &self.context_lines,
+                    self.context_radius,
+                ))
+            },
             theme: self.theme,
-            syntax_mapping: self.syntax_mapping,
+            syntax_mapping: self
+                .syntax_mapping
+                .consolidate()
+                .context("invalid syntax mapping")?,
             pager: self.pager,
             use_italic_text: self.use_italic_text,
             highlighted_lines: self.highlighted_lines,
             always_show_decorations: self.always_show_decorations,
             #[cfg(feature = "lessopen")]
             no_lessopen: self.no_lessopen,
-        }
+            concurrency: self.concurrency.map_or(1, NonZeroUsize::get),
+            content_filter: self.content_filter,
+            encoding: self.encoding,
+            style_overlays: self.style_overlays,
+            match_highlights: self.match_highlights,
+            diff: self.diff,
+            side_by_side: self.side_by_side,
+            navigate: self.navigate,
+        })
     }
 }
 
@@ -157,24 +341,37 @@ impl Config {
 pub struct ConsolidatedConfig {
     pub language: Option<String>,
     pub nonprintable_notation: Option<NonprintableNotation>,
+    pub hex_dump: bool,
     pub term_width: NonZeroUsize,
     pub tab_width: TabWidth,
     pub loop_through: bool,
     pub colored_output: bool,
     pub true_color: bool,
     pub style_components: ExpandedStyleComponents,
+    pub grid_color: Option<ColorSpec>,
+    pub line_number_color: Option<ColorSpec>,
+    pub header_color: Option<ColorSpec>,
+    pub background_mode: Option<Mode>,
     pub wrapping_mode: WrappingMode,
     #[cfg(feature = "paging")]
     pub paging_mode: PagingMode,
     pub visible_lines: VisibleLines,
     pub theme: Option<String>,
-    pub syntax_mapping: SyntaxMapping,
+    pub syntax_mapping: SyntaxMapping<'static>,
     pub pager: Option<String>,
     pub use_italic_text: bool,
     pub highlighted_lines: HighlightedLineRanges,
     pub always_show_decorations: bool,
     #[cfg(feature = "lessopen")]
     pub no_lessopen: bool,
+    pub concurrency: usize,
+    pub content_filter: Option<ContentFilter>,
+    pub encoding: Option<&'static Encoding>,
+    pub style_overlays: StyleOverlays,
+    pub match_highlights: MatchHighlights,
+    pub diff: bool,
+    pub side_by_side: bool,
+    pub navigate: bool,
 }
 
 pub(crate) fn get_env_var(key: &str) -> Result<Option<String>> {
@@ -0,0 +1,25 @@
+use regex::bytes::Regex;
+
+/// A grep-style line filter for [`super::Controller::print_file_ranges`]:
+/// only lines matching `pattern`, plus `before_context`/`after_context`
+/// surrounding lines (like `-B`/`-A`), are shown. Lines excluded by the
+/// filter are treated the same way as lines excluded by
+/// [`super::line_range::LineRanges`]: the highlighter still sees them (to
+/// keep its parse state continuous), but they're not emitted, and a gap
+/// between emitted regions triggers `print_snip`.
+#[derive(Debug, Clone)]
+pub struct ContentFilter {
+    pub pattern: Regex,
+    pub before_context: usize,
+    pub after_context: usize,
+}
+
+impl ContentFilter {
+    pub fn new(pattern: Regex, before_context: usize, after_context: usize) -> Self {
+        ContentFilter {
+            pattern,
+            before_context,
+            after_context,
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use std::path::Path;
+
+use unicode_width::UnicodeWidthChar;
+
+/// Classification of a single raw line of unified-diff (`git diff`/`diff -u`)
+/// input, used by [`crate::config::ConsolidatedConfig::diff`] mode to decide
+/// how [`crate::printer::InteractivePrinter`] should render it: headers and
+/// hunk markers are rendered as section separators instead of being syntax
+/// highlighted, while added/removed lines get a background tint on top of
+/// whatever syntax highlighting their content would otherwise receive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DiffLineKind {
+    /// A `+++`/`--- ` file marker, e.g. `+++ b/src/main.rs`.
+    FileMarker,
+    /// An `@@ -a,b +c,d @@` hunk header.
+    HunkHeader,
+    /// Other diff metadata: `diff --git ...`, `index ...`, `new file mode
+    /// ...`, a rename/copy note, or the "no newline" marker.
+    Meta,
+    /// A `+`-prefixed added line.
+    Added,
+    /// A `-`-prefixed removed line.
+    Removed,
+    /// An unprefixed context line (or anything that isn't recognized as one
+    /// of the above).
+    Context,
+}
+
+const META_PREFIXES: &[&[u8]] = &[
+    b"diff --git ",
+    b"diff --cc ",
+    b"index ",
+    b"new file mode ",
+    b"deleted file mode ",
+    b"old mode ",
+    b"new mode ",
+    b"similarity index ",
+    b"dissimilarity index ",
+    b"rename from ",
+    b"rename to ",
+    b"copy from ",
+    b"copy to ",
+    b"Binary files ",
+    b"\\ No newline at end of file",
+];
+
+/// Classifies a single raw line of unified-diff input by its leading bytes.
+pub(crate) fn classify_diff_line(line: &[u8]) -> DiffLineKind {
+    if line.starts_with(b"+++ ") || line.starts_with(b"--- ") {
+        DiffLineKind::FileMarker
+    } else if line.starts_with(b"@@ ") || line.starts_with(b"@@\t") {
+        DiffLineKind::HunkHeader
+    } else if META_PREFIXES.iter().any(|prefix| line.starts_with(prefix)) {
+        DiffLineKind::Meta
+    } else if line.starts_with(b"+") {
+        DiffLineKind::Added
+    } else if line.starts_with(b"-") {
+        DiffLineKind::Removed
+    } else {
+        DiffLineKind::Context
+    }
+}
+
+/// Extracts the file path from a `+++ `/`--- ` file marker line, stripping
+/// the conventional `a/`/`b/` prefix `git diff` adds and ignoring the
+/// `/dev/null` sentinel used for added/removed files. Returns `None` for
+/// `/dev/null` or a line that turns out not to carry a usable path.
+pub(crate) fn file_marker_path(line: &str) -> Option<&Path> {
+    let rest = line
+        .strip_prefix("+++ ")
+        .or_else(|| line.strip_prefix("--- "))?;
+    let rest = rest.trim_end_matches(['\r', '\n']);
+    // In the plain `diff -u` format (as opposed to `git diff`'s), a tab
+    // separates the path from an optional trailing timestamp.
+    let rest = rest.split('\t').next().unwrap_or(rest);
+    if rest.is_empty() || rest == "/dev/null" {
+        return None;
+    }
+    let rest = rest
+        .strip_prefix("a/")
+        .or_else(|| rest.strip_prefix("b/"))
+        .unwrap_or(rest);
+    Some(Path::new(rest))
+}
+
+/// A run of word characters or non-word characters within a line, tagged
+/// with its display-column span, produced by [`tokenize_for_word_diff`].
+struct WordToken<'a> {
+    text: &'a str,
+    start_col: usize,
+    end_col: usize,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Splits `line` into runs of word-characters vs. non-word-characters (the
+/// tokens [`word_diff_ranges`] aligns), each tagged with its display-column
+/// span.
+fn tokenize_for_word_diff(line: &str) -> Vec<WordToken<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    let mut col = 0;
+
+    while let Some(&(start_byte, first_ch)) = chars.peek() {
+        let is_word = is_word_char(first_ch);
+        let start_col = col;
+        let mut end_byte = start_byte;
+
+        while let Some(&(byte, ch)) = chars.peek() {
+            if is_word_char(ch) != is_word {
+                break;
+            }
+            end_byte = byte + ch.len_utf8();
+            col += ch.width().unwrap_or(0);
+            chars.next();
+        }
+
+        tokens.push(WordToken {
+            text: &line[start_byte..end_byte],
+            start_col,
+            end_col: col,
+        });
+    }
+
+    tokens
+}
+
+/// Bounds the LCS alignment's `O(tokens_removed * tokens_added)` DP table:
+/// past this many tokens on either side, [`word_diff_ranges`] gives up on
+/// alignment and treats the whole line pair as changed instead.
+const MAX_WORD_DIFF_TOKENS: usize = 500;
+
+/// Computes the column ranges of the tokens that changed between a replaced
+/// `-`/`+` line pair, for [`crate::printer::InteractivePrinter`] to
+/// highlight on top of the line-level diff background. Tokenizes both lines
+/// into runs of word-characters vs. non-word-characters (see
+/// [`tokenize_for_word_diff`]), aligns them with the longest common
+/// subsequence of tokens, and returns the column spans of the tokens left
+/// over on each side, i.e. those that aren't part of that alignment.
+///
+/// Falls back to treating both lines as changed in full, skipping
+/// alignment altogether, when one side is empty or either side has more
+/// than [`MAX_WORD_DIFF_TOKENS`] tokens.
+pub(crate) fn word_diff_ranges(
+    removed: &str,
+    added: &str,
+) -> (Vec<(usize, usize)>, Vec<(usize, usize)>) {
+    let removed_tokens = tokenize_for_word_diff(removed);
+    let added_tokens = tokenize_for_word_diff(added);
+
+    let whole_removed = || removed_tokens.last().map(|t| (0, t.end_col)).into_iter().collect();
+    let whole_added = || added_tokens.last().map(|t| (0, t.end_col)).into_iter().collect();
+
+    if removed_tokens.is_empty() || added_tokens.is_empty() {
+        return (whole_removed(), whole_added());
+    }
+    if removed_tokens.len() > MAX_WORD_DIFF_TOKENS || added_tokens.len() > MAX_WORD_DIFF_TOKENS {
+        return (whole_removed(), whole_added());
+    }
+
+    let m = removed_tokens.len();
+    let n = added_tokens.len();
+
+    // lcs[i][j] = length of the LCS of the first i removed tokens and the
+    // first j added tokens.
+    let mut lcs = vec![vec![0u32; n + 1]; m + 1];
+    for i in 0..m {
+        for j in 0..n {
+            lcs[i + 1][j + 1] = if removed_tokens[i].text == added_tokens[j].text {
+                lcs[i][j] + 1
+            } else {
+                lcs[i][j + 1].max(lcs[i + 1][j])
+            };
+        }
+    }
+
+    // Walk the table backwards to mark which tokens the LCS actually passes
+    // through; everything else is a changed token.
+    let mut removed_on_lcs = vec![false; m];
+    let mut added_on_lcs = vec![false; n];
+    let (mut i, mut j) = (m, n);
+    while i > 0 && j > 0 {
+        if removed_tokens[i - 1].text == added_tokens[j - 1].text
+            && lcs[i][j] == lcs[i - 1][j - 1] + 1
+        {
+            removed_on_lcs[i - 1] = true;
+            added_on_lcs[j - 1] = true;
+            i -= 1;
+            j -= 1;
+        } else if lcs[i - 1][j] >= lcs[i][j - 1] {
+            i -= 1;
+        } else {
+            j -= 1;
+        }
+    }
+
+    let changed_ranges = |tokens: &[WordToken], on_lcs: &[bool]| {
+        tokens
+            .iter()
+            .zip(on_lcs)
+            .filter(|&(_, &on_lcs)| !on_lcs)
+            .map(|(token, _)| (token.start_col, token.end_col))
+            .collect()
+    };
+
+    (
+        changed_ranges(&removed_tokens, &removed_on_lcs),
+        changed_ranges(&added_tokens, &added_on_lcs),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_classify_file_markers() {
+        assert_eq!(classify_diff_line(b"--- a/src/main.rs"), DiffLineKind::FileMarker);
+        assert_eq!(classify_diff_line(b"+++ b/src/main.rs"), DiffLineKind::FileMarker);
+    }
+
+    #[test]
+    fn test_classify_hunk_header() {
+        assert_eq!(
+            classify_diff_line(b"@@ -10,7 +10,8 @@ fn main() {"),
+            DiffLineKind::HunkHeader
+        );
+    }
+
+    #[test]
+    fn test_classify_meta() {
+        assert_eq!(
+            classify_diff_line(b"diff --git a/src/main.rs b/src/main.rs"),
+            DiffLineKind::Meta
+        );
+        assert_eq!(
+            classify_diff_line(b"index 1234567..89abcde 100644"),
+            DiffLineKind::Meta
+        );
+    }
+
+    #[test]
+    fn test_classify_added_removed_context() {
+        assert_eq!(classify_diff_line(b"+let x = 1;"), DiffLineKind::Added);
+        assert_eq!(classify_diff_line(b"-let x = 1;"), DiffLineKind::Removed);
+        assert_eq!(classify_diff_line(b"    let x = 1;"), DiffLineKind::Context);
+        assert_eq!(classify_diff_line(b""), DiffLineKind::Context);
+    }
+
+    #[test]
+    fn test_file_marker_path_strips_git_prefix() {
+        assert_eq!(
+            file_marker_path("+++ b/src/main.rs\n"),
+            Some(Path::new("src/main.rs"))
+        );
+        assert_eq!(
+            file_marker_path("--- a/src/main.rs\n"),
+            Some(Path::new("src/main.rs"))
+        );
+    }
+
+    #[test]
+    fn test_file_marker_path_ignores_dev_null() {
+        assert_eq!(file_marker_path("+++ /dev/null\n"), None);
+    }
+
+    #[test]
+    fn test_file_marker_path_strips_trailing_timestamp() {
+        assert_eq!(
+            file_marker_path("--- a/src/main.rs\t2024-01-01 00:00:00.000000000 +0000\n"),
+            Some(Path::new("src/main.rs"))
+        );
+    }
+
+    #[test]
+    fn test_file_marker_path_rejects_non_marker() {
+        assert_eq!(file_marker_path("not a marker"), None);
+    }
+
+    #[test]
+    fn test_word_diff_marks_only_the_changed_word() {
+        let (removed, added) = word_diff_ranges("let x = 1;", "let x = 2;");
+        assert_eq!(removed, vec![(8, 9)]);
+        assert_eq!(added, vec![(8, 9)]);
+    }
+
+    #[test]
+    fn test_word_diff_identical_lines_have_no_changes() {
+        let (removed, added) = word_diff_ranges("let x = 1;", "let x = 1;");
+        assert!(removed.is_empty());
+        assert!(added.is_empty());
+    }
+
+    #[test]
+    fn test_word_diff_empty_removed_marks_whole_added_line() {
+        let (removed, added) = word_diff_ranges("", "let x = 1;");
+        assert!(removed.is_empty());
+        assert_eq!(added, vec![(0, 10)]);
+    }
+
+    #[test]
+    fn test_word_diff_empty_added_marks_whole_removed_line() {
+        let (removed, added) = word_diff_ranges("let x = 1;", "");
+        assert_eq!(removed, vec![(0, 10)]);
+        assert!(added.is_empty());
+    }
+
+    #[test]
+    fn test_word_diff_falls_back_past_the_token_cap() {
+        let huge = "a ".repeat(MAX_WORD_DIFF_TOKENS + 1);
+        let (removed, added) = word_diff_ranges(&huge, "b");
+        assert_eq!(removed, vec![(0, huge.len())]);
+        assert_eq!(added, vec![(0, 1)]);
+    }
+}
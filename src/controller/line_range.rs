@@ -1,11 +1,11 @@
 #[allow(unused_imports)]
 use zwrite::{write, writeln};
 
-use std::cmp::Ordering;
 use std::error::Error as StdError;
 use std::fmt::{self, Display};
-use std::ops::{Bound, RangeBounds};
+use std::ops::Bound;
 
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug)]
@@ -21,57 +21,293 @@ impl Display for LineRangeParseError {
 
 impl StdError for LineRangeParseError {}
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
-pub struct LineRange(Bound<usize>, Bound<usize>);
+/// An out-of-range `$`-relative offset: the address asked for more lines
+/// from the end of the file than the file actually has.
+#[derive(Debug)]
+pub struct LineRangeResolveError {
+    pub offset: usize,
+    pub total_lines: usize,
+}
 
-impl RangeBounds<usize> for LineRange {
-    fn start_bound(&self) -> Bound<&usize> {
-        self.0.as_ref()
+impl Display for LineRangeResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line range offset '$-{}' exceeds the file's {} line(s)",
+            self.offset, self.total_lines
+        )
     }
+}
+
+impl StdError for LineRangeResolveError {}
+
+/// A compiled program size past which a regex line-range address is
+/// rejected, so a pathological pattern (e.g. deeply nested repetition)
+/// can't stall startup compiling it.
+const PATTERN_SIZE_LIMIT: usize = 1 << 20;
+
+/// One endpoint of a [`LineRange::Numeric`] range: either a plain 1-based
+/// line number, or a `$`-relative offset counted back from the last line
+/// (`FromEnd(0)` is the last line, `FromEnd(9)` the 10th-from-last, written
+/// `$` and `$-9`). A `FromEnd` endpoint isn't comparable to a line number
+/// until [`LineRange::resolve`] turns it into a concrete `Line` once the
+/// file's total line count is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineNumber {
+    Line(usize),
+    FromEnd(usize),
+}
 
-    fn end_bound(&self) -> Bound<&usize> {
-        self.1.as_ref()
+impl Display for LineNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineNumber::Line(v) => write!(f, "{v}"),
+            LineNumber::FromEnd(0) => write!(f, "$"),
+            LineNumber::FromEnd(v) => write!(f, "$-{v}"),
+        }
     }
+}
 
-    fn contains<U>(&self, item: &U) -> bool
-    where
-        usize: PartialOrd<U>,
-        U: ?Sized + PartialOrd<usize>,
-    {
-        let left = match self.0 {
-            Bound::Unbounded => true,
-            Bound::Included(ref v) => v <= item,
-            Bound::Excluded(ref v) => v < item,
-        };
-        let right = match self.1 {
-            Bound::Unbounded => true,
-            Bound::Included(ref v) => item <= v,
-            Bound::Excluded(ref v) => item < v,
-        };
-        left && right
+/// Parses a single numeric range endpoint: a plain line number, or a
+/// `$`-relative one (`$` for the last line, `$-N` for the `N`th line
+/// before it).
+fn parse_line_number(s: &str) -> Option<LineNumber> {
+    if s == "$" {
+        return Some(LineNumber::FromEnd(0));
+    }
+    if let Some(offset) = s.strip_prefix("$-") {
+        return offset.parse().ok().map(LineNumber::FromEnd);
+    }
+    s.parse().ok().map(LineNumber::Line)
+}
+
+/// A single `--line-range`/`--highlight-line` address, either a plain
+/// numeric range (`40`, `40:50`, `:50`, `40:`, `40:+10`, `40:-10`, `$-9:$`)
+/// or a sed-style regular-expression address (`/pattern/`, `/start/:/end/`).
+///
+/// A `Pattern` address isn't directly checkable against a line number, and
+/// neither is a `Numeric` range with a `$`-relative endpoint: both need to
+/// be resolved into a concrete form first -- [`LineRanges::resolve_patterns`]
+/// turns `Pattern` addresses into `Numeric` ranges by walking the file's
+/// actual lines, and [`LineRanges::resolve`] turns `$`-relative endpoints
+/// into plain line numbers once the file's total line count is known.
+/// Until that happens, [`LineRanges::check`] simply treats the address as
+/// matching nothing, so one that was never resolved degrades to a no-op
+/// rather than panicking.
+#[derive(Debug, Clone)]
+pub enum LineRange {
+    Numeric(Bound<LineNumber>, Bound<LineNumber>),
+    Pattern { start: Regex, end: Option<Regex> },
+}
+
+impl LineRange {
+    /// This address's bounds as plain line numbers, or `None` if it isn't
+    /// one yet -- a `Pattern` address, or a `Numeric` range with a
+    /// `$`-relative endpoint, can't be compared against a line number
+    /// until it's been resolved (see [`Self::needs_resolve`]).
+    fn concrete_bounds(&self) -> Option<(Bound<usize>, Bound<usize>)> {
+        fn concrete(bound: Bound<LineNumber>) -> Option<Bound<usize>> {
+            Some(match bound {
+                Bound::Unbounded => Bound::Unbounded,
+                Bound::Included(LineNumber::Line(v)) => Bound::Included(v),
+                Bound::Excluded(LineNumber::Line(v)) => Bound::Excluded(v),
+                Bound::Included(LineNumber::FromEnd(_)) | Bound::Excluded(LineNumber::FromEnd(_)) => return None,
+            })
+        }
+
+        match self {
+            LineRange::Numeric(start, end) => Some((concrete(*start)?, concrete(*end)?)),
+            LineRange::Pattern { .. } => None,
+        }
+    }
+
+    /// The range's upper bound, for the [`LineRanges::check`] fallback
+    /// logic. An unresolved `Pattern` or `$`-relative endpoint is treated
+    /// as `Unbounded`, since its true extent isn't known until it's been
+    /// resolved -- this keeps `check` from prematurely reporting
+    /// `AfterLastRange` just because a later range hasn't been resolved yet.
+    fn end_bound(&self) -> Bound<usize> {
+        self.concrete_bounds().map_or(Bound::Unbounded, |(_, end)| end)
+    }
+
+    /// Returns `true` if this address needs a resolution pass --
+    /// [`LineRanges::resolve_patterns`] for a `Pattern`, or
+    /// [`LineRanges::resolve`] for a `Numeric` range with a `$`-relative
+    /// endpoint -- before [`Self::concrete_bounds`] will return `Some`.
+    fn needs_resolve(&self) -> bool {
+        match self {
+            LineRange::Pattern { .. } => true,
+            LineRange::Numeric(start, end) => {
+                matches!(start, Bound::Included(LineNumber::FromEnd(_)) | Bound::Excluded(LineNumber::FromEnd(_)))
+                    || matches!(end, Bound::Included(LineNumber::FromEnd(_)) | Bound::Excluded(LineNumber::FromEnd(_)))
+            }
+        }
+    }
+
+    /// Converts any `$`-relative endpoints into concrete line numbers now
+    /// that `total_lines` is known, saturating at line 1 if the offset
+    /// would otherwise fall before the start of the file, and erroring if
+    /// the offset is larger than the file itself. A `Pattern` address, or
+    /// a `Numeric` range with no `$`-relative endpoint, is returned
+    /// unchanged -- `Pattern` addresses are expected to already have been
+    /// turned into `Numeric` ranges by [`LineRanges::resolve_patterns`] by
+    /// this point.
+    fn resolve(&self, total_lines: usize) -> Result<LineRange, LineRangeResolveError> {
+        fn resolve_line_number(n: LineNumber, total_lines: usize) -> Result<usize, LineRangeResolveError> {
+            match n {
+                LineNumber::Line(v) => Ok(v),
+                LineNumber::FromEnd(offset) => {
+                    if offset > total_lines {
+                        return Err(LineRangeResolveError { offset, total_lines });
+                    }
+                    Ok(total_lines.saturating_sub(offset).max(1))
+                }
+            }
+        }
+
+        fn resolve_bound(
+            bound: Bound<LineNumber>,
+            total_lines: usize,
+        ) -> Result<Bound<LineNumber>, LineRangeResolveError> {
+            Ok(match bound {
+                Bound::Unbounded => Bound::Unbounded,
+                Bound::Included(n) => Bound::Included(LineNumber::Line(resolve_line_number(n, total_lines)?)),
+                Bound::Excluded(n) => Bound::Excluded(LineNumber::Line(resolve_line_number(n, total_lines)?)),
+            })
+        }
+
+        match self {
+            LineRange::Numeric(start, end) => Ok(LineRange::Numeric(
+                resolve_bound(*start, total_lines)?,
+                resolve_bound(*end, total_lines)?,
+            )),
+            LineRange::Pattern { .. } => Ok(self.clone()),
+        }
+    }
+}
+
+impl Display for LineRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineRange::Pattern { start, end } => {
+                write!(f, "/{}/", start.as_str().replace('/', "\\/"))?;
+                if let Some(end) = end {
+                    write!(f, ":/{}/", end.as_str().replace('/', "\\/"))?;
+                }
+                Ok(())
+            }
+            LineRange::Numeric(start, end) => {
+                if let (Bound::Included(lower), Bound::Included(upper)) = (start, end) {
+                    if lower == upper {
+                        return write!(f, "{lower}");
+                    }
+                }
+                match start {
+                    Bound::Unbounded => {}
+                    Bound::Included(v) | Bound::Excluded(v) => write!(f, "{v}")?,
+                }
+                write!(f, ":")?;
+                match end {
+                    Bound::Unbounded => {}
+                    Bound::Included(v) | Bound::Excluded(v) => write!(f, "{v}")?,
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Serialize for LineRange {
+    /// Round-trips through the same textual form [`LineRange::parse`]
+    /// accepts, since a compiled [`Regex`] can't derive `Serialize` --
+    /// mirrors [`super::super::config::ConfigString`]'s owned,
+    /// (de)serializable stand-in for an otherwise-borrowed runtime value.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for LineRange {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        LineRange::parse(&raw).map_err(serde::de::Error::custom)
     }
 }
 
 impl Default for LineRange {
     fn default() -> Self {
-        LineRange(Bound::Unbounded, Bound::Unbounded)
+        LineRange::Numeric(Bound::Unbounded, Bound::Unbounded)
+    }
+}
+
+/// Compiles `pattern` with a size limit, so a pathological line-range
+/// address can't blow up compile time.
+fn compile_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .size_limit(PATTERN_SIZE_LIMIT)
+        .build()
+}
+
+/// Parses a single `/.../`-delimited address starting at `s` (which must
+/// begin with `/`), honoring `\/` as an escaped literal slash. Returns the
+/// compiled pattern and whatever text follows the closing `/`.
+fn parse_address(s: &str) -> Option<(Regex, &str)> {
+    let body = s.strip_prefix('/')?;
+    let mut pattern = String::new();
+    let mut chars = body.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if matches!(chars.peek(), Some((_, '/'))) => {
+                chars.next();
+                pattern.push('/');
+            }
+            '/' => {
+                let rest = &body[i + c.len_utf8()..];
+                let regex = compile_pattern(&pattern).ok()?;
+                return Some((regex, rest));
+            }
+            _ => pattern.push(c),
+        }
     }
+    None
 }
 
 impl LineRange {
     pub fn parse(range_raw: &str) -> Result<LineRange, LineRangeParseError> {
-        let mut new_range = LineRange::default();
-
         let invalid = || LineRangeParseError {
             value: range_raw.to_owned(),
         };
 
+        if range_raw.starts_with('/') {
+            let (start, rest) = parse_address(range_raw).ok_or_else(invalid)?;
+            let end = match rest {
+                "" => None,
+                rest => {
+                    let rest = rest.strip_prefix(':').ok_or_else(invalid)?;
+                    let (end, rest) = parse_address(rest).ok_or_else(invalid)?;
+                    if !rest.is_empty() {
+                        return Err(invalid());
+                    }
+                    Some(end)
+                }
+            };
+            return Ok(LineRange::Pattern { start, end });
+        }
+
+        if range_raw == ":" {
+            // What `Display` writes for the all-lines default.
+            return Ok(LineRange::default());
+        }
         if let Some(upper) = range_raw.strip_prefix(':') {
-            new_range.1 = Bound::Included(upper.parse().map_err(|_| invalid())?);
-            return Ok(new_range);
+            return Ok(LineRange::Numeric(
+                Bound::Unbounded,
+                Bound::Included(parse_line_number(upper).ok_or_else(invalid)?),
+            ));
         } else if let Some(lower) = range_raw.strip_suffix(':') {
-            new_range.0 = Bound::Included(lower.parse().map_err(|_| invalid())?);
-            return Ok(new_range);
+            return Ok(LineRange::Numeric(
+                Bound::Included(parse_line_number(lower).ok_or_else(invalid)?),
+                Bound::Unbounded,
+            ));
         }
 
         let mut iter = range_raw.split(':');
@@ -82,31 +318,39 @@ impl LineRange {
 
         match line_numbers {
             (Some(number), None) => {
-                let number = number.parse().map_err(|_| invalid())?;
-                new_range.0 = Bound::Included(number);
-                new_range.1 = Bound::Included(number);
-                Ok(new_range)
+                let number = parse_line_number(number).ok_or_else(invalid)?;
+                Ok(LineRange::Numeric(Bound::Included(number), Bound::Included(number)))
             }
             (Some(left), Some(right)) => {
-                let lower = left.parse().map_err(|_| invalid())?;
-                new_range.0 = Bound::Included(lower);
+                let lower = parse_line_number(left).ok_or_else(invalid)?;
 
-                if let Some(upper) = right.strip_prefix('+') {
+                let new_range = if let Some(upper) = right.strip_prefix('+') {
+                    let LineNumber::Line(lower) = lower else {
+                        return Err(invalid());
+                    };
                     let upper = upper.parse().map_err(|_| invalid())?;
                     let upper = lower.checked_add(upper).ok_or_else(invalid)?;
-                    new_range.1 = Bound::Included(upper);
+                    LineRange::Numeric(
+                        Bound::Included(LineNumber::Line(lower)),
+                        Bound::Included(LineNumber::Line(upper)),
+                    )
                 } else if let Some(upper) = right.strip_prefix('-') {
                     if upper.strip_prefix('+').is_some() {
                         return Err(invalid());
                     }
-                    let upper = upper.parse().map_err(|_| invalid())?;
-                    let upper = lower.checked_sub(upper).ok_or_else(invalid)?;
-                    new_range.0 = Bound::Included(upper);
-                    new_range.1 = Bound::Included(lower);
+                    let LineNumber::Line(lower) = lower else {
+                        return Err(invalid());
+                    };
+                    let upper: usize = upper.parse().map_err(|_| invalid())?;
+                    let lower = lower.checked_sub(upper).ok_or_else(invalid)?;
+                    LineRange::Numeric(
+                        Bound::Included(LineNumber::Line(lower)),
+                        Bound::Included(LineNumber::Line(lower + upper)),
+                    )
                 } else {
-                    let upper = right.parse().map_err(|_| invalid())?;
-                    new_range.1 = Bound::Included(upper);
-                }
+                    let upper = parse_line_number(right).ok_or_else(invalid)?;
+                    LineRange::Numeric(Bound::Included(lower), Bound::Included(upper))
+                };
 
                 Ok(new_range)
             }
@@ -127,9 +371,91 @@ pub(crate) enum RangeCheckResult {
     AfterLastRange,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(transparent)]
-pub struct LineRanges(Vec<LineRange>);
+/// The last line covered by `end`, as a plain `Option<usize>` ordered the
+/// natural way (`None` standing in for "no upper bound"), so bounds that
+/// differ only in `Included`/`Excluded` can still be compared and merged.
+fn effective_end(end: Bound<usize>) -> Option<usize> {
+    match end {
+        Bound::Unbounded => None,
+        Bound::Included(v) => Some(v),
+        Bound::Excluded(v) => Some(v.saturating_sub(1)),
+    }
+}
+
+/// The first line covered by `start`, the `effective_end` counterpart.
+fn effective_start(start: Bound<usize>) -> Option<usize> {
+    match start {
+        Bound::Unbounded => None,
+        Bound::Included(v) => Some(v),
+        Bound::Excluded(v) => v.checked_add(1),
+    }
+}
+
+/// Whether an interval ending at `a_end` can be merged with one starting
+/// at `b_start` immediately after it in sorted order, i.e. `b_start` is at
+/// most one line past `a_end` (so the two intervals are overlapping or
+/// adjacent, with no gap between them).
+fn can_merge(a_end: Bound<usize>, b_start: Bound<usize>) -> bool {
+    match (effective_end(a_end), effective_start(b_start)) {
+        (None, _) | (_, None) => true,
+        (Some(a), Some(b)) => b <= a.saturating_add(1),
+    }
+}
+
+/// The later of two end bounds, for extending an interval during merging
+/// and for tracking the maximum end bound across every address.
+fn max_end_bound(a: Bound<usize>, b: Bound<usize>) -> Bound<usize> {
+    match (effective_end(a), effective_end(b)) {
+        (None, _) | (_, None) => Bound::Unbounded,
+        (Some(ea), Some(eb)) => {
+            if ea >= eb {
+                a
+            } else {
+                b
+            }
+        }
+    }
+}
+
+/// A list of `--line-range`/`--highlight-line` addresses.
+///
+/// Besides the addresses themselves, this keeps two derived views that
+/// make [`Self::check`] an O(log n) lookup instead of a linear scan over
+/// every address for every line of output:
+///
+/// - `intervals`: the disjoint, start-sorted intervals obtained by
+///   coalescing every address's [`LineRange::concrete_bounds`] (merging
+///   one interval into the previous when its start is at most the
+///   previous interval's end `+ 1`). An unresolved `Pattern` or
+///   `$`-relative address contributes no interval here, since it can't
+///   be compared against a line number yet.
+/// - `max_end`: the largest [`LineRange::end_bound`] across *every*
+///   address, resolved or not -- an unresolved address reports
+///   `Unbounded` here, so [`Self::check`]'s `BeforeOrBetweenRanges`
+///   fallback still treats the whole list as open-ended until the
+///   address in question is actually resolved.
+#[derive(Debug, Clone)]
+pub struct LineRanges {
+    ranges: Vec<LineRange>,
+    intervals: Vec<(Bound<usize>, Bound<usize>)>,
+    max_end: Option<Bound<usize>>,
+}
+
+impl Serialize for LineRanges {
+    /// Serializes just the address list, the same shape this type used to
+    /// have as a `#[serde(transparent)]` tuple struct -- `intervals` and
+    /// `max_end` are recomputed from it on deserialize instead of being
+    /// stored.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.ranges.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LineRanges {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<LineRange>::deserialize(deserializer).map(LineRanges::from)
+    }
+}
 
 impl LineRanges {
     pub fn none() -> LineRanges {
@@ -140,27 +466,184 @@ impl LineRanges {
         LineRanges::from(vec![LineRange::default()])
     }
 
-    pub fn from(mut ranges: Vec<LineRange>) -> LineRanges {
-        ranges.sort_by(|a, b| match (a.end_bound(), b.end_bound()) {
-            (Bound::Unbounded, Bound::Unbounded) => Ordering::Equal,
-            (_, Bound::Unbounded) => Ordering::Less,
-            (Bound::Unbounded, _) => Ordering::Greater,
-            (Bound::Included(left), Bound::Included(right))
-            | (Bound::Excluded(left), Bound::Excluded(right)) => left.cmp(right),
-            (Bound::Included(left), Bound::Excluded(right)) => left
-                .checked_add(1)
-                .map_or(Ordering::Greater, |left| left.cmp(right)),
-            (Bound::Excluded(left), Bound::Included(right)) => right
-                .checked_add(1)
-                .map_or(Ordering::Less, |right| left.cmp(&right)),
-        });
-        LineRanges(ranges)
+    /// Coalesces `ranges`'s concrete bounds into disjoint, start-sorted
+    /// intervals, and tracks the maximum end bound across all of `ranges`
+    /// (including any that are still unresolved) -- see the type docs.
+    fn coalesce(ranges: &[LineRange]) -> (Vec<(Bound<usize>, Bound<usize>)>, Option<Bound<usize>>) {
+        let max_end = ranges.iter().map(LineRange::end_bound).reduce(max_end_bound);
+
+        let mut concrete: Vec<(Bound<usize>, Bound<usize>)> =
+            ranges.iter().filter_map(LineRange::concrete_bounds).collect();
+        concrete.sort_by_key(|&(start, _)| effective_start(start));
+
+        let mut intervals: Vec<(Bound<usize>, Bound<usize>)> = Vec::with_capacity(concrete.len());
+        for (start, end) in concrete {
+            match intervals.last_mut() {
+                Some((_, last_end)) if can_merge(*last_end, start) => {
+                    *last_end = max_end_bound(*last_end, end);
+                }
+                _ => intervals.push((start, end)),
+            }
+        }
+
+        (intervals, max_end)
     }
 
+    pub fn from(ranges: Vec<LineRange>) -> LineRanges {
+        let (intervals, max_end) = LineRanges::coalesce(&ranges);
+        LineRanges { ranges, intervals, max_end }
+    }
+
+    /// Builds the union of `[line - radius, line + radius]` (clamped to
+    /// `1..`) for each `line` in `focus_lines`, like `grep -C`. Overlapping
+    /// or adjacent windows don't need to be merged up front: [`Self::check`]
+    /// only cares whether a line is covered by *any* range, so two windows
+    /// that touch or overlap simply leave no gap between them, and
+    /// `print_file_ranges` only emits a snip where an actual gap exists.
+    pub fn context_window(focus_lines: &[usize], radius: usize) -> LineRanges {
+        LineRanges::from(
+            focus_lines
+                .iter()
+                .map(|&line| {
+                    let lower = line.saturating_sub(radius).max(1);
+                    let upper = line.saturating_add(radius);
+                    LineRange::Numeric(
+                        Bound::Included(LineNumber::Line(lower)),
+                        Bound::Included(LineNumber::Line(upper)),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns `true` if any range needs a resolution pass before
+    /// [`Self::check`] will do anything useful with it: either an
+    /// unresolved sed-style [`LineRange::Pattern`] address (see
+    /// [`Self::resolve_patterns`]), or a `$`-relative endpoint (see
+    /// [`Self::resolve`]).
+    pub fn needs_resolve(&self) -> bool {
+        self.ranges.iter().any(LineRange::needs_resolve)
+    }
+
+    /// Turns every sed-style [`LineRange::Pattern`] address into a concrete
+    /// [`LineRange::Numeric`] range by walking `lines` once. A `start`
+    /// match (while the address is inactive) opens a block, recording the
+    /// line it started on; if the address also carries an `end` pattern,
+    /// the block closes -- inclusive of the matching line -- on the first
+    /// subsequent match, or extends to the file's last line if `end` never
+    /// matches. A `start`-only address (no `end`) instead emits one
+    /// single-line range per match, like `sed -n '/pattern/p'`. Plain
+    /// `Numeric` ranges pass through unchanged. The result is an ordinary
+    /// numeric [`LineRanges`], so [`Self::check`] needs no special casing.
+    pub fn resolve_patterns<'a>(&self, lines: impl Iterator<Item = &'a str>) -> LineRanges {
+        if !self.ranges.iter().any(|r| matches!(r, LineRange::Pattern { .. })) {
+            return self.clone();
+        }
+
+        let mut resolved: Vec<LineRange> = self
+            .ranges
+            .iter()
+            .filter(|r| !matches!(r, LineRange::Pattern { .. }))
+            .cloned()
+            .collect();
+
+        struct OpenBlock<'p> {
+            start: &'p Regex,
+            end: Option<&'p Regex>,
+            open_since: Option<usize>,
+        }
+
+        let mut blocks: Vec<OpenBlock> = self
+            .ranges
+            .iter()
+            .filter_map(|r| match r {
+                LineRange::Pattern { start, end } => Some(OpenBlock {
+                    start,
+                    end: end.as_ref(),
+                    open_since: None,
+                }),
+                LineRange::Numeric(..) => None,
+            })
+            .collect();
+
+        let mut last_line = 0;
+        for (index, line) in lines.enumerate() {
+            let line_number = index + 1;
+            last_line = line_number;
+            for block in &mut blocks {
+                match block.open_since {
+                    Some(started) if block.end.is_some_and(|end| end.is_match(line)) => {
+                        resolved.push(LineRange::Numeric(
+                            Bound::Included(LineNumber::Line(started)),
+                            Bound::Included(LineNumber::Line(line_number)),
+                        ));
+                        block.open_since = None;
+                    }
+                    None if block.start.is_match(line) => {
+                        if block.end.is_none() {
+                            resolved.push(LineRange::Numeric(
+                                Bound::Included(LineNumber::Line(line_number)),
+                                Bound::Included(LineNumber::Line(line_number)),
+                            ));
+                        } else {
+                            block.open_since = Some(line_number);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for block in blocks {
+            if let Some(started) = block.open_since {
+                resolved.push(LineRange::Numeric(
+                    Bound::Included(LineNumber::Line(started)),
+                    Bound::Included(LineNumber::Line(last_line.max(started))),
+                ));
+            }
+        }
+
+        LineRanges::from(resolved)
+    }
+
+    /// Converts every `$`-relative endpoint into a concrete line number now
+    /// that the file's `total_lines` is known -- see [`LineRange::resolve`].
+    /// Call this once the line count becomes available, after
+    /// [`Self::resolve_patterns`] if any addresses are sed-style as well.
+    pub fn resolve(&self, total_lines: usize) -> Result<LineRanges, LineRangeResolveError> {
+        Ok(LineRanges::from(
+            self.ranges
+                .iter()
+                .map(|r| r.resolve(total_lines))
+                .collect::<Result<Vec<_>, _>>()?,
+        ))
+    }
+
+    /// Checks whether `line` is covered by any address, in O(log n) via a
+    /// binary search over `intervals` -- see the type docs.
     pub(crate) fn check(&self, line: usize) -> RangeCheckResult {
-        if self.0.iter().any(|r| r.contains(&line)) {
+        // `intervals` is sorted by start, so every interval before the
+        // partition point starts at or before `line`; since they're also
+        // disjoint, only the last one of those (if any) can possibly
+        // contain `line`.
+        let candidate = self.intervals[..self.intervals.partition_point(|&(start, _)| {
+            match start {
+                Bound::Unbounded => true,
+                Bound::Included(v) => v <= line,
+                Bound::Excluded(v) => v < line,
+            }
+        })]
+        .last();
+
+        let in_range = candidate.is_some_and(|&(_, end)| match end {
+            Bound::Unbounded => true,
+            Bound::Included(v) => line <= v,
+            Bound::Excluded(v) => line < v,
+        });
+
+        if in_range {
             RangeCheckResult::InRange
-        } else if match self.0.last().map(|range| range.1) {
+        } else if match self.max_end {
             None => false,
             Some(Bound::Included(upper)) => line <= upper,
             Some(Bound::Excluded(upper)) => line < upper,
@@ -198,32 +681,52 @@ mod test {
     use super::*;
     use std::ops::Bound::*;
 
+    fn numeric_bounds(range: &LineRange) -> (Bound<usize>, Bound<usize>) {
+        fn to_usize(bound: Bound<LineNumber>) -> Bound<usize> {
+            match bound {
+                Bound::Unbounded => Bound::Unbounded,
+                Bound::Included(LineNumber::Line(v)) => Bound::Included(v),
+                Bound::Excluded(LineNumber::Line(v)) => Bound::Excluded(v),
+                Bound::Included(LineNumber::FromEnd(_)) | Bound::Excluded(LineNumber::FromEnd(_)) => {
+                    panic!("expected a plain numeric bound")
+                }
+            }
+        }
+        match range {
+            LineRange::Numeric(start, end) => (to_usize(*start), to_usize(*end)),
+            LineRange::Pattern { .. } => panic!("expected a numeric range"),
+        }
+    }
+
+    fn from_end_bounds(range: &LineRange) -> (Bound<LineNumber>, Bound<LineNumber>) {
+        match range {
+            LineRange::Numeric(start, end) => (*start, *end),
+            LineRange::Pattern { .. } => panic!("expected a numeric range"),
+        }
+    }
+
     #[test]
     fn test_parse_full() {
         let range = LineRange::parse("40:50").expect("Shouldn't fail on test!");
-        assert_eq!(Included(40), range.0);
-        assert_eq!(Included(50), range.1);
+        assert_eq!((Included(40), Included(50)), numeric_bounds(&range));
     }
 
     #[test]
     fn test_parse_partial_min() {
         let range = LineRange::parse(":50").expect("Shouldn't fail on test!");
-        assert_eq!(Unbounded, range.0);
-        assert_eq!(Included(50), range.1);
+        assert_eq!((Unbounded, Included(50)), numeric_bounds(&range));
     }
 
     #[test]
     fn test_parse_partial_max() {
         let range = LineRange::parse("40:").expect("Shouldn't fail on test!");
-        assert_eq!(Included(40), range.0);
-        assert_eq!(Unbounded, range.1);
+        assert_eq!((Included(40), Unbounded), numeric_bounds(&range));
     }
 
     #[test]
     fn test_parse_single() {
         let range = LineRange::parse("40").expect("Shouldn't fail on test!");
-        assert_eq!(Included(40), range.0);
-        assert_eq!(Included(40), range.1);
+        assert_eq!((Included(40), Included(40)), numeric_bounds(&range));
     }
 
     #[test]
@@ -239,8 +742,7 @@ mod test {
     #[test]
     fn test_parse_plus() {
         let range = LineRange::parse("40:+10").expect("Shouldn't fail on test!");
-        assert_eq!(Included(40), range.0);
-        assert_eq!(Included(50), range.1);
+        assert_eq!((Included(40), Included(50)), numeric_bounds(&range));
     }
 
     #[test]
@@ -262,18 +764,15 @@ mod test {
     #[test]
     fn test_parse_minus_success() {
         let range = LineRange::parse("40:-10").expect("Shouldn't fail on test!");
-        assert_eq!(Included(30), range.0);
-        assert_eq!(Included(40), range.1);
+        assert_eq!((Included(30), Included(40)), numeric_bounds(&range));
     }
 
     #[test]
     fn test_parse_minus_edge_cases_success() {
         let range = LineRange::parse("5:-4").expect("Shouldn't fail on test!");
-        assert_eq!(Included(1), range.0);
-        assert_eq!(Included(5), range.1);
+        assert_eq!((Included(1), Included(5)), numeric_bounds(&range));
         let range = LineRange::parse("5:-5").expect("Shouldn't fail on test!");
-        assert_eq!(Included(0), range.0);
-        assert_eq!(Included(5), range.1);
+        assert_eq!((Included(0), Included(5)), numeric_bounds(&range));
         let range = LineRange::parse("5:-100");
         assert!(range.is_err());
     }
@@ -288,6 +787,111 @@ mod test {
         assert!(range.is_err());
     }
 
+    #[test]
+    fn test_parse_pattern_single() {
+        let range = LineRange::parse("/TODO/").expect("Shouldn't fail on test!");
+        assert!(matches!(range, LineRange::Pattern { end: None, .. }));
+    }
+
+    #[test]
+    fn test_parse_pattern_range() {
+        let range = LineRange::parse("/TODO/:/DONE/").expect("Shouldn't fail on test!");
+        assert!(matches!(range, LineRange::Pattern { end: Some(_), .. }));
+    }
+
+    #[test]
+    fn test_parse_pattern_escaped_slash() {
+        let range = LineRange::parse(r"/a\/b/").expect("Shouldn't fail on test!");
+        let LineRange::Pattern { start, .. } = range else {
+            panic!("expected a pattern range")
+        };
+        assert!(start.is_match("a/b"));
+    }
+
+    #[test]
+    fn test_parse_pattern_fail() {
+        assert!(LineRange::parse("/unterminated").is_err());
+        assert!(LineRange::parse("/a/:50").is_err());
+        assert!(LineRange::parse("/a/:").is_err());
+        assert!(LineRange::parse("/a/:/b/c").is_err());
+    }
+
+    #[test]
+    fn test_parse_dollar_single() {
+        let range = LineRange::parse("$").expect("Shouldn't fail on test!");
+        assert_eq!(
+            (Included(LineNumber::FromEnd(0)), Included(LineNumber::FromEnd(0))),
+            from_end_bounds(&range)
+        );
+    }
+
+    #[test]
+    fn test_parse_dollar_offset() {
+        let range = LineRange::parse("$-9").expect("Shouldn't fail on test!");
+        assert_eq!(
+            (Included(LineNumber::FromEnd(9)), Included(LineNumber::FromEnd(9))),
+            from_end_bounds(&range)
+        );
+    }
+
+    #[test]
+    fn test_parse_dollar_range() {
+        let range = LineRange::parse("120:$").expect("Shouldn't fail on test!");
+        assert_eq!(
+            (Included(LineNumber::Line(120)), Included(LineNumber::FromEnd(0))),
+            from_end_bounds(&range)
+        );
+    }
+
+    #[test]
+    fn test_parse_dollar_tail() {
+        let range = LineRange::parse("$-9:$").expect("Shouldn't fail on test!");
+        assert_eq!(
+            (Included(LineNumber::FromEnd(9)), Included(LineNumber::FromEnd(0))),
+            from_end_bounds(&range)
+        );
+    }
+
+    #[test]
+    fn test_parse_dollar_partial_min() {
+        let range = LineRange::parse(":$").expect("Shouldn't fail on test!");
+        assert_eq!((Unbounded, Included(LineNumber::FromEnd(0))), from_end_bounds(&range));
+    }
+
+    #[test]
+    fn test_parse_dollar_fail() {
+        assert!(LineRange::parse("$-z").is_err());
+        assert!(LineRange::parse("$-").is_err());
+        assert!(LineRange::parse("40:$+10").is_err());
+    }
+
+    #[test]
+    fn test_resolve_end_relative_tail() {
+        let range = LineRange::parse("$-9:$").expect("Shouldn't fail on test!");
+        let resolved = range.resolve(100).expect("Shouldn't fail on test!");
+        assert_eq!((Included(91), Included(100)), numeric_bounds(&resolved));
+    }
+
+    #[test]
+    fn test_resolve_end_relative_saturates_at_line_one() {
+        let range = LineRange::parse("$-5").expect("Shouldn't fail on test!");
+        let resolved = range.resolve(5).expect("Shouldn't fail on test!");
+        assert_eq!((Included(1), Included(1)), numeric_bounds(&resolved));
+    }
+
+    #[test]
+    fn test_resolve_end_relative_errors_past_start() {
+        let range = LineRange::parse("$-10").expect("Shouldn't fail on test!");
+        assert!(range.resolve(5).is_err());
+    }
+
+    #[test]
+    fn test_resolve_end_relative_leaves_plain_numbers_untouched() {
+        let range = LineRange::parse("3:8").expect("Shouldn't fail on test!");
+        let resolved = range.resolve(100).expect("Shouldn't fail on test!");
+        assert_eq!((Included(3), Included(8)), numeric_bounds(&resolved));
+    }
+
     fn ranges(rs: &[&str]) -> LineRanges {
         LineRanges::from(rs.iter().map(|r| LineRange::parse(r).unwrap()).collect())
     }
@@ -347,4 +951,122 @@ mod test {
 
         assert_ne!(RangeCheckResult::InRange, ranges.check(1));
     }
+
+    #[test]
+    fn test_context_window_merges_overlapping_windows() {
+        // Focus lines 10 and 13 with radius 2 give windows 8:12 and 11:15,
+        // which overlap and should leave no gap between them.
+        let ranges = LineRanges::context_window(&[10, 13], 2);
+
+        assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, ranges.check(7));
+        for line in 8..=15 {
+            assert_eq!(RangeCheckResult::InRange, ranges.check(line));
+        }
+        assert_eq!(RangeCheckResult::AfterLastRange, ranges.check(16));
+    }
+
+    #[test]
+    fn test_context_window_keeps_separate_windows_separate() {
+        let ranges = LineRanges::context_window(&[5, 50], 1);
+
+        assert_eq!(RangeCheckResult::InRange, ranges.check(5));
+        assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, ranges.check(20));
+        assert_eq!(RangeCheckResult::InRange, ranges.check(50));
+    }
+
+    #[test]
+    fn test_context_window_clamps_to_line_one() {
+        let ranges = LineRanges::context_window(&[2], 5);
+
+        assert_eq!(RangeCheckResult::InRange, ranges.check(1));
+    }
+
+    fn lines_of(text: &str) -> Vec<&str> {
+        text.lines().collect()
+    }
+
+    #[test]
+    fn test_resolve_patterns_single_pattern_selects_every_match() {
+        let ranges = ranges(&["/fn /"]);
+        let text = "fn a() {}\nlet x = 1;\nfn b() {}\n";
+        let resolved = ranges.resolve_patterns(lines_of(text).into_iter());
+
+        assert_eq!(RangeCheckResult::InRange, resolved.check(1));
+        assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, resolved.check(2));
+        assert_eq!(RangeCheckResult::InRange, resolved.check(3));
+    }
+
+    #[test]
+    fn test_resolve_patterns_start_end_pair_is_inclusive() {
+        let ranges = ranges(&["/TODO/:/DONE/"]);
+        let text = "before\nTODO: thing\nmiddle\nDONE\nafter\n";
+        let resolved = ranges.resolve_patterns(lines_of(text).into_iter());
+
+        assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, resolved.check(1));
+        for line in 2..=4 {
+            assert_eq!(RangeCheckResult::InRange, resolved.check(line));
+        }
+        assert_eq!(RangeCheckResult::AfterLastRange, resolved.check(5));
+    }
+
+    #[test]
+    fn test_resolve_patterns_unmatched_end_extends_to_eof() {
+        let ranges = ranges(&["/TODO/:/DONE/"]);
+        let text = "before\nTODO: thing\nmiddle\n";
+        let resolved = ranges.resolve_patterns(lines_of(text).into_iter());
+
+        assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, resolved.check(1));
+        assert_eq!(RangeCheckResult::InRange, resolved.check(2));
+        assert_eq!(RangeCheckResult::InRange, resolved.check(3));
+    }
+
+    #[test]
+    fn test_resolve_patterns_reopens_disjoint_blocks() {
+        let ranges = ranges(&["/START/:/END/"]);
+        let text = "START\na\nEND\nb\nSTART\nc\nEND\n";
+        let resolved = ranges.resolve_patterns(lines_of(text).into_iter());
+
+        for line in 1..=3 {
+            assert_eq!(RangeCheckResult::InRange, resolved.check(line));
+        }
+        assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, resolved.check(4));
+        for line in 5..=7 {
+            assert_eq!(RangeCheckResult::InRange, resolved.check(line));
+        }
+    }
+
+    #[test]
+    fn test_resolve_patterns_leaves_numeric_ranges_untouched() {
+        let ranges = ranges(&["3:8"]);
+        let resolved = ranges.resolve_patterns(lines_of("a\nb\nc\n").into_iter());
+
+        assert_eq!(RangeCheckResult::InRange, resolved.check(5));
+        assert_eq!(RangeCheckResult::AfterLastRange, resolved.check(9));
+    }
+
+    #[test]
+    fn test_unresolved_pattern_matches_nothing() {
+        let ranges = ranges(&["/TODO/"]);
+
+        assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, ranges.check(1));
+        assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, ranges.check(1000));
+    }
+
+    #[test]
+    fn test_unresolved_end_relative_matches_nothing() {
+        let ranges = ranges(&["$-9:$"]);
+
+        assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, ranges.check(1));
+        assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, ranges.check(1000));
+    }
+
+    #[test]
+    fn test_line_ranges_resolve_converts_end_relative_endpoints() {
+        let ranges = ranges(&["$-9:$"]).resolve(100).expect("Shouldn't fail on test!");
+
+        assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, ranges.check(90));
+        for line in 91..=100 {
+            assert_eq!(RangeCheckResult::InRange, ranges.check(line));
+        }
+    }
 }
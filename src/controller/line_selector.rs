@@ -0,0 +1,63 @@
+use std::collections::VecDeque;
+
+use super::content_filter::ContentFilter;
+
+/// Applies an optional [`ContentFilter`] to a stream of lines that are
+/// already known to be within the visible `LineRanges`, deciding which of
+/// them should actually be rendered. Shared by [`super::Controller`]'s
+/// byte-oriented and visitor-driven rendering paths so both apply identical
+/// range/filter semantics.
+pub(crate) struct LineSelector<'a> {
+    content_filter: Option<&'a ContentFilter>,
+    before_buffer: VecDeque<(usize, Vec<u8>)>,
+    after_countdown: usize,
+}
+
+impl<'a> LineSelector<'a> {
+    pub(crate) fn new(content_filter: Option<&'a ContentFilter>) -> Self {
+        LineSelector {
+            content_filter,
+            before_buffer: VecDeque::new(),
+            after_countdown: 0,
+        }
+    }
+
+    /// Feeds one line known to be within the visible ranges. Returns the
+    /// lines that are now ready to be emitted, in order, each paired with
+    /// whether the content filter selected it.
+    pub(crate) fn feed_in_range(
+        &mut self,
+        line_number: usize,
+        bytes: Vec<u8>,
+    ) -> Vec<(usize, Vec<u8>, bool)> {
+        let Some(filter) = self.content_filter else {
+            return vec![(line_number, bytes, true)];
+        };
+
+        let mut ready = Vec::new();
+        if filter.pattern.is_match(&bytes) {
+            ready.extend(self.before_buffer.drain(..).map(|(n, b)| (n, b, true)));
+            self.after_countdown = filter.after_context;
+            ready.push((line_number, bytes, true));
+        } else if self.after_countdown > 0 {
+            self.after_countdown -= 1;
+            ready.push((line_number, bytes, true));
+        } else {
+            self.before_buffer.push_back((line_number, bytes));
+            if self.before_buffer.len() > filter.before_context {
+                let (number, bytes) = self.before_buffer.pop_front().unwrap();
+                ready.push((number, bytes, false));
+            }
+        }
+        ready
+    }
+
+    /// Flushes any lines still held for "before" context once reading is
+    /// done, since they never found a later match.
+    pub(crate) fn finish(self) -> Vec<(usize, Vec<u8>, bool)> {
+        self.before_buffer
+            .into_iter()
+            .map(|(number, bytes)| (number, bytes, false))
+            .collect()
+    }
+}
@@ -1,17 +1,28 @@
+use std::collections::{BTreeMap, VecDeque};
 use std::io::{self, IsTerminal, Write};
+use std::num::NonZeroUsize;
+use std::sync::{mpsc, Mutex};
 
 use clircle::{Clircle, Identifier};
 use nu_ansi_term::Color;
+use syntect::easy::HighlightLines;
 
-use crate::assets::HighlightingAssets;
+use crate::assets::{HighlightingAssets, SyntaxReferenceInSet};
 use crate::config::ConsolidatedConfig as Config;
 use crate::error::*;
-use crate::input::{Input, OpenedInput};
+use crate::input::{decode_line, ContentType, Input, InputDescription, InputKind, OpenedInput};
 use crate::output::OutputType;
-use crate::printer::{InteractivePrinter, Printer, SimplePrinter};
+use crate::printer::{side_by_side, InteractivePrinter, Printer, SimplePrinter};
+use diff::DiffLineKind;
 use line_range::{LineRanges, RangeCheckResult};
+use line_selector::LineSelector;
+use visitor::{HeaderEvent, LineEvent, Visitor};
 
+pub mod content_filter;
+pub(crate) mod diff;
 pub mod line_range;
+mod line_selector;
+pub mod visitor;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ErrorHandling {
@@ -88,6 +99,43 @@ impl<'a> Controller<'a> {
         .then(clircle::Identifier::stdout)
         .flatten();
 
+        if self.config.side_by_side {
+            return match (output_buffer.as_mut(), output_type.as_mut()) {
+                (Some(buffer), None) => self.run_side_by_side(inputs, *buffer),
+                (None, Some(output_type)) if output_type.is_pager() => {
+                    self.run_side_by_side(inputs, output_type.pager_handle().unwrap())
+                }
+                (None, Some(output_type)) if output_type.is_stdout() => {
+                    self.run_side_by_side(inputs, output_type.stdout_handle().unwrap())
+                }
+                _ => unreachable!(),
+            };
+        }
+
+        // Concurrent rendering needs each input to be independent of what
+        // came before it (ruled out by `loop_through`'s incremental `cat`
+        // behavior) and needs stdout's identity to stay stable across the
+        // whole run (ruled out when `clircle` cycle-detection is tied to the
+        // real fd), so fall back to the serial path in either case.
+        if self.config.concurrency > 1
+            && !self.config.loop_through
+            && stdout_identifier.is_none()
+            && inputs.len() > 1
+        {
+            return match (output_buffer.as_mut(), output_type.as_mut()) {
+                (Some(buffer), None) => self.run_concurrent(inputs, *buffer, &handle_error),
+                (None, Some(output_type)) if output_type.is_pager() => {
+                    self.run_concurrent(inputs, output_type.pager_handle().unwrap(), &handle_error)
+                }
+                (None, Some(output_type)) if output_type.is_stdout() => self.run_concurrent(
+                    inputs,
+                    output_type.stdout_handle().unwrap(),
+                    &handle_error,
+                ),
+                _ => unreachable!(),
+            };
+        }
+
         let mut no_errors: bool = true;
         let mut stderr = io::stderr();
 
@@ -139,6 +187,196 @@ impl<'a> Controller<'a> {
         })
     }
 
+    /// Renders `inputs` using a bounded pool of worker threads, each opening
+    /// and highlighting one input into its own in-memory buffer, then
+    /// reassembles the results in the original order before flushing them to
+    /// `writer`. A small reorder buffer (keyed by input index) holds results
+    /// that finished out of order until the ones before them have flushed.
+    fn run_concurrent(
+        &self,
+        inputs: Vec<Input>,
+        writer: &mut dyn Write,
+        handle_error: &impl Fn(Error, &mut dyn Write, bool) -> ErrorHandling,
+    ) -> Result<ErrorHandling> {
+        let total = inputs.len();
+        let concurrency = self.config.concurrency.min(total);
+        let work: Mutex<VecDeque<(usize, Input)>> =
+            Mutex::new(inputs.into_iter().enumerate().collect());
+        let (result_tx, result_rx) = mpsc::channel::<(usize, Result<Vec<u8>>)>();
+
+        std::thread::scope(|scope| {
+            for _ in 0..concurrency {
+                let work = &work;
+                let result_tx = result_tx.clone();
+                scope.spawn(move || {
+                    while let Some((index, input)) = work.lock().unwrap().pop_front() {
+                        let mut buffer = Vec::new();
+                        let result = self
+                            .print_input(input, &mut buffer, None, index == 0)
+                            .map(|()| buffer);
+                        if result_tx.send((index, result)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(result_tx);
+
+            let mut pending = BTreeMap::new();
+            let mut next_index = 0;
+            let mut no_errors = true;
+            let is_terminal = io::stdout().is_terminal();
+
+            for (index, result) in result_rx {
+                pending.insert(index, result);
+                while let Some(result) = pending.remove(&next_index) {
+                    match result {
+                        Ok(buffer) => writer.write_all(&buffer)?,
+                        Err(error) => {
+                            match handle_error(error, writer, is_terminal) {
+                                ErrorHandling::Handled | ErrorHandling::NoError => (),
+                                ErrorHandling::SilentFail => {
+                                    return Ok(ErrorHandling::SilentFail)
+                                }
+                            }
+                            no_errors = false;
+                        }
+                    }
+                    next_index += 1;
+                }
+            }
+
+            Ok(if no_errors {
+                ErrorHandling::NoError
+            } else {
+                ErrorHandling::Handled
+            })
+        })
+    }
+
+    /// Renders `inputs` as two gutter-numbered columns side by side: with
+    /// `--diff`, `inputs` must be the single diff to split into its
+    /// removed/added halves (see [`Self::split_diff_input`]); otherwise
+    /// `inputs` must be exactly the two files to compare line-for-line. Each
+    /// half is rendered independently through the ordinary single-column
+    /// pipeline at half `term_width` with wrapping forced off, then the two
+    /// renders are zipped row-by-row by [`side_by_side::combine`] — forcing
+    /// off wrapping keeps that zip equivalent to aligning by logical line
+    /// number, since otherwise a long line wrapping to extra rows on one
+    /// side but not the other would desync every row after it.
+    fn run_side_by_side(&self, inputs: Vec<Input>, writer: &mut dyn Write) -> Result<ErrorHandling> {
+        let (left, right) = if self.config.diff {
+            let mut inputs = inputs.into_iter();
+            let input = inputs
+                .next()
+                .ok_or_else(|| Error::msg("--side-by-side with --diff needs exactly one input"))?;
+            if inputs.next().is_some() {
+                return Err(Error::msg(
+                    "--side-by-side with --diff needs exactly one input",
+                ));
+            }
+            self.split_diff_input(input)?
+        } else {
+            let mut inputs = inputs.into_iter();
+            match (inputs.next(), inputs.next()) {
+                (Some(left), Some(right)) if inputs.next().is_none() => (left, right),
+                _ => {
+                    return Err(Error::msg(
+                        "--side-by-side needs exactly two inputs, unless combined with --diff",
+                    ))
+                }
+            }
+        };
+
+        let half_width =
+            NonZeroUsize::new((usize::from(self.config.term_width) / 2).max(1)).unwrap();
+        let mut half_config = self.config.clone();
+        half_config.term_width = half_width;
+        half_config.side_by_side = false;
+        // `side_by_side::combine` aligns the two halves by physical output
+        // row; that's only the same thing as aligning by logical line
+        // number if wrapping can't make one side's row count diverge from
+        // the other's by turning a single long line into several rows, so
+        // wrapping is always off here, same as upstream bat.
+        half_config.wrapping_mode = crate::printer::WrappingMode::NoWrapping;
+        let half_controller = Controller::new(&half_config, self.assets);
+
+        let mut no_errors = true;
+        let mut left_buffer = Vec::new();
+        let mut right_buffer = Vec::new();
+        let is_terminal = io::stdout().is_terminal();
+        for (input, buffer) in [(left, &mut left_buffer), (right, &mut right_buffer)] {
+            if let Err(error) = half_controller.print_input(input, buffer, None, true) {
+                match default_error_handler(error, writer, is_terminal) {
+                    ErrorHandling::Handled | ErrorHandling::NoError => (),
+                    ErrorHandling::SilentFail => return Ok(ErrorHandling::SilentFail),
+                }
+                no_errors = false;
+            }
+        }
+
+        side_by_side::combine(
+            &left_buffer,
+            &right_buffer,
+            usize::from(half_width),
+            usize::from(half_width),
+            half_config.style_components.grid(),
+            writer,
+        )?;
+
+        Ok(if no_errors {
+            ErrorHandling::NoError
+        } else {
+            ErrorHandling::Handled
+        })
+    }
+
+    /// Splits a single unified-diff input into its removed/added halves for
+    /// [`Self::run_side_by_side`]: context and header/meta lines go to both
+    /// halves (so each column's syntax highlighting and hunk boundaries stay
+    /// intact), while `+`/`-` lines go only to the half they belong to.
+    fn split_diff_input(&self, input: Input) -> Result<(Input, Input)> {
+        let mut opened = input.open(
+            None,
+            #[cfg(feature = "lessopen")]
+            !self.config.no_lessopen,
+            self.config.encoding,
+        )?;
+
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        let mut line_buffer = Vec::new();
+        while opened.reader.read_line(&mut line_buffer).with_context(|| {
+            let description = &opened.description;
+            if let Some(name) = description.name.as_ref() {
+                format!("failed to read '{}'", name.to_string_lossy())
+            } else {
+                format!("failed to read {}", description.kind)
+            }
+        })? {
+            match diff::classify_diff_line(&line_buffer) {
+                DiffLineKind::Added => added.extend_from_slice(&line_buffer),
+                DiffLineKind::Removed => removed.extend_from_slice(&line_buffer),
+                DiffLineKind::FileMarker | DiffLineKind::HunkHeader | DiffLineKind::Meta
+                | DiffLineKind::Context => {
+                    removed.extend_from_slice(&line_buffer);
+                    added.extend_from_slice(&line_buffer);
+                }
+            }
+            line_buffer.clear();
+        }
+
+        let name = opened.description.name;
+        let to_input = |bytes: Vec<u8>, side: &str| Input {
+            kind: InputKind::CustomReader(Box::new(io::Cursor::new(bytes))),
+            description: InputDescription {
+                name: name.clone(),
+                kind: side.to_owned(),
+            },
+        };
+        Ok((to_input(removed, "removed"), to_input(added, "added")))
+    }
+
     fn print_input<W: Write>(
         &self,
         input: Input,
@@ -150,6 +388,7 @@ impl<'a> Controller<'a> {
             stdout_identifier,
             #[cfg(feature = "lessopen")]
             !self.config.no_lessopen,
+            self.config.encoding,
         )?;
 
         if self.config.loop_through {
@@ -174,14 +413,36 @@ impl<'a> Controller<'a> {
         }
 
         if input.reader.content_type.is_some() {
-            let line_ranges = &self.config.visible_lines.0;
-            self.print_file_ranges(printer, writer, input, line_ranges)?;
+            let line_ranges = self.resolve_visible_lines(input)?;
+            self.print_file_ranges(printer, writer, input, &line_ranges)?;
         }
         printer.print_footer(writer, input)?;
 
         Ok(())
     }
 
+    /// Resolves any sed-style regex addresses and `$`-relative endpoints in
+    /// `visible_lines` against `input`'s actual content before the
+    /// line-by-line `LineRanges::check` loop walks it; plain numeric ranges
+    /// are returned unchanged without reading ahead.
+    fn resolve_visible_lines(&self, input: &mut OpenedInput) -> Result<LineRanges> {
+        let visible_lines = &self.config.visible_lines.0;
+        if !visible_lines.needs_resolve() {
+            return Ok(visible_lines.clone());
+        }
+
+        let lines = input.reader.peek_all_lines_lossy().with_context(|| {
+            let description = &input.description;
+            if let Some(name) = description.name.as_ref() {
+                format!("failed to read '{}'", name.to_string_lossy())
+            } else {
+                format!("failed to read {}", description.kind)
+            }
+        })?;
+        let resolved = visible_lines.resolve_patterns(lines.iter().map(String::as_str));
+        Ok(resolved.resolve(lines.len())?)
+    }
+
     fn print_file_ranges<W: Write>(
         &self,
         printer: &mut impl Printer<W>,
@@ -195,10 +456,33 @@ impl<'a> Controller<'a> {
         let mut mid_range: bool = false;
 
         let style_snip = self.config.style_components.snip();
+        let mut selector = LineSelector::new(self.config.content_filter.as_ref());
+
+        macro_rules! emit_in_range {
+            ($number:expr, $bytes:expr) => {{
+                if style_snip {
+                    if first_range {
+                        first_range = false;
+                        mid_range = true;
+                    } else if !mid_range {
+                        mid_range = true;
+                        printer.print_snip(writer)?;
+                    }
+                }
+                printer.print_line(false, writer, $number, $bytes)?;
+            }};
+        }
+        macro_rules! emit_out_of_range {
+            ($number:expr, $bytes:expr) => {{
+                // Call the printer in case we need to call the syntax highlighter
+                // for this line. However, set `out_of_range` to `true`.
+                printer.print_line(true, writer, $number, $bytes)?;
+                mid_range = false;
+            }};
+        }
 
         for line_number in 1.. {
-            let range_check = line_ranges.check(line_number);
-            if range_check == RangeCheckResult::AfterLastRange {
+            if line_ranges.check(line_number) == RangeCheckResult::AfterLastRange {
                 break;
             }
 
@@ -213,34 +497,193 @@ impl<'a> Controller<'a> {
                 break;
             }
 
-            match line_ranges.check(line_number) {
-                RangeCheckResult::BeforeOrBetweenRanges => {
-                    // Call the printer in case we need to call the syntax highlighter
-                    // for this line. However, set `out_of_range` to `true`.
-                    printer.print_line(true, writer, line_number, &line_buffer)?;
-                    mid_range = false;
+            if line_ranges.check(line_number) == RangeCheckResult::InRange {
+                for (number, bytes, selected) in
+                    selector.feed_in_range(line_number, line_buffer.clone())
+                {
+                    if selected {
+                        emit_in_range!(number, &bytes);
+                    } else {
+                        emit_out_of_range!(number, &bytes);
+                    }
                 }
+            } else {
+                emit_out_of_range!(line_number, &line_buffer);
+            }
+
+            line_buffer.clear();
+        }
+
+        for (number, bytes, _) in selector.finish() {
+            emit_out_of_range!(number, &bytes);
+        }
 
-                RangeCheckResult::InRange => {
+        Ok(())
+    }
+
+    /// Renders `inputs` the same way [`Controller::run`] does, but instead of
+    /// writing ANSI-encoded bytes to a [`Write`] sink, delivers structured
+    /// [`visitor::HeaderEvent`]/[`visitor::LineEvent`] events (with syntax
+    /// highlighting attached as spans) to a caller-supplied [`Visitor`]. This
+    /// drives the same per-line `LineRanges`/[`content_filter::ContentFilter`]
+    /// selection logic as [`Controller::print_file_ranges`], via the shared
+    /// [`LineSelector`].
+    pub fn run_with_visitor(&self, inputs: Vec<Input>, visitor: &mut dyn Visitor) -> Result<()> {
+        for input in inputs {
+            let mut opened_input = input.open(
+                None,
+                #[cfg(feature = "lessopen")]
+                !self.config.no_lessopen,
+                self.config.encoding,
+            )?;
+            self.visit_file(visitor, &mut opened_input)?;
+        }
+        Ok(())
+    }
+
+    fn visit_file(&self, visitor: &mut dyn Visitor, input: &mut OpenedInput) -> Result<()> {
+        let is_binary = input
+            .reader
+            .content_type
+            .as_ref()
+            .map_or(false, ContentType::is_binary);
+
+        let syntax_in_set = (!is_binary)
+            .then(|| {
+                self.assets
+                    .get_syntax(self.config.language, input, &self.config.syntax_mapping)
+            })
+            .transpose()
+            .ok()
+            .flatten();
+
+        visitor.header(HeaderEvent {
+            path: input.description.name.as_deref(),
+            kind: &input.description.kind,
+            is_binary,
+            language: syntax_in_set.as_ref().map(|s| s.syntax.name.as_str()),
+        })?;
+
+        if input.reader.content_type.is_some() {
+            let line_ranges = self.resolve_visible_lines(input)?;
+            self.visit_file_ranges(visitor, syntax_in_set, input, &line_ranges)?;
+        }
+
+        visitor.footer()?;
+
+        Ok(())
+    }
+
+    fn visit_file_ranges(
+        &self,
+        visitor: &mut dyn Visitor,
+        syntax_in_set: Option<SyntaxReferenceInSet>,
+        input: &mut OpenedInput,
+        line_ranges: &LineRanges,
+    ) -> Result<()> {
+        let mut highlighter = syntax_in_set.map(|syntax_in_set| {
+            let theme = self.config.theme.as_ref().map_or_else(
+                || self.assets.get_default_theme(),
+                |name| {
+                    self.assets
+                        .get_theme(name)
+                        .unwrap_or_else(|_| self.assets.get_default_theme())
+                },
+            );
+            (
+                HighlightLines::new(syntax_in_set.syntax, theme),
+                syntax_in_set.syntax_set,
+            )
+        });
+
+        let mut line_buffer = Vec::new();
+        let mut first_range: bool = true;
+        let mut mid_range: bool = false;
+
+        let style_snip = self.config.style_components.snip();
+        let mut selector = LineSelector::new(self.config.content_filter.as_ref());
+
+        macro_rules! emit {
+            ($number:expr, $bytes:expr, $in_range:expr) => {{
+                if $in_range {
                     if style_snip {
                         if first_range {
                             first_range = false;
                             mid_range = true;
                         } else if !mid_range {
                             mid_range = true;
-                            printer.print_snip(writer)?;
+                            visitor.snip()?;
                         }
                     }
+                } else {
+                    mid_range = false;
+                }
+
+                let spans = highlighter
+                    .as_mut()
+                    .and_then(|(highlighter, syntax_set)| {
+                        let decoded = decode_line(
+                            &$bytes,
+                            input.reader.content_type.as_ref()?,
+                            $number == 1,
+                        )?;
+                        let highlighted = highlighter.highlight_line(&decoded, syntax_set).ok()?;
+                        let mut offset = 0;
+                        Some(
+                            highlighted
+                                .into_iter()
+                                .map(|(style, text)| {
+                                    let range = offset..offset + text.len();
+                                    offset = range.end;
+                                    (style, range)
+                                })
+                                .collect::<Vec<_>>(),
+                        )
+                    })
+                    .unwrap_or_default();
+
+                visitor.line(LineEvent {
+                    number: $number,
+                    in_range: $in_range,
+                    raw_bytes: &$bytes,
+                    styled_spans: if $in_range { &spans[..] } else { &[] },
+                })?;
+            }};
+        }
 
-                    printer.print_line(false, writer, line_number, &line_buffer)?;
+        for line_number in 1.. {
+            if line_ranges.check(line_number) == RangeCheckResult::AfterLastRange {
+                break;
+            }
+
+            if !input.reader.read_line(&mut line_buffer).with_context(|| {
+                let description = &input.description;
+                if let Some(name) = description.name.as_ref() {
+                    format!("failed to read '{}'", name.to_string_lossy())
+                } else {
+                    format!("failed to read {}", description.kind)
                 }
+            })? {
+                break;
+            }
 
-                RangeCheckResult::AfterLastRange => unreachable!(),
+            if line_ranges.check(line_number) == RangeCheckResult::InRange {
+                for (number, bytes, selected) in
+                    selector.feed_in_range(line_number, line_buffer.clone())
+                {
+                    emit!(number, bytes, selected);
+                }
+            } else {
+                emit!(line_number, line_buffer, false);
             }
 
             line_buffer.clear();
         }
 
+        for (number, bytes, _) in selector.finish() {
+            emit!(number, bytes, false);
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,39 @@
+use std::ffi::OsStr;
+use std::ops::Range;
+
+use syntect::highlighting::Style;
+
+use crate::error::Result;
+
+/// Metadata about an input, passed to [`Visitor::header`] once before any
+/// [`LineEvent`]s.
+pub struct HeaderEvent<'a> {
+    pub path: Option<&'a OsStr>,
+    pub kind: &'a str,
+    pub is_binary: bool,
+    pub language: Option<&'a str>,
+}
+
+/// One line of input, in source order. Lines excluded by the requested
+/// [`super::line_range::LineRanges`] or [`super::content_filter::ContentFilter`]
+/// are still delivered (so a visitor doing its own highlighting can keep
+/// parser state continuous across the gap), but `in_range` is `false` for
+/// them and `styled_spans` is empty.
+pub struct LineEvent<'a> {
+    pub number: usize,
+    pub in_range: bool,
+    pub raw_bytes: &'a [u8],
+    pub styled_spans: &'a [(Style, Range<usize>)],
+}
+
+/// Receives structured rendering events from
+/// [`super::Controller::run_with_visitor`], as an alternative to the
+/// byte-oriented [`crate::printer::Printer`] trait used by `run`/`run_with_options`.
+/// This lets downstream tools (editors, TUIs, LSP-style integrations) consume
+/// bat's syntax highlighting as span metadata rather than ANSI-encoded text.
+pub trait Visitor {
+    fn header(&mut self, event: HeaderEvent) -> Result<()>;
+    fn line(&mut self, event: LineEvent) -> Result<()>;
+    fn snip(&mut self) -> Result<()>;
+    fn footer(&mut self) -> Result<()>;
+}
@@ -0,0 +1,83 @@
+//! Computes which lines of a file differ from its git `HEAD` blob, for the
+//! `changes` style component's gutter marker column. Shells out to the
+//! `git` binary (the same subprocess approach `bin/bat/gitconfig.rs` uses
+//! for reading `git config`), rather than depending on a git library, since
+//! `git diff` already knows how to locate the repository and blob for us.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+/// How a line differs from the file's git `HEAD` revision.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineChange {
+    Added,
+    Modified,
+    /// Lines were deleted immediately before this line number; the deleted
+    /// lines themselves have no position in the working-tree file, so, like
+    /// most gutter-diff implementations, we mark the following line instead.
+    Removed,
+}
+
+/// Maps 1-based line numbers in `path`'s current working-tree content to how
+/// they differ from `HEAD`. Returns `None` if `path` isn't in a git work
+/// tree, `git` isn't on `PATH`, or the file has no changes relative to
+/// `HEAD`.
+pub fn line_changes(path: &Path) -> Option<BTreeMap<usize, LineChange>> {
+    let output = Command::new("git")
+        .args(["diff", "--no-color", "--no-ext-diff", "-U0", "HEAD", "--"])
+        .arg(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let diff = String::from_utf8(output.stdout).ok()?;
+
+    let mut changes = BTreeMap::new();
+    for line in diff.lines() {
+        let Some(hunk) = line.strip_prefix("@@ ") else {
+            continue;
+        };
+        let mut fields = hunk.split_whitespace();
+        let (Some(old_range), Some(new_range)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let (Some((_, old_count)), Some((new_start, new_count))) =
+            (parse_hunk_range(old_range), parse_hunk_range(new_range))
+        else {
+            continue;
+        };
+
+        if new_count == 0 {
+            // `new_start` is the last unchanged line before the deletion (or
+            // `0` for a deletion at the very start of the file); the gutter
+            // marks the line right after it, where the deleted lines used to
+            // be, except at the start of the file where there is no earlier
+            // line to offset from.
+            let marked_line = if new_start == 0 { 1 } else { new_start + 1 };
+            changes.insert(marked_line, LineChange::Removed);
+        } else {
+            let kind = if old_count == 0 {
+                LineChange::Added
+            } else {
+                LineChange::Modified
+            };
+            for line_number in new_start..new_start + new_count {
+                changes.insert(line_number, kind);
+            }
+        }
+    }
+
+    (!changes.is_empty()).then_some(changes)
+}
+
+/// Parses one side of a hunk header, e.g. `-12,3` or `+7` (an implicit count
+/// of `1` when there's no `,count`), returning `(start, count)`.
+fn parse_hunk_range(spec: &str) -> Option<(usize, usize)> {
+    let spec = spec.trim_start_matches(['+', '-']);
+    match spec.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((spec.parse().ok()?, 1)),
+    }
+}
@@ -2,7 +2,6 @@ use std::cmp::Ordering;
 use std::sync::Arc;
 
 use ndarray::{Array0, CowArray};
-use once_cell::sync::OnceCell;
 use ort::{tensor::OrtOwnedTensor, Environment, InMemorySession, SessionBuilder, Value};
 
 const LABELS: [&str; 54] = [
@@ -62,33 +61,74 @@ const LABELS: [&str; 54] = [
     "yaml",
 ];
 
-static ENVIRONMENT: OnceCell<Arc<Environment>> = OnceCell::new();
-static SESSION: OnceCell<InMemorySession> = OnceCell::new();
+/// One of the model's raw predictions: a guesslang label (an entry of
+/// `LABELS`) and the model's confidence in it, in `[0, 1]`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GuessLangCandidate {
+    pub(crate) token: &'static str,
+    pub(crate) confidence: f32,
+}
+
+#[derive(Debug)]
+pub(crate) struct GuessLang {
+    session: InMemorySession<'static>,
+}
+
+impl GuessLang {
+    pub(crate) fn new(model: Vec<u8>) -> Self {
+        // Leaked so the session (which borrows the environment) can be
+        // 'static, letting `GuessLang` be an ordinary owned value instead of
+        // borrowing from a process-wide static, as the model bytes
+        // themselves already are per-instance.
+        let environment: &'static Arc<Environment> =
+            Box::leak(Box::new(Environment::default().into_arc()));
+        let session = SessionBuilder::new(environment)
+            .and_then(|b| b.with_custom_op_lib(env!("OCOS_LIB_PATH"))) // path to onnxruntime extensions "libortextensions"
+            .and_then(|b| b.with_optimization_level(ort::GraphOptimizationLevel::Disable)) // the model is already optimized for CPU
+            .and_then(|b| b.with_model_from_memory(&model))
+            .expect("failed to init guesslang session");
+        GuessLang { session }
+    }
+
+    /// Returns the single best-guess label, or `None` if the model's top
+    /// confidence doesn't clear the detection threshold.
+    pub(crate) fn guess(&self, text: String) -> Option<&'static str> {
+        self.guess_ranked(text)
+            .into_iter()
+            .next()
+            .filter(|candidate| candidate.confidence > 0.5)
+            .map(|candidate| candidate.token)
+    }
 
-pub(crate) fn guesslang(mut t: String) -> Option<&'static str> {
-    let environment = ENVIRONMENT
-        .get_or_init(|| Environment::default().into_arc());
-    let session = SESSION
-        .get_or_try_init(|| {
-            SessionBuilder::new(environment)?
-                .with_custom_op_lib(env!("OCOS_LIB_PATH"))? // path to onnxruntime extensions "libortextensions"
-                .with_optimization_level(ort::GraphOptimizationLevel::Disable)? // the model is already optimized for CPU
-                .with_model_from_memory(include_bytes!("../assets/guesslang.onnx"))
-        })
-        .expect("failed to init guesslang session");
+    /// Runs the model and returns every label it considered, ranked by
+    /// confidence, highest first.
+    pub(crate) fn guess_ranked(&self, mut text: String) -> Vec<GuessLangCandidate> {
+        text.truncate(10000); // this is maximum of model input
+        let input = CowArray::from(Array0::from_elem((), text)).into_dyn();
+        let Some(inputs) = Value::from_array(self.session.allocator(), &input)
+            .ok()
+            .map(|value| vec![value]) // may fail if string contains \0
+        else {
+            return Vec::new();
+        };
+        let Ok(outputs) = self.session.run(inputs) else {
+            return Vec::new(); // the model may error with very short input
+        };
+        let Ok(output) = outputs[0].try_extract::<f32>() else {
+            return Vec::new(); // WTH is going on?
+        };
 
-    t.truncate(10000); // this is maximum of model input
-    let input = CowArray::from(Array0::from_elem((), t)).into_dyn();
-    let inputs = vec![Value::from_array(session.allocator(), &input).ok()?]; // may fail if string contains \0
-    let outputs = session.run(inputs).ok()?; // the model may error with very short input
-    let output: OrtOwnedTensor<f32, _> = outputs[0].try_extract().ok()?; // WTH is going on?
-    let output = output.view();
-    let (index, prob) = output
-        .iter()
-        .cloned()
-        .enumerate()
-        .max_by(|(_, l), (_, r)| l.partial_cmp(r).unwrap_or(Ordering::Equal))
-        .unwrap();
-    let lang = LABELS[index];
-    (prob > 0.5).then_some(lang)
+        let mut candidates: Vec<GuessLangCandidate> = output
+            .view()
+            .iter()
+            .zip(LABELS)
+            .map(|(&confidence, token)| GuessLangCandidate { token, confidence })
+            .collect();
+        candidates.sort_by(|a, b| {
+            b.confidence
+                .partial_cmp(&a.confidence)
+                .unwrap_or(Ordering::Equal)
+        });
+        candidates
+    }
 }
@@ -3,9 +3,10 @@ use std::fmt::{self, Display};
 use std::io::{self, IoSliceMut, Read};
 use std::mem;
 use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
-use std::thread::sleep;
-use std::time::Duration;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use bstr::ByteSlice;
 
@@ -13,6 +14,50 @@ use super::{Input, InputKind};
 use crate::config::get_env_var;
 use crate::error::*;
 
+/// How long `LessOpen::new` waits, by default, for a `||`-piped
+/// preprocessor to either produce its first byte of output or exit, before
+/// giving up and falling back to the original file. Overridable via
+/// `BAT_LESSOPEN_TIMEOUT` (milliseconds); see [`lessopen_timeout`].
+const DEFAULT_LESSOPEN_TIMEOUT_MS: u64 = 100;
+
+/// How often [`wait_for_readiness`] re-checks the preprocessor's exit
+/// status and readiness channel while waiting.
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Reads the `BAT_LESSOPEN_TIMEOUT` env var (milliseconds), falling back to
+/// [`DEFAULT_LESSOPEN_TIMEOUT_MS`] if it's unset.
+fn lessopen_timeout() -> Result<Duration> {
+    let millis = match get_env_var("BAT_LESSOPEN_TIMEOUT")? {
+        Some(value) => value
+            .parse()
+            .with_context(|| format!("invalid BAT_LESSOPEN_TIMEOUT value '{value}'"))?,
+        None => DEFAULT_LESSOPEN_TIMEOUT_MS,
+    };
+    Ok(Duration::from_millis(millis))
+}
+
+/// Extracts non-empty, trimmed stderr text from a preprocessor's captured
+/// output, for inclusion in a `[bat warning]` when it misbehaves. `None`
+/// when there's nothing worth showing.
+fn format_stderr(stderr: &[u8]) -> Option<String> {
+    let trimmed = String::from_utf8_lossy(stderr).trim().to_owned();
+    (!trimmed.is_empty()).then_some(trimmed)
+}
+
+/// Kills `child` (it's being abandoned) and drains its stderr for a
+/// diagnostic message, bounded by the kill rather than a plain blocking
+/// read, so an abandoned-but-still-running preprocessor can't make bat
+/// hang while we're trying to explain why we gave up on it.
+fn take_stderr_and_kill(mut child: Child) -> Option<String> {
+    _ = child.kill();
+    let mut buf = Vec::new();
+    if let Some(mut stderr) = child.stderr.take() {
+        _ = stderr.read_to_end(&mut buf);
+    }
+    _ = child.wait();
+    format_stderr(&buf)
+}
+
 #[derive(Debug)]
 pub struct PathNotUnicode {
     pub path: PathBuf,
@@ -40,7 +85,7 @@ enum LessOpenKind {
 }
 
 #[cfg(unix)]
-fn run_script(script: &str, stdin: Stdio, stdout: Stdio) -> Result<Child> {
+fn run_script(script: &str, stdin: Stdio, stdout: Stdio, stderr: Stdio) -> Result<Child> {
     Ok(Command::new("/bin/sh")
         .arg("-c")
         .arg(script)
@@ -48,12 +93,12 @@ fn run_script(script: &str, stdin: Stdio, stdout: Stdio) -> Result<Child> {
         .env_remove("LESSCLOSE")
         .stdin(stdin)
         .stdout(stdout)
-        .stderr(Stdio::inherit())
+        .stderr(stderr)
         .spawn()?)
 }
 
 #[cfg(not(unix))]
-fn run_script(script: &str, stdin: Stdio, stdout: Stdio) -> Result<Child> {
+fn run_script(script: &str, stdin: Stdio, stdout: Stdio, stderr: Stdio) -> Result<Child> {
     let all_args = shell_words::split(script)?;
     let (script, args) = all_args.split_first().unwrap();
     let script = grep_cli::resolve_binary(script)?;
@@ -63,7 +108,7 @@ fn run_script(script: &str, stdin: Stdio, stdout: Stdio) -> Result<Child> {
         .env_remove("LESSCLOSE")
         .stdin(stdin)
         .stdout(stdout)
-        .stderr(Stdio::inherit())
+        .stderr(stderr)
         .spawn()?)
 }
 
@@ -135,6 +180,7 @@ impl LessOpen {
                     Stdio::null()
                 },
                 Stdio::piped(),
+                Stdio::piped(),
             )
             .context("failed to spawn lessopen preprocessor")?;
 
@@ -158,6 +204,12 @@ impl LessOpen {
                             Some(LessOpen { child: None, close })
                         }
                     } else {
+                        if let Some(stderr) = format_stderr(&output.stderr) {
+                            eprintln!(
+                                "[bat warning] lessopen preprocessor exited with {}, falling back to the original file: {stderr}",
+                                output.status
+                            );
+                        }
                         None
                     }
                 }
@@ -165,6 +217,11 @@ impl LessOpen {
                     let stdout = child.stdout.take().unwrap();
                     let mut reader = PeekReader::new(stdout);
                     if reader.peek().map(|byte| byte.is_none()).unwrap_or(true) {
+                        if let Some(stderr) = take_stderr_and_kill(child) {
+                            eprintln!(
+                                "[bat warning] lessopen preprocessor produced no output, falling back to the original file: {stderr}"
+                            );
+                        }
                         None
                     } else {
                         let close =
@@ -178,26 +235,25 @@ impl LessOpen {
                 }
                 LessOpenKind::Piped => {
                     let stdout = child.stdout.take().unwrap();
-                    let mut reader = PeekReader::new(stdout);
-                    if reader.peek().is_err()
-                        || {
-                            sleep(Duration::from_millis(10));
-                            false
+                    let reader = PeekReader::new(stdout);
+                    match wait_for_readiness(&mut child, reader, lessopen_timeout()?) {
+                        Readiness::Ready(reader) => {
+                            let close = lessclose
+                                .map(|lessclose| make_lessclose(lessclose, file_name, "-"));
+                            input.kind = InputKind::CustomReader(Box::new(reader));
+                            Some(LessOpen {
+                                child: Some(child),
+                                close,
+                            })
+                        }
+                        Readiness::GiveUp => {
+                            if let Some(stderr) = take_stderr_and_kill(child) {
+                                eprintln!(
+                                    "[bat warning] lessopen preprocessor produced no output in time, falling back to the original file: {stderr}"
+                                );
+                            }
+                            None
                         }
-                        || child
-                            .try_wait()
-                            .map(|status| status.map_or(false, |status| !status.success()))
-                            .unwrap_or(true)
-                    {
-                        None
-                    } else {
-                        let close =
-                            lessclose.map(|lessclose| make_lessclose(lessclose, file_name, "-"));
-                        input.kind = InputKind::CustomReader(Box::new(reader));
-                        Some(LessOpen {
-                            child: Some(child),
-                            close,
-                        })
                     }
                 }
             })
@@ -207,6 +263,81 @@ impl LessOpen {
     }
 }
 
+/// The outcome of [`wait_for_readiness`]: either the preprocessor buffered
+/// its first byte of output in time (carrying the [`PeekReader`] it was
+/// read through, so the already-peeked byte isn't lost), or it should be
+/// abandoned in favor of the original file.
+enum Readiness {
+    Ready(PeekReader<ChildStdout>),
+    GiveUp,
+}
+
+/// Waits up to `timeout` for a `||`-piped preprocessor to either buffer its
+/// first byte of output or exit unsuccessfully with nothing buffered,
+/// whichever comes first; empty output followed by a successful exit within
+/// `timeout` counts as ready -- replacing the old fixed 10ms sleep, which
+/// raced on slow preprocessors and stalled on fast ones.
+///
+/// `reader.peek()` blocks on its underlying pipe with no way to poll it
+/// directly, so the read happens on a helper thread and is awaited via a
+/// channel; meanwhile the calling thread polls `child`'s exit status at
+/// [`READINESS_POLL_INTERVAL`] so a preprocessor that fails fast doesn't
+/// have to wait out the full timeout. If `timeout` elapses with neither
+/// settled, the helper thread is left to finish on its own (it'll exit
+/// once the preprocessor produces output or closes its pipe) and this
+/// gives up.
+fn wait_for_readiness(
+    child: &mut Child,
+    reader: PeekReader<ChildStdout>,
+    timeout: Duration,
+) -> Readiness {
+    let (tx, rx) = mpsc::channel();
+    let spawned = thread::Builder::new().spawn(move || {
+        let mut reader = reader;
+        let result = reader.peek();
+        _ = tx.send((reader, result));
+    });
+    if spawned.is_err() {
+        return Readiness::GiveUp;
+    }
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        match rx.try_recv() {
+            Ok((reader, Ok(Some(_)))) => return Readiness::Ready(reader),
+            Ok((reader, Ok(None))) => {
+                // Empty output is used as-is once the preprocessor exits successfully.
+                while Instant::now() < deadline {
+                    match child.try_wait() {
+                        Ok(Some(status)) if status.success() => return Readiness::Ready(reader),
+                        Ok(None) => thread::sleep(READINESS_POLL_INTERVAL),
+                        _ => break,
+                    }
+                }
+                return Readiness::GiveUp;
+            }
+            Ok(_) => return Readiness::GiveUp,
+            Err(mpsc::TryRecvError::Disconnected) => return Readiness::GiveUp,
+            Err(mpsc::TryRecvError::Empty) => {}
+        }
+
+        if matches!(child.try_wait(), Ok(Some(status)) if !status.success()) {
+            // Give the in-flight peek a brief grace period in case the
+            // preprocessor flushed output right before exiting.
+            return match rx.recv_timeout(READINESS_POLL_INTERVAL) {
+                Ok((reader, Ok(Some(_)))) => Readiness::Ready(reader),
+                _ => Readiness::GiveUp,
+            };
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Readiness::GiveUp;
+        }
+        thread::sleep(remaining.min(READINESS_POLL_INTERVAL));
+    }
+}
+
 impl Drop for LessOpen {
     fn drop(&mut self) {
         // wait child
@@ -225,6 +356,7 @@ impl Drop for LessOpen {
                 } else {
                     Stdio::null()
                 },
+                Stdio::inherit(),
             ) {
                 _ = child.wait();
             }
@@ -1,22 +1,30 @@
 use std::borrow::Cow;
 use std::error::Error as StdError;
-use std::ffi::{OsStr, OsString};
+#[cfg(unix)]
+use std::ffi::OsStr;
+use std::ffi::OsString;
 use std::fmt::{self, Display};
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read, Write};
+use std::io::{self, BorrowedBuf, BufRead, BufReader, Read};
+#[cfg(feature = "zero-copy")]
+use std::io::Seek;
+#[cfg(unix)]
+use std::io::Write;
+use std::mem::MaybeUninit;
 use std::path::{Path, PathBuf};
+#[cfg(unix)]
 use std::process::{Command, Stdio};
 
-use bstr::{ByteSlice, ByteVec};
+#[cfg(unix)]
+use bstr::ByteSlice;
 use clircle::{Clircle, Identifier};
-#[cfg(feature = "zero-copy")]
-use memmap2::MmapOptions;
+use encoding_rs::{CoderResult, Decoder, Encoding};
 
 use crate::error::*;
 #[cfg(feature = "lessopen")]
 use lessopen::LessOpen;
 #[cfg(feature = "zero-copy")]
-use zero_copy::{leak_mmap, LeakySliceReader};
+use zero_copy::{create_file_mapped_leaky_slice, LeakySliceReader};
 
 #[cfg(feature = "lessopen")]
 pub mod lessopen;
@@ -49,6 +57,25 @@ impl Display for IsDirectory {
 
 impl StdError for IsDirectory {}
 
+#[derive(Debug)]
+pub struct ArchiveMemberNotFound {
+    pub archive: PathBuf,
+    pub entry: PathBuf,
+}
+
+impl Display for ArchiveMemberNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' not found in '{}'",
+            self.entry.display(),
+            self.archive.display()
+        )
+    }
+}
+
+impl StdError for ArchiveMemberNotFound {}
+
 /// A description of an Input source.
 /// This tells bat how to refer to the input.
 #[derive(Debug, Clone)]
@@ -68,6 +95,8 @@ pub enum InputKind {
     OrdinaryFile(PathBuf),
     StdIn,
     CustomReader(Box<dyn Read>),
+    /// A single member of a tar archive, addressed as `archive.tar:entry/path`.
+    ArchiveMember { archive: PathBuf, entry: PathBuf },
 }
 
 impl InputKind {
@@ -78,6 +107,12 @@ impl InputKind {
             }
             InputKind::StdIn => InputDescription::new(None, "STDIN".to_owned()),
             InputKind::CustomReader(_) => InputDescription::new(None, "READER".to_owned()),
+            InputKind::ArchiveMember { archive, entry } => {
+                let mut name = archive.as_os_str().to_os_string();
+                name.push(":");
+                name.push(entry.as_os_str());
+                InputDescription::new(Some(name), "File".to_owned())
+            }
         }
     }
 }
@@ -99,6 +134,23 @@ impl OpenedInput {
     pub(crate) fn path(&self) -> Option<&Path> {
         self.description.name.as_ref().map(Path::new)
     }
+
+    /// A handle for seeking backward over this input's bytes, if it's an
+    /// mmap'd file read through the `zero-copy` path and not, e.g., further
+    /// wrapped by a decompressor. `None` for every other input source.
+    #[cfg(feature = "zero-copy")]
+    pub(crate) fn seekable(&mut self) -> Option<&mut LeakySliceReader> {
+        self.reader.seekable()
+    }
+
+    /// Seeks back to the start of the input and resets the decoding state
+    /// that [`InputReader::read_line`] keeps between calls (BOM-skipping,
+    /// the streaming `encoding_rs` decoder), so reading can restart cleanly.
+    /// `None` if [`Self::seekable`] would be `None`.
+    #[cfg(feature = "zero-copy")]
+    pub(crate) fn rewind(&mut self) -> Option<io::Result<()>> {
+        self.reader.rewind()
+    }
 }
 
 impl Input {
@@ -126,10 +178,24 @@ impl Input {
         }
     }
 
+    /// Opens a single member of a tar archive (e.g. `archive.tar:src/main.rs`)
+    /// without extracting it.
+    pub fn from_archive_member(archive: impl Into<PathBuf>, entry: impl Into<PathBuf>) -> Self {
+        let kind = InputKind::ArchiveMember {
+            archive: archive.into(),
+            entry: entry.into(),
+        };
+        Input {
+            description: kind.description(),
+            kind,
+        }
+    }
+
     pub(crate) fn open(
         mut self,
         stdout_identifier: Option<&Identifier>,
         #[cfg(feature = "lessopen")] lessopen: bool,
+        forced_encoding: Option<&'static Encoding>,
     ) -> Result<OpenedInput> {
         #[cfg(feature = "lessopen")]
         let lessopen = if lessopen {
@@ -152,7 +218,11 @@ impl Input {
 
                 Ok(OpenedInput {
                     description,
-                    reader: InputReader::new(io::stdin().lock()),
+                    reader: InputReader::new(
+                        decompress(io::stdin().lock())?,
+                        forced_encoding,
+                        DEFAULT_DETECTION_WINDOW,
+                    ),
                     #[cfg(feature = "lessopen")]
                     lessopen,
                 })
@@ -179,31 +249,59 @@ impl Input {
                     }
 
                     #[cfg(feature = "zero-copy")]
-                    let r = metadata
+                    let (raw, seekable): (Box<dyn BufRead>, Option<LeakySliceReader>) = metadata
                         .is_file()
-                        .then_some(metadata.len())
-                        .and_then(|len| {
-                            unsafe {
-                                MmapOptions::new()
-                                    .len(isize::try_from(len).ok()?.try_into().unwrap())
-                                    .map_copy(&file)
-                            }
-                            .ok()
-                        })
+                        .then_some(())
+                        .and_then(|()| unsafe { create_file_mapped_leaky_slice(&file) }.ok())
                         .map_or_else(
-                            || InputReader::new(BufReader::new(file)),
-                            |mmap| InputReader::new(LeakySliceReader::new(leak_mmap(mmap))),
+                            || (Box::new(BufReader::new(file)) as Box<dyn BufRead>, None),
+                            |slice| {
+                                let mut reader = LeakySliceReader::new(slice);
+                                // A decompressor's output bytes don't correspond
+                                // 1:1 to offsets into the mmap'd (compressed)
+                                // input, so only report `reader` as seekable if
+                                // `decompress` below is about to pass it through
+                                // unchanged.
+                                let seekable = (!is_compressed_magic(
+                                    reader.fill_buf().unwrap_or_default(),
+                                ))
+                                .then(|| reader.clone());
+                                (Box::new(reader) as Box<dyn BufRead>, seekable)
+                            },
                         );
                     #[cfg(not(feature = "zero-copy"))]
-                    let r = InputReader::new(BufReader::new(file));
-                    r
+                    let raw: Box<dyn BufRead> = Box::new(BufReader::new(file));
+
+                    #[cfg(feature = "zero-copy")]
+                    let reader =
+                        InputReader::new(decompress(raw)?, forced_encoding, DEFAULT_DETECTION_WINDOW)
+                            .with_seekable(seekable);
+                    #[cfg(not(feature = "zero-copy"))]
+                    let reader =
+                        InputReader::new(decompress(raw)?, forced_encoding, DEFAULT_DETECTION_WINDOW);
+                    reader
                 },
                 #[cfg(feature = "lessopen")]
                 lessopen,
             }),
             InputKind::CustomReader(reader) => Ok(OpenedInput {
                 description,
-                reader: InputReader::new(BufReader::new(reader)),
+                reader: InputReader::new(
+                    decompress(BufReader::new(reader))?,
+                    forced_encoding,
+                    DEFAULT_DETECTION_WINDOW,
+                ),
+                #[cfg(feature = "lessopen")]
+                lessopen,
+            }),
+
+            InputKind::ArchiveMember { archive, entry } => Ok(OpenedInput {
+                description,
+                reader: InputReader::new(
+                    decompress(io::Cursor::new(read_archive_member(&archive, &entry)?))?,
+                    forced_encoding,
+                    DEFAULT_DETECTION_WINDOW,
+                ),
                 #[cfg(feature = "lessopen")]
                 lessopen,
             }),
@@ -211,8 +309,78 @@ impl Input {
     }
 }
 
+/// Sniffs the first bytes of `reader` for a known compression magic number
+/// and, if found, transparently wraps it in the matching decompressor so
+/// that e.g. `bat access.log.gz` shows highlighted text the same way an
+/// ordinary file would. The decompressed stream is what gets handed to
+/// `InputReader::new`, so content/encoding inspection runs against the
+/// decompressed bytes.
+fn decompress<R: BufRead + 'static>(mut reader: R) -> Result<Box<dyn BufRead>> {
+    let magic = reader.fill_buf().unwrap_or_default();
+
+    Ok(if magic.starts_with(&[0x1F, 0x8B]) {
+        Box::new(BufReader::new(flate2::bufread::GzDecoder::new(reader)))
+    } else if magic.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+        Box::new(
+            zstd::stream::read::Decoder::new(reader)
+                .context("failed to initialize zstd decompression")?,
+        )
+    } else if magic.starts_with(&[0xFD, 0x37, 0x7A, 0x58, 0x5A]) {
+        Box::new(BufReader::new(xz2::bufread::XzDecoder::new(reader)))
+    } else if magic.starts_with(&[0x42, 0x5A, 0x68]) {
+        Box::new(BufReader::new(bzip2::bufread::BzDecoder::new(reader)))
+    } else {
+        Box::new(reader)
+    })
+}
+
+/// The same compression magic numbers `decompress` checks, exposed
+/// separately so `Input::open`'s zero-copy mmap branch can tell, before
+/// `decompress` runs, whether the reader it's about to hand over is going to
+/// come back out unwrapped (and therefore still seekable) or wrapped in a
+/// decompressor (whose output bytes don't correspond 1:1 to offsets into the
+/// mmap'd, still-compressed input).
+#[cfg(feature = "zero-copy")]
+fn is_compressed_magic(buf: &[u8]) -> bool {
+    buf.starts_with(&[0x1F, 0x8B])
+        || buf.starts_with(&[0x28, 0xB5, 0x2F, 0xFD])
+        || buf.starts_with(&[0xFD, 0x37, 0x7A, 0x58, 0x5A])
+        || buf.starts_with(&[0x42, 0x5A, 0x68])
+}
+
+/// Reads a single member out of a tar archive, by streaming through its
+/// entries until the requested path is found. The matching entry is read
+/// fully into memory since `tar::Entry` borrows from the `Archive`/`Entries`
+/// iterator and can't be kept open as a `'static` reader.
+fn read_archive_member(archive: &Path, entry: &Path) -> Result<Vec<u8>> {
+    let file = File::open(archive)
+        .with_context(|| format!("failed to open '{}'", archive.display()))?;
+    let mut archive_reader = tar::Archive::new(file);
+    let entries = archive_reader
+        .entries()
+        .with_context(|| format!("failed to read '{}'", archive.display()))?;
+
+    for tar_entry in entries {
+        let mut tar_entry =
+            tar_entry.with_context(|| format!("failed to read '{}'", archive.display()))?;
+        if tar_entry.path().ok().as_deref() == Some(entry) {
+            let mut contents = Vec::new();
+            tar_entry
+                .read_to_end(&mut contents)
+                .with_context(|| format!("failed to read '{}'", entry.display()))?;
+            return Ok(contents);
+        }
+    }
+
+    Err(ArchiveMemberNotFound {
+        archive: archive.to_owned(),
+        entry: entry.to_owned(),
+    }
+    .into())
+}
+
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub(crate) enum ContentType {
     /// "binary" data
     Binary(Option<String>),
@@ -231,24 +399,183 @@ pub(crate) enum ContentType {
 
     /// UTF-32 encoded "text" data (big endian)
     UTF_32BE,
+
+    /// Text in some other encoding (legacy/CJK encodings such as
+    /// Shift-JIS, EUC-JP, GB18030, Big5, KOI8-R, or Windows-125x), either
+    /// detected (from the `file --mime-encoding` label on unix, by
+    /// `chardetng` elsewhere) or forced by the
+    /// caller via `Config::encoding`/`Input::open`'s `forced_encoding`.
+    Other(&'static Encoding),
+}
+
+impl PartialEq for ContentType {
+    fn eq(&self, other: &Self) -> bool {
+        use ContentType::*;
+        match (self, other) {
+            (Binary(a), Binary(b)) => a == b,
+            (UTF_8, UTF_8) => true,
+            (UTF_16LE, UTF_16LE) => true,
+            (UTF_16BE, UTF_16BE) => true,
+            (UTF_32LE, UTF_32LE) => true,
+            (UTF_32BE, UTF_32BE) => true,
+            // `Encoding` values are `&'static`, one per encoding, so pointer
+            // equality is equivalent to value equality here.
+            (Other(a), Other(b)) => std::ptr::eq(*a, *b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for ContentType {}
+
+/// Wraps a boxed reader with a look-ahead buffer, so callers can peek
+/// further ahead than a single `fill_buf` call promises without losing
+/// those bytes for the next real read. Bytes only ever move from `source`
+/// into `held_back`, never the other way, so every consumer of
+/// `fill_buf`/`consume` sees exactly the same stream it would without
+/// peeking -- just with a little of it pulled into memory earlier.
+struct PeekReader {
+    source: Box<dyn BufRead>,
+    held_back: Vec<u8>,
+    held_back_pos: usize,
+}
+
+impl PeekReader {
+    fn new(source: Box<dyn BufRead>) -> PeekReader {
+        PeekReader {
+            source,
+            held_back: Vec::new(),
+            held_back_pos: 0,
+        }
+    }
+
+    /// Returns up to `len` bytes of look-ahead from the front of the
+    /// not-yet-consumed stream, without consuming them. When `source`
+    /// already has `len` bytes buffered in a single `fill_buf` call (the
+    /// common case), they're handed back on loan with no copy at all;
+    /// otherwise chunks are accumulated into `held_back` until there are
+    /// `len` of them or `source` hits EOF.
+    fn peek(&mut self, len: usize) -> io::Result<&[u8]> {
+        if self.held_back_pos == self.held_back.len() {
+            let chunk = self.source.fill_buf()?;
+            if chunk.len() >= len || chunk.is_empty() {
+                return Ok(chunk);
+            }
+        }
+        loop {
+            let have = self.held_back.len() - self.held_back_pos;
+            if have >= len {
+                break;
+            }
+            let chunk = self.source.fill_buf()?;
+            if chunk.is_empty() {
+                break;
+            }
+            let n = chunk.len().min(len - have);
+            self.held_back.extend_from_slice(&chunk[..n]);
+            self.source.consume(n);
+        }
+        Ok(&self.held_back[self.held_back_pos..])
+    }
+
+    fn is_eof(&mut self) -> io::Result<bool> {
+        Ok(self.peek(1)?.is_empty())
+    }
+
+    /// Discards any buffered look-ahead, for a caller that just seeked
+    /// `source` somewhere else out from under this `PeekReader` (e.g.
+    /// [`InputReader::rewind`]) -- otherwise stale bytes from the old
+    /// position would be served ahead of the real ones at the new one.
+    #[cfg(feature = "zero-copy")]
+    fn reset(&mut self) {
+        self.held_back.clear();
+        self.held_back_pos = 0;
+    }
+}
+
+impl Read for PeekReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let slice = self.fill_buf()?;
+        let len = slice.len().min(buf.len());
+        buf[..len].copy_from_slice(&slice[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+
+    fn read_buf(&mut self, mut cursor: io::BorrowedCursor<'_>) -> io::Result<()> {
+        let slice = self.fill_buf()?;
+        let len = slice.len().min(cursor.capacity());
+        cursor.append(&slice[..len]);
+        self.consume(len);
+        Ok(())
+    }
 }
 
+impl BufRead for PeekReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.held_back_pos < self.held_back.len() {
+            Ok(&self.held_back[self.held_back_pos..])
+        } else {
+            self.held_back.clear();
+            self.held_back_pos = 0;
+            self.source.fill_buf()
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let available = self.held_back.len() - self.held_back_pos;
+        if available > 0 {
+            assert!(amt <= available, "consume amount larger than length");
+            self.held_back_pos += amt;
+        } else {
+            self.source.consume(amt);
+        }
+    }
+}
+
+/// Detection windows default to this many bytes, matching the size
+/// `InputReader::new` always used before [`InputReader::peek`] existed.
+pub(crate) const DEFAULT_DETECTION_WINDOW: usize = 8192;
+
 pub(crate) struct InputReader {
-    inner: Box<dyn BufRead>,
+    inner: PeekReader,
+    /// A second handle onto `inner`'s bytes that can seek, set by
+    /// `Input::open`'s zero-copy mmap branch via [`Self::with_seekable`]
+    /// when `inner` is an un-decompressed [`LeakySliceReader`]; `None`
+    /// (always, when the `zero-copy` feature is off) otherwise.
+    #[cfg(feature = "zero-copy")]
+    seekable: Option<LeakySliceReader>,
     pub(crate) first_read: Option<String>,
     pub(crate) content_type: Option<ContentType>,
+    bom_stripped: bool,
+    /// Incremental decoder for `ContentType::Other`, driving `read_line`'s
+    /// streaming transcoding to UTF-8. Independent of the one-shot `decode`
+    /// call used for `first_read`, same as the UTF-16/UTF-32 paths.
+    encoding_decoder: Option<Decoder>,
+    /// Text already decoded by `encoding_decoder` but not yet consumed by
+    /// `read_line`.
+    encoding_residual: String,
 }
 
 impl InputReader {
-    pub(crate) fn new<R: BufRead + 'static>(mut reader: R) -> InputReader {
-        let first_read = reader.fill_buf().ok().and_then(|buf| {
-            let limit = 8192;
-            let len = buf.len();
-            (len != 0).then_some(&buf[..limit.min(len)])
-        });
+    /// `detection_window` bounds how many bytes of look-ahead (via
+    /// [`Self::peek`]) content/BOM detection scans: large enough to see
+    /// past a short initial read (a pipe that hasn't buffered its first
+    /// page yet, a multi-byte BOM split across chunks), while still bounded
+    /// so a huge file doesn't get fully scanned just to classify it.
+    pub(crate) fn new<R: BufRead + 'static>(
+        reader: R,
+        forced_encoding: Option<&'static Encoding>,
+        detection_window: usize,
+    ) -> InputReader {
+        let mut inner = PeekReader::new(Box::new(reader));
+        let first_read = inner
+            .peek(detection_window)
+            .ok()
+            .and_then(|buf| (!buf.is_empty()).then_some(buf));
 
         let (first_read, content_type) = if let Some(first_read) = first_read {
-            let content_type = inspect(first_read);
+            let content_type = forced_encoding.map_or_else(|| inspect(first_read), ContentType::Other);
             let first_read = decode(first_read, &content_type, true);
             let first_read = if let Some(first_read) = first_read {
                 let truncated = first_read.trim_end_matches(char::REPLACEMENT_CHARACTER);
@@ -272,28 +599,318 @@ impl InputReader {
             (None, None)
         };
 
+        let encoding_decoder = Self::new_encoding_decoder(&content_type);
+
         InputReader {
-            inner: Box::new(reader),
+            inner,
+            #[cfg(feature = "zero-copy")]
+            seekable: None,
             first_read,
             content_type,
+            bom_stripped: false,
+            encoding_decoder,
+            encoding_residual: String::new(),
+        }
+    }
+
+    fn new_encoding_decoder(content_type: &Option<ContentType>) -> Option<Decoder> {
+        match content_type {
+            Some(ContentType::Other(encoding)) => Some(encoding.new_decoder_without_bom_handling()),
+            _ => None,
         }
     }
 
+    /// Returns up to `len` bytes of look-ahead from the front of the
+    /// not-yet-consumed stream without consuming them -- unlike `fill_buf`,
+    /// which only promises *some* unspecified (possibly short) amount, this
+    /// keeps pulling from the underlying reader until it has `len` bytes or
+    /// hits EOF. Lets a caller re-run detection over a wider window than
+    /// whatever the first `fill_buf` call happened to return.
+    pub(crate) fn peek(&mut self, len: usize) -> io::Result<&[u8]> {
+        self.inner.peek(len)
+    }
+
+    /// Decodes the entire remaining stream as UTF-8 (lossily) and splits it
+    /// into lines, without consuming it -- see [`Self::peek`]. For callers
+    /// that need to scan a whole file's content up front, e.g. resolving
+    /// sed-style regex line-range addresses, before any actual line has
+    /// been read yet. Unlike `read_line`, this doesn't apply
+    /// `ContentType`-aware transcoding or BOM stripping, so it's a coarse,
+    /// best-effort view rather than the exact text that will be printed.
+    pub(crate) fn peek_all_lines_lossy(&mut self) -> io::Result<Vec<String>> {
+        let bytes = self.peek(usize::MAX)?;
+        Ok(String::from_utf8_lossy(bytes)
+            .lines()
+            .map(str::to_owned)
+            .collect())
+    }
+
+    /// Whether the stream has no more bytes left to read.
+    #[allow(dead_code)] // public surface for callers to pair with `peek`
+    pub(crate) fn is_eof(&mut self) -> io::Result<bool> {
+        self.inner.is_eof()
+    }
+
+    /// Attaches a seek handle onto `self.inner`'s bytes, for [`Self::seekable`]
+    /// to later expose. Only [`Input::open`]'s zero-copy mmap branch, which
+    /// alone can prove `reader` and `seekable` are views over the same
+    /// un-decompressed bytes, should call this.
+    #[cfg(feature = "zero-copy")]
+    fn with_seekable(mut self, seekable: Option<LeakySliceReader>) -> InputReader {
+        self.seekable = seekable;
+        self
+    }
+
+    /// A handle for seeking backward over the bytes behind this reader, if
+    /// it came from [`Input::open`]'s zero-copy mmap branch unwrapped by any
+    /// decompressor; `None` otherwise. The handle is positioned where
+    /// reading continues, with no look-ahead left buffered in front of it, so
+    /// the next read starts wherever the handle is seeked to.
+    #[cfg(feature = "zero-copy")]
+    pub(crate) fn seekable(&mut self) -> Option<&mut LeakySliceReader> {
+        let seekable = self.seekable.as_mut()?;
+        let held_back = self.inner.held_back.len() - self.inner.held_back_pos;
+        if held_back > 0 {
+            // Step back over the bytes `inner` already pulled ahead, then drop them.
+            seekable.seek(io::SeekFrom::Current(-(held_back as i64))).ok()?;
+            self.inner.reset();
+        }
+        Some(seekable)
+    }
+
+    /// Seeks back to the start and resets the decoding state `read_line`
+    /// keeps between calls (BOM-skipping, the streaming `encoding_rs`
+    /// decoder), so reading can restart cleanly from byte 0. `None` if
+    /// [`Self::seekable`] would be `None`.
+    #[cfg(feature = "zero-copy")]
+    pub(crate) fn rewind(&mut self) -> Option<io::Result<()>> {
+        let result = self.seekable.as_mut()?.rewind();
+        if result.is_ok() {
+            // `inner` shares the same underlying bytes as `seekable`, but its
+            // own look-ahead buffer still holds whatever it had peeked at
+            // the old position -- drop it so reading restarts from byte 0.
+            self.inner.reset();
+            self.bom_stripped = false;
+            self.encoding_residual.clear();
+            self.encoding_decoder = Self::new_encoding_decoder(&self.content_type);
+        }
+        Some(result)
+    }
+
+    /// Uses `read_buf`/`BorrowedBuf` instead of a zeroed `[u8; WIDTH]` +
+    /// `Read::read`, so the scratch buffer -- re-allocated for every code
+    /// unit scanned -- is never memset before being filled with the bytes
+    /// actually read.
     fn read_char<const WIDTH: usize>(&mut self) -> io::Result<Option<[u8; WIDTH]>> {
-        let mut buffer = [0; WIDTH];
-        let mut read_bytes = 0;
-        while read_bytes < WIDTH {
-            let bytes = self.inner.read(&mut buffer[read_bytes..])?;
-            if bytes == 0 {
-                if read_bytes == 0 {
-                    return Ok(None);
+        let mut buffer = [MaybeUninit::<u8>::uninit(); WIDTH];
+        let mut buf = BorrowedBuf::from(&mut buffer[..]);
+        while buf.len() < WIDTH {
+            let filled_before = buf.len();
+            self.inner.read_buf(buf.unfilled())?;
+            if buf.len() == filled_before {
+                return if filled_before == 0 {
+                    Ok(None)
                 } else {
-                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+                    Err(io::Error::from(io::ErrorKind::UnexpectedEof))
+                };
+            }
+        }
+        let mut result = [0; WIDTH];
+        result.copy_from_slice(buf.filled());
+        Ok(Some(result))
+    }
+
+    /// Like [`Self::read_char`], but a short final read (a code unit
+    /// truncated by EOF) is reported as [`RawUnit::Truncated`] instead of an
+    /// error, for callers that transcode and fall back to `U+FFFD` rather
+    /// than failing outright.
+    fn read_raw_unit<const WIDTH: usize>(&mut self) -> io::Result<RawUnit<WIDTH>> {
+        let mut buffer = [MaybeUninit::<u8>::uninit(); WIDTH];
+        let mut buf = BorrowedBuf::from(&mut buffer[..]);
+        while buf.len() < WIDTH {
+            let filled_before = buf.len();
+            self.inner.read_buf(buf.unfilled())?;
+            if buf.len() == filled_before {
+                return Ok(if filled_before == 0 {
+                    RawUnit::End
+                } else {
+                    RawUnit::Truncated
+                });
+            }
+        }
+        let mut result = [0; WIDTH];
+        result.copy_from_slice(buf.filled());
+        Ok(RawUnit::Full(result))
+    }
+
+    /// Skips the BOM at the start of the stream, if any, for the wide
+    /// encodings that [`Self::read_line`] transcodes to UTF-8. Only has an
+    /// effect the first time it's called.
+    fn skip_bom(&mut self) -> io::Result<()> {
+        if std::mem::replace(&mut self.bom_stripped, true) {
+            return Ok(());
+        }
+        use ContentType::*;
+        let bom: &[u8] = match self.content_type {
+            Some(UTF_16LE) => &[0xFF, 0xFE],
+            Some(UTF_16BE) => &[0xFE, 0xFF],
+            Some(UTF_32LE) => &[0xFF, 0xFE, 0x00, 0x00],
+            Some(UTF_32BE) => &[0x00, 0x00, 0xFE, 0xFF],
+            _ => return Ok(()),
+        };
+        // `peek`, not `fill_buf` -- a short initial read (e.g. a slow pipe)
+        // could otherwise return fewer bytes than `bom` and miss a BOM that
+        // later bytes would have confirmed.
+        if self.peek(bom.len())?.starts_with(bom) {
+            self.inner.consume(bom.len());
+        }
+        Ok(())
+    }
+
+    /// Transcodes a UTF-16 line to UTF-8, one code unit at a time so that a
+    /// unit split across two `fill_buf` chunks is still read correctly (via
+    /// [`Self::read_raw_unit`]). A high surrogate is held back until the
+    /// following unit is read so the two can be combined into a single
+    /// `char`; an unpaired surrogate or a tail unit truncated by EOF becomes
+    /// `U+FFFD`.
+    fn scan_line_utf16<const LE: bool>(&mut self, buf: &mut Vec<u8>) -> io::Result<bool> {
+        let mut pending_high: Option<u16> = None;
+        let mut any = false;
+        loop {
+            match self.read_raw_unit::<2>()? {
+                RawUnit::Full(bytes) => {
+                    any = true;
+                    let unit = if LE {
+                        u16::from_le_bytes(bytes)
+                    } else {
+                        u16::from_be_bytes(bytes)
+                    };
+                    match unit {
+                        0x000A if pending_high.is_none() => {
+                            buf.push(b'\n');
+                            return Ok(true);
+                        }
+                        high if (0xD800..=0xDBFF).contains(&high) => {
+                            if pending_high.take().is_some() {
+                                push_char(buf, char::REPLACEMENT_CHARACTER);
+                            }
+                            pending_high = Some(high);
+                        }
+                        low if (0xDC00..=0xDFFF).contains(&low) => {
+                            let c = pending_high.take().and_then(|high| {
+                                let c = 0x10000
+                                    + (u32::from(high) - 0xD800) * 0x400
+                                    + (u32::from(low) - 0xDC00);
+                                char::from_u32(c)
+                            });
+                            push_char(buf, c.unwrap_or(char::REPLACEMENT_CHARACTER));
+                        }
+                        unit => {
+                            if pending_high.take().is_some() {
+                                push_char(buf, char::REPLACEMENT_CHARACTER);
+                            }
+                            push_char(
+                                buf,
+                                char::from_u32(u32::from(unit))
+                                    .unwrap_or(char::REPLACEMENT_CHARACTER),
+                            );
+                        }
+                    }
+                }
+                RawUnit::Truncated => {
+                    if pending_high.take().is_some() {
+                        push_char(buf, char::REPLACEMENT_CHARACTER);
+                    }
+                    push_char(buf, char::REPLACEMENT_CHARACTER);
+                    return Ok(true);
+                }
+                RawUnit::End => {
+                    if pending_high.take().is_some() {
+                        push_char(buf, char::REPLACEMENT_CHARACTER);
+                    }
+                    return Ok(any);
                 }
             }
-            read_bytes += bytes;
         }
-        Ok(Some(buffer))
+    }
+
+    /// Transcodes a UTF-32 line to UTF-8, one code unit at a time; see
+    /// [`Self::scan_line_utf16`] for how boundary-spanning units are
+    /// handled. An invalid scalar value or a tail unit truncated by EOF
+    /// becomes `U+FFFD`.
+    fn scan_line_utf32<const LE: bool>(&mut self, buf: &mut Vec<u8>) -> io::Result<bool> {
+        loop {
+            match self.read_raw_unit::<4>()? {
+                RawUnit::Full(bytes) => {
+                    let unit = if LE {
+                        u32::from_le_bytes(bytes)
+                    } else {
+                        u32::from_be_bytes(bytes)
+                    };
+                    if unit == 0x0000_000A {
+                        buf.push(b'\n');
+                        return Ok(true);
+                    }
+                    push_char(
+                        buf,
+                        char::from_u32(unit).unwrap_or(char::REPLACEMENT_CHARACTER),
+                    );
+                }
+                RawUnit::Truncated => {
+                    push_char(buf, char::REPLACEMENT_CHARACTER);
+                    return Ok(true);
+                }
+                RawUnit::End => return Ok(false),
+            }
+        }
+    }
+
+    /// Transcodes a `ContentType::Other` line to UTF-8 via the persistent
+    /// `encoding_decoder`, which is created once in [`Self::new`] and kept
+    /// between calls so multibyte sequences spanning `fill_buf` chunks
+    /// still decode correctly. Feeds chunks to the decoder with `last =
+    /// false` as they're read, and once with `last = true` at EOF to flush
+    /// any trailing state.
+    fn scan_line_encoding(&mut self, buf: &mut Vec<u8>) -> io::Result<bool> {
+        loop {
+            if let Some(pos) = self.encoding_residual.find('\n') {
+                buf.extend_from_slice(self.encoding_residual[..=pos].as_bytes());
+                self.encoding_residual.replace_range(..=pos, "");
+                return Ok(true);
+            }
+
+            let chunk = self.inner.fill_buf()?;
+            if chunk.is_empty() {
+                if let Some(decoder) = &mut self.encoding_decoder {
+                    decoder.decode_to_string(&[], &mut self.encoding_residual, true);
+                }
+                self.encoding_decoder = None;
+                return if self.encoding_residual.is_empty() {
+                    Ok(false)
+                } else {
+                    buf.extend_from_slice(self.encoding_residual.as_bytes());
+                    self.encoding_residual.clear();
+                    Ok(true)
+                };
+            }
+
+            let len = chunk.len();
+            let Some(decoder) = &mut self.encoding_decoder else {
+                // Already flushed (shouldn't happen before EOF); bail out.
+                return Ok(false);
+            };
+            let mut consumed = 0;
+            loop {
+                let (result, read, _) =
+                    decoder.decode_to_string(&chunk[consumed..], &mut self.encoding_residual, false);
+                consumed += read;
+                match result {
+                    CoderResult::InputEmpty => break,
+                    CoderResult::OutputFull => self.encoding_residual.reserve(len),
+                }
+            }
+            self.inner.consume(consumed);
+        }
     }
 
     fn scan_line<const WIDTH: usize>(
@@ -332,18 +949,41 @@ impl InputReader {
         }
     }
 
+    /// Reads one line, with the original newline still attached. For
+    /// UTF-16/UTF-32/`Other` inputs the line is transcoded to UTF-8 on the
+    /// fly (see [`Self::scan_line_utf16`]/[`Self::scan_line_utf32`]/
+    /// [`Self::scan_line_encoding`]), so the returned bytes are always valid
+    /// UTF-8 and the newline is always `\n`, regardless of the source
+    /// encoding.
     pub(crate) fn read_line(&mut self, buf: &mut Vec<u8>) -> io::Result<bool> {
         use ContentType::*;
+        self.skip_bom()?;
         match self.content_type {
-            Some(UTF_16LE) => self.scan_line(buf, [b'\n', b'\0']),
-            Some(UTF_16BE) => self.scan_line(buf, [b'\0', b'\n']),
-            Some(UTF_32LE) => self.scan_line(buf, [b'\n', b'\0', b'\0', b'\0']),
-            Some(UTF_32BE) => self.scan_line(buf, [b'\0', b'\0', b'\0', b'\n']),
+            Some(UTF_16LE) => self.scan_line_utf16::<true>(buf),
+            Some(UTF_16BE) => self.scan_line_utf16::<false>(buf),
+            Some(UTF_32LE) => self.scan_line_utf32::<true>(buf),
+            Some(UTF_32BE) => self.scan_line_utf32::<false>(buf),
+            Some(Other(_)) => self.scan_line_encoding(buf),
             _ => self.scan_line(buf, [b'\n']),
         }
     }
 }
 
+/// The result of reading one fixed-width code unit with
+/// [`InputReader::read_raw_unit`].
+enum RawUnit<const WIDTH: usize> {
+    Full([u8; WIDTH]),
+    /// EOF was reached partway through the unit.
+    Truncated,
+    /// EOF was reached before any of the unit's bytes were read.
+    End,
+}
+
+fn push_char(buf: &mut Vec<u8>, c: char) {
+    let mut tmp = [0; 4];
+    buf.extend_from_slice(c.encode_utf8(&mut tmp).as_bytes());
+}
+
 impl ContentType {
     pub(crate) fn is_binary(&self) -> bool {
         matches!(self, ContentType::Binary(_))
@@ -428,11 +1068,35 @@ pub(crate) fn decode<'a>(
             }
             s.into()
         }
+        Other(encoding) => encoding.decode_without_bom_handling(input).0,
         Binary(_) => return None,
     })
 }
 
-#[cfg(not(unix))]
+/// Like [`decode`], but for a line already read via
+/// [`InputReader::read_line`]: UTF-16/UTF-32/`Other` input is transcoded to
+/// UTF-8 by `read_line` itself, so such a line is returned as-is instead of
+/// being decoded a second time (which would misinterpret the already-UTF-8
+/// bytes as the original encoding).
+pub(crate) fn decode_line<'a>(
+    line: &'a [u8],
+    content_type: &ContentType,
+    is_first_line: bool,
+) -> Option<Cow<'a, str>> {
+    use ContentType::*;
+    match content_type {
+        UTF_16LE | UTF_16BE | UTF_32LE | UTF_32BE | Other(_) => Some(String::from_utf8_lossy(line)),
+        _ => decode(line, content_type, is_first_line),
+    }
+}
+
+/// Classifies a buffer's content. `content_inspector` tells UTF-8/UTF-16/
+/// UTF-32 text apart from everything else; anything it calls binary is given
+/// a second look by [`detect_legacy_encoding`], since a legacy/CJK-encoded
+/// text file (Shift-JIS, EUC-JP, GB18030, Big5, KOI8-R, Windows-125x, ...) is
+/// indistinguishable from binary data to a UTF-aware-only heuristic. Only if
+/// that also comes up empty does [`guess_binary_format`] try to put a
+/// human-readable name on the data, the same way `file -b` used to.
 fn inspect(buffer: &[u8]) -> ContentType {
     use content_inspector::ContentType::*;
     match content_inspector::inspect(buffer) {
@@ -441,53 +1105,181 @@ fn inspect(buffer: &[u8]) -> ContentType {
         UTF_16BE => ContentType::UTF_16BE,
         UTF_32LE => ContentType::UTF_32LE,
         UTF_32BE => ContentType::UTF_32BE,
-        BINARY => ContentType::Binary(None),
+        BINARY => detect_legacy_encoding(buffer)
+            .map(ContentType::Other)
+            .unwrap_or_else(|| ContentType::Binary(guess_binary_format(buffer))),
+    }
+}
+
+/// Tries to name `buffer`'s charset if it's actually legacy/CJK-encoded text
+/// that `content_inspector` mistook for binary data, by asking the `file`
+/// tool for its best guess (unix) or running a dedicated charset-detection
+/// crate over it (elsewhere). Returns `None` -- leaving the caller to fall
+/// back to [`guess_binary_format`] -- when the input really does look like
+/// binary data, or when detection isn't available/conclusive.
+#[cfg(unix)]
+fn detect_legacy_encoding(buffer: &[u8]) -> Option<&'static Encoding> {
+    let label = invoke_file(["--brief", "--mime-encoding", "-"], buffer)?;
+    match label.as_slice() {
+        // `file` falls back to these labels when it can't identify a text
+        // encoding either -- not a legacy-encoding hit.
+        b"binary" | b"unknown-8bit" | b"us-ascii" | b"utf-8" => None,
+        label => Encoding::for_label(label),
     }
 }
 
 #[cfg(unix)]
-fn execuate_file(args: impl IntoIterator<Item = impl AsRef<OsStr>>, buffer: &[u8]) -> Vec<u8> {
-    let failure_msg = "failed to execuate /usr/bin/file";
-    let mut child = Command::new("/usr/bin/file")
+fn invoke_file(args: impl IntoIterator<Item = impl AsRef<OsStr>>, buffer: &[u8]) -> Option<Vec<u8>> {
+    let mut child = Command::new("file")
         .args(args)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
+        .stderr(Stdio::null())
         .spawn()
-        .expect(failure_msg);
-    _ = child
-        .stdin
-        .take()
-        .expect(failure_msg)
-        .write(buffer)
-        .expect(failure_msg);
-    let output = child.wait_with_output().expect(failure_msg);
-    assert!(output.status.success(), "{}", failure_msg);
-    let mut s = output.stdout;
-    s.truncate(s.trim_end().len());
-    s
+        .ok()?;
+    child.stdin.take()?.write_all(buffer).ok()?;
+    let output = child.wait_with_output().ok()?;
+    output.status.success().then(|| output.stdout.trim_end().to_vec())
 }
 
-#[cfg(unix)]
-fn inspect(buffer: &[u8]) -> ContentType {
-    let encoding = execuate_file(["--brief", "--mime-encoding", "-"], buffer);
-    match encoding.as_slice() {
-        b"us-ascii" | b"utf-8" | b"unknown-8bit" => ContentType::UTF_8,
-        b"utf-16le" => ContentType::UTF_16LE,
-        b"utf-16be" => ContentType::UTF_16BE,
-        b"utf-32le" => ContentType::UTF_32LE,
-        b"utf-32be" => ContentType::UTF_32BE,
-        _ => ContentType::Binary({
-            let format = execuate_file(["--brief", "-"], buffer);
-            (&format != b"data" && &format != b"very short file (no magic)")
-                .then(|| format.into_string_lossy())
-        }),
+/// `chardetng` (the detector behind Firefox's charset sniffing) always
+/// returns its single best guess rather than `None`, even for data that isn't
+/// text at all -- so, mirroring `content_inspector`'s own core heuristic for
+/// telling text from binary data, a NUL byte anywhere in `buffer` is treated
+/// as a binary signal and skips detection entirely.
+#[cfg(not(unix))]
+fn detect_legacy_encoding(buffer: &[u8]) -> Option<&'static Encoding> {
+    if buffer.contains(&0) {
+        return None;
+    }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(buffer, true);
+    Some(detector.guess(None, false))
+}
+
+/// Identifies a handful of common binary file formats by their leading magic
+/// bytes, for the label the header prints for `Binary(Some(_))`. Formats not
+/// in this small table fall back to `Binary(None)`, same as an unrecognized
+/// format previously did under `file -b`.
+fn guess_binary_format(buffer: &[u8]) -> Option<String> {
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"\x7FELF", "ELF executable"),
+        (b"MZ", "DOS/PE executable"),
+        (b"\x89PNG\r\n\x1a\n", "PNG image"),
+        (b"GIF87a", "GIF image"),
+        (b"GIF89a", "GIF image"),
+        (b"\xFF\xD8\xFF", "JPEG image"),
+        (b"%PDF-", "PDF document"),
+        (b"PK\x03\x04", "Zip archive"),
+        (b"PK\x05\x06", "Zip archive (empty)"),
+        (b"\x1F\x8B", "gzip compressed data"),
+        (b"BZh", "bzip2 compressed data"),
+        (b"\xFD7zXZ\x00", "XZ compressed data"),
+        (b"\x28\xB5\x2F\xFD", "Zstandard compressed data"),
+    ];
+    MAGIC
+        .iter()
+        .find(|(magic, _)| buffer.starts_with(magic))
+        .map(|(_, name)| (*name).to_owned())
+}
+
+#[cfg(feature = "zero-copy")]
+#[test]
+fn leaky_slice_reader_seek_past_eof_clamps() {
+    let mut reader = LeakySliceReader::from_leaky_vec(b"hello".to_vec());
+
+    // Seeking past the end clamps to EOF rather than erroring -- `File`'s
+    // `Seek` doesn't error there either, though it keeps the past-EOF
+    // position -- since it's only a subsequent read that would observe
+    // anything (or rather, nothing).
+    assert_eq!(reader.seek(io::SeekFrom::Start(100)).unwrap(), 5);
+    assert_eq!(reader.fill_buf().unwrap(), b"");
+
+    assert_eq!(reader.seek(io::SeekFrom::Start(0)).unwrap(), 0);
+    assert_eq!(reader.seek(io::SeekFrom::End(100)).unwrap(), 5);
+    assert_eq!(reader.seek(io::SeekFrom::Current(-3)).unwrap(), 2);
+    assert_eq!(reader.fill_buf().unwrap(), b"llo");
+
+    // A seek to a negative position is an error, not a clamp.
+    assert!(reader.seek(io::SeekFrom::Current(-100)).is_err());
+}
+
+#[cfg(feature = "zero-copy")]
+#[test]
+fn seek_mid_utf16_codepoint_then_read_line() {
+    // BOM, then "ab\n" as UTF-16LE.
+    let content = b"\xFF\xFE\x61\x00\x62\x00\x0A\x00".to_vec();
+    let slice_reader = LeakySliceReader::from_leaky_vec(content);
+    let seek_handle = slice_reader.clone();
+
+    let mut reader = InputReader::new(slice_reader, None, DEFAULT_DETECTION_WINDOW)
+        .with_seekable(Some(seek_handle));
+
+    // Byte 3 is the high byte of 'a' (0x0061), so every code unit read from
+    // here on is shifted by one byte relative to how the stream was
+    // written. `read_line` has no way to know that; it must still come back
+    // with *something* well-formed instead of panicking.
+    reader
+        .seekable()
+        .unwrap()
+        .seek(io::SeekFrom::Start(3))
+        .unwrap();
+
+    let mut buffer = vec![];
+    let res = reader.read_line(&mut buffer);
+    assert!(res.is_ok());
+    assert!(res.unwrap());
+    assert!(std::str::from_utf8(&buffer).is_ok());
+}
+
+#[test]
+fn peek_reader_accumulates_across_short_fill_buf_chunks() {
+    // Returns one byte at a time, to exercise `PeekReader`'s slow path,
+    // where look-ahead has to be accumulated across several short
+    // `fill_buf` calls instead of being served from a single one.
+    struct OneByteAtATime<'a>(&'a [u8]);
+
+    impl Read for OneByteAtATime<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let slice = self.fill_buf()?;
+            let len = slice.len().min(buf.len());
+            buf[..len].copy_from_slice(&slice[..len]);
+            self.consume(len);
+            Ok(len)
+        }
     }
+
+    impl BufRead for OneByteAtATime<'_> {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            Ok(&self.0[..self.0.len().min(1)])
+        }
+
+        fn consume(&mut self, amt: usize) {
+            self.0 = &self.0[amt..];
+        }
+    }
+
+    let mut reader = PeekReader::new(Box::new(OneByteAtATime(b"hello world")));
+    assert_eq!(reader.peek(5).unwrap(), b"hello");
+    // Peeking further still includes the first peek's bytes, plus more.
+    assert_eq!(reader.peek(8).unwrap(), b"hello wo");
+
+    // The peeked-at bytes are still there to actually read afterwards.
+    let mut buf = [0; 5];
+    assert_eq!(reader.read(&mut buf).unwrap(), 5);
+    assert_eq!(&buf, b"hello");
+
+    assert!(!reader.is_eof().unwrap());
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b" world");
+    assert!(reader.is_eof().unwrap());
 }
 
 #[test]
 fn basic() {
     let content = b"#!/bin/bash\necho hello";
-    let mut reader = InputReader::new(&content[..]);
+    let mut reader = InputReader::new(&content[..], None, DEFAULT_DETECTION_WINDOW);
 
     assert_eq!("#!/bin/bash\n", &reader.first_read.as_ref().unwrap()[..12]);
 
@@ -515,22 +1307,24 @@ fn basic() {
 
 #[test]
 fn utf16le() {
+    // BOM, then "s\n", then "d" (no trailing newline).
     let content = b"\xFF\xFE\x73\x00\x0A\x00\x64\x00";
-    let mut reader = InputReader::new(&content[..]);
+    let mut reader = InputReader::new(&content[..], None, DEFAULT_DETECTION_WINDOW);
 
     let mut buffer = vec![];
 
+    // `read_line` transcodes to UTF-8 and strips the BOM.
     let res = reader.read_line(&mut buffer);
     assert!(res.is_ok());
     assert!(res.unwrap());
-    assert_eq!(b"\xFF\xFE\x73\x00\x0A\x00", &buffer[..]);
+    assert_eq!(b"s\n", &buffer[..]);
 
     buffer.clear();
 
     let res = reader.read_line(&mut buffer);
     assert!(res.is_ok());
     assert!(res.unwrap());
-    assert_eq!(b"\x64\x00", &buffer[..]);
+    assert_eq!(b"d", &buffer[..]);
 
     buffer.clear();
 
@@ -539,3 +1333,17 @@ fn utf16le() {
     assert!(!res.unwrap());
     assert!(buffer.is_empty());
 }
+
+#[test]
+fn utf16le_surrogate_pair_and_truncated_tail() {
+    // BOM, then U+1F600 (😀) as a surrogate pair, then an unpaired high
+    // surrogate followed by a truncated tail byte.
+    let content = b"\xFF\xFE\x3D\xD8\x00\xDE\x3D\xD8\x41";
+    let mut reader = InputReader::new(&content[..], None, DEFAULT_DETECTION_WINDOW);
+
+    let mut buffer = vec![];
+    let res = reader.read_line(&mut buffer);
+    assert!(res.is_ok());
+    assert!(res.unwrap());
+    assert_eq!("😀\u{FFFD}\u{FFFD}".as_bytes(), &buffer[..]);
+}
@@ -2,16 +2,19 @@
 use zwrite::{write, writeln};
 
 use std::alloc::{GlobalAlloc, Layout};
+use std::cell::Cell;
 use std::ffi::c_void;
-use std::io::{self, BufRead, Read};
+use std::fs::File;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
 use std::mem::forget;
+use std::rc::Rc;
 #[cfg(debug_assertions)]
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use bincode::BincodeRead;
 #[allow(clippy::wildcard_imports)] // too many imports
 use libmimalloc_sys::*;
-use memmap2::MmapMut;
+use memmap2::{MmapMut, MmapOptions};
 
 #[cfg(debug_assertions)]
 use crate::error::Error;
@@ -82,25 +85,70 @@ pub(crate) fn leak_mmap(mut mmap: MmapMut) -> &'static mut [u8] {
     slice
 }
 
+/// Maps `file`'s entire contents into a writable mapping and leaks it (via
+/// [`leak_mmap`]) into a `'static` slice, for reading it zero-copy through a
+/// [`LeakySliceReader`] without keeping `file` or the mapping itself around.
+///
+/// # Safety
+/// Same caveat as [`memmap2::MmapOptions::map_copy`]: `file` must not be
+/// modified by another process/thread while the returned slice is in use.
+pub(crate) unsafe fn create_file_mapped_leaky_slice(file: &File) -> io::Result<&'static mut [u8]> {
+    let len = usize::try_from(file.metadata()?.len())
+        .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+    let mmap = MmapOptions::new().len(len).map_copy(file)?;
+    Ok(leak_mmap(mmap))
+}
+
+/// Allocates `length` bytes via an anonymous mapping and leaks it (via
+/// [`leak_mmap`]) into a `'static` slice, for callers that need a
+/// [`LeakySliceReader`]-compatible buffer to fill in themselves (e.g.
+/// decompressing a cached asset into it).
+pub(crate) fn create_leaky_slice(length: usize) -> io::Result<&'static mut [u8]> {
+    let mmap = MmapOptions::new().len(length).map_anon()?;
+    Ok(leak_mmap(mmap))
+}
+
+/// A `Read`/`BufRead`/`BincodeRead` view over a leaked `'static` slice (an
+/// mmap'd file or another leaky allocation), for reading it without copying.
+///
+/// `pos` is shared (`Rc<Cell<_>>`) rather than owned so that a
+/// [`LeakySliceReader`] can be cheaply cloned into a second handle -- used to
+/// expose seeking on the underlying bytes (see `InputReader::seekable` in
+/// `crate::input`) -- that still advances in lockstep with the original
+/// reader when either is read from or seeked.
+#[derive(Clone)]
 pub(crate) struct LeakySliceReader {
-    ptr: *mut u8,
-    len: usize,
+    base: *mut u8,
+    total_len: usize,
+    pos: Rc<Cell<usize>>,
 }
 
 impl LeakySliceReader {
     pub fn new(slice: &'static mut [u8]) -> LeakySliceReader {
-        let ptr = slice.as_mut_ptr();
-        let len = slice.len();
+        let base = slice.as_mut_ptr();
+        let total_len = slice.len();
         assert!(
-            unsafe { !mi_is_in_heap_region(ptr.cast::<c_void>()) },
+            unsafe { !mi_is_in_heap_region(base.cast::<c_void>()) },
             "slice not leaky"
         );
-        LeakySliceReader { ptr, len }
+        LeakySliceReader {
+            base,
+            total_len,
+            pos: Rc::new(Cell::new(0)),
+        }
     }
 
     pub fn from_leaky_vec(mut vec: Vec<u8>) -> LeakySliceReader {
         Self::new(unsafe { std::slice::from_raw_parts_mut(vec.as_mut_ptr(), vec.len()) })
     }
+
+    fn remaining_len(&self) -> usize {
+        self.total_len - self.pos.get()
+    }
+
+    fn remaining_ptr(&self) -> *mut u8 {
+        unsafe { self.base.add(self.pos.get()) }
+    }
 }
 
 impl Read for LeakySliceReader {
@@ -111,28 +159,76 @@ impl Read for LeakySliceReader {
         self.consume(len);
         Ok(len)
     }
+
+    // The leaked bytes behind `self.base` are already initialized, so the
+    // cursor's uninitialized tail never needs to be touched -- no
+    // `MaybeUninit` unsafety required, unlike a generic `read_buf` impl.
+    fn read_buf(&mut self, mut cursor: io::BorrowedCursor<'_>) -> io::Result<()> {
+        let slice = self.fill_buf()?;
+        let len = slice.len().min(cursor.capacity());
+        cursor.append(&slice[..len]);
+        self.consume(len);
+        Ok(())
+    }
 }
 
 impl BufRead for LeakySliceReader {
     fn fill_buf(&mut self) -> io::Result<&[u8]> {
-        Ok(unsafe { std::slice::from_raw_parts(self.ptr, self.len) })
+        Ok(unsafe { std::slice::from_raw_parts(self.remaining_ptr(), self.remaining_len()) })
     }
 
     fn consume(&mut self, amt: usize) {
-        assert!(amt <= self.len, "comsume amount larger than length");
-        self.ptr = unsafe { self.ptr.add(amt) };
-        self.len -= amt;
+        assert!(amt <= self.remaining_len(), "consume amount larger than length");
+        self.pos.set(self.pos.get() + amt);
+    }
+}
+
+// `total_len` is O(1) to reach by pointer arithmetic from `base`, so every
+// `SeekFrom` variant is O(1): no data is scanned, only `pos` is updated.
+impl Seek for LeakySliceReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(n) => {
+                self.pos.set((n as usize).min(self.total_len));
+                return Ok(self.pos.get() as u64);
+            }
+            SeekFrom::End(n) => (self.total_len as u64, n),
+            SeekFrom::Current(n) => (self.pos.get() as u64, n),
+        };
+        let new_pos = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.unsigned_abs())
+        };
+        match new_pos {
+            // A seek past EOF clamps instead of erroring (`File`'s `Seek`
+            // doesn't error either, though it keeps the past-EOF position)
+            // -- it's only a subsequent read that would observe anything
+            // (or rather, nothing).
+            Some(n) => {
+                self.pos.set((n as usize).min(self.total_len));
+                Ok(self.pos.get() as u64)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.pos.get() as u64)
     }
 }
 
 impl LeakySliceReader {
     fn get_byte_slice(&mut self, length: usize) -> bincode::Result<&'static mut [u8]> {
-        if self.len < length {
+        if self.remaining_len() < length {
             return Err(Box::new(bincode::ErrorKind::Io(io::Error::from(
                 io::ErrorKind::UnexpectedEof,
             ))));
         }
-        let slice = unsafe { std::slice::from_raw_parts_mut(self.ptr, length) };
+        let slice = unsafe { std::slice::from_raw_parts_mut(self.remaining_ptr(), length) };
         self.consume(length);
         Ok(slice)
     }
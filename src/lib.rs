@@ -14,9 +14,16 @@ pub mod assets;
 pub mod config;
 pub mod controller;
 pub mod error;
+#[cfg(feature = "git")]
+mod git_diff;
+#[cfg(feature = "guesslang")]
+mod guesslang;
 pub mod input;
 pub mod output;
 pub mod printer;
+pub mod syntax_mapping;
+
+pub use syntax_mapping::SyntaxMapping;
 
 #[cfg(all(debug_assertions, feature = "zero-copy"))]
 mod membrane {
@@ -15,6 +15,14 @@ pub use pager::PagingMode;
 mod less;
 pub(crate) mod pager;
 
+/// Printed (concealed via the SGR "hidden" attribute) before each file
+/// header when `--navigate` is set. When `try_pager` launches `less` 600 or
+/// newer with bat's own arguments (not user-supplied ones), it adds an
+/// initial search for this exact text, so the ordinary search-repeat keys
+/// (`n`/`N`) step between the markers, i.e. between files, instead of
+/// requiring manual scrolling.
+pub const NAVIGATION_MARKER: &str = "bat-navigation-marker-6f6a02";
+
 #[derive(Debug)]
 pub struct InvalidPagerValueBat;
 
@@ -132,6 +140,10 @@ impl OutputType {
                         p.arg(format!("0,{col_header}"));
                         p.arg("--no-search-headers");
                     }
+
+                    if config.navigate {
+                        p.arg(format!("+/{NAVIGATION_MARKER}"));
+                    }
                 }
             } else {
                 p.args(args);
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+/// Column ranges (0-indexed, by Unicode display width, end-exclusive) to
+/// emphasize within specific lines, on top of whatever style syntax
+/// highlighting (and any [`super::overlay::StyleOverlays`]) already assigned
+/// — e.g. the exact span a search pattern matched in that line.
+#[derive(Debug, Clone, Default)]
+pub struct MatchHighlights {
+    ranges: HashMap<usize, Vec<(usize, usize)>>,
+}
+
+impl MatchHighlights {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks `[start_col, end_col)` of `line_number` (1-indexed, matching
+    /// bat's line numbering elsewhere) for highlighting.
+    pub fn add(&mut self, line_number: usize, start_col: usize, end_col: usize) {
+        self.ranges
+            .entry(line_number)
+            .or_default()
+            .push((start_col, end_col));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Returns the column ranges registered for `line_number`, if any.
+    pub fn for_line(&self, line_number: usize) -> &[(usize, usize)] {
+        self.ranges.get(&line_number).map_or(&[], Vec::as_slice)
+    }
+}
@@ -2,8 +2,8 @@ use std::borrow::Cow;
 use std::io;
 use std::io::Write;
 
-use console::AnsiCodeIterator;
-use nu_ansi_term::{Color as TermColor, Style};
+use nu_ansi_term::ansi::RESET;
+use nu_ansi_term::{Color as TermColor, Difference, Style};
 use serde::{Deserialize, Serialize};
 use syntect::easy::HighlightLines;
 use syntect::highlighting::Color;
@@ -13,26 +13,112 @@ use unicode_width::UnicodeWidthChar;
 
 use crate::assets::{HighlightingAssets, SyntaxReferenceInSet, SyntaxUndetected};
 use crate::config::Config;
-use crate::controller::line_range::RangeCheckResult;
+use crate::controller::diff::{classify_diff_line, file_marker_path, word_diff_ranges, DiffLineKind};
+use crate::controller::line_range::{LineRanges, RangeCheckResult};
 use crate::error::*;
-use crate::input::{decode, ContentType, OpenedInput};
-use preprocessor::{expand_tabs, replace_nonprintable};
-use terminal::{to_ansi_color, to_ansi_style};
+#[cfg(feature = "git")]
+use crate::git_diff::LineChange;
+use crate::input::{decode_line, ContentType, OpenedInput};
+use crate::output::NAVIGATION_MARKER;
+use preprocessor::{expand_tabs, hex_dump_rows, replace_nonprintable};
+use terminal::{to_ansi_color, to_ansi_style, ColorDepth, Mode};
 use vscreen::AnsiStyle;
 
+pub mod match_highlight;
+pub mod overlay;
 pub mod preprocessor;
+pub(crate) mod side_by_side;
 pub mod style;
-mod terminal;
+pub mod terminal;
 mod vscreen;
 
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum WrappingMode {
     Character,
+    /// Like `Character`, but breaks at whitespace/word boundaries instead of
+    /// mid-word, falling back to a character break only for a single word
+    /// too long to fit on its own line.
+    Word,
     // The bool specifies whether wrapping has been explicitly disabled by the user via --wrap=never
     #[default]
     NoWrapping,
 }
 
+/// Splits `text` into maximal runs of either whitespace or non-whitespace
+/// characters, in order, for [`WrappingMode::Word`].
+fn word_tokens(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let is_ws = rest.chars().next().unwrap().is_whitespace();
+        let split = rest
+            .char_indices()
+            .find(|&(_, c)| c.is_whitespace() != is_ws)
+            .map_or(rest.len(), |(i, _)| i);
+        let (token, remainder) = rest.split_at(split);
+        rest = remainder;
+        Some(token)
+    })
+}
+
+/// Scans `text` for inline CSI (`ESC [ … final-byte`, e.g. SGR color codes)
+/// and OSC (`ESC ] … BEL` or `ESC ] … ESC \`, e.g. hyperlinks) escape
+/// sequences, yielding `(chunk, is_escape)` pairs -- plain text chunks
+/// interleaved with escape-sequence chunks, the same shape as
+/// `console::AnsiCodeIterator`. Unlike that iterator, this one also
+/// recognizes OSC sequences, so hyperlink/title-setting passthrough in
+/// already-colored input doesn't leak into the plain-text chunks and get
+/// miscounted as display width during wrapping.
+fn vte_chunks(text: &str) -> impl Iterator<Item = (&str, bool)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        if let Some(len) = escape_sequence_len(rest) {
+            let (chunk, remainder) = rest.split_at(len);
+            rest = remainder;
+            return Some((chunk, true));
+        }
+
+        let len = rest.find('\x1b').unwrap_or(rest.len()).max(1);
+        let (chunk, remainder) = rest.split_at(len);
+        rest = remainder;
+        Some((chunk, false))
+    })
+}
+
+/// If `s` starts with a recognized CSI or OSC escape sequence, returns its
+/// byte length (including the introducer and terminator); otherwise `None`.
+fn escape_sequence_len(s: &str) -> Option<usize> {
+    let rest = s.strip_prefix('\x1b')?;
+
+    if let Some(body) = rest.strip_prefix('[') {
+        // CSI: any number of parameter/intermediate bytes (0x20..=0x3F),
+        // then exactly one final byte (0x40..=0x7E).
+        let final_byte_pos = body.bytes().position(|b| (0x40..=0x7E).contains(&b))?;
+        Some(2 + final_byte_pos + 1)
+    } else if let Some(body) = rest.strip_prefix(']') {
+        // OSC: runs until a BEL terminator or the two-byte ST (`ESC \`)
+        // terminator.
+        if let Some(bel_pos) = body.find('\x07') {
+            Some(2 + bel_pos + 1)
+        } else if let Some(st_pos) = body.find("\x1b\\") {
+            Some(2 + st_pos + 2)
+        } else {
+            // Unterminated -- swallow the rest of the line rather than
+            // letting a stray title-set/hyperlink sequence leak into the
+            // visible text.
+            Some(s.len())
+        }
+    } else {
+        None
+    }
+}
+
 #[allow(type_alias_bounds)]
 pub(crate) type OutputHandle<'a, W: Write> = &'a mut W;
 
@@ -58,11 +144,20 @@ pub(crate) trait Printer<W: Write> {
 
 pub(crate) struct SimplePrinter<'a> {
     config: &'a Config<'a>,
+    /// Running absolute byte offset and leftover bytes not yet long enough
+    /// for a full row, for [`Config::hex_dump`] -- see
+    /// [`preprocessor::hex_dump_rows`].
+    hex_dump_offset: usize,
+    hex_dump_pending: Vec<u8>,
 }
 
 impl<'a> SimplePrinter<'a> {
     pub(crate) fn new(config: &'a Config) -> Self {
-        SimplePrinter { config }
+        SimplePrinter {
+            config,
+            hex_dump_offset: 0,
+            hex_dump_pending: Vec::new(),
+        }
     }
 }
 
@@ -76,7 +171,13 @@ impl<'a, W: Write> Printer<W> for SimplePrinter<'a> {
         Ok(())
     }
 
-    fn print_footer(&mut self, _handle: OutputHandle<W>, _input: &OpenedInput) -> Result<()> {
+    fn print_footer(&mut self, handle: OutputHandle<W>, _input: &OpenedInput) -> Result<()> {
+        if self.config.hex_dump {
+            let rows = hex_dump_rows(&mut self.hex_dump_pending, &[], &mut self.hex_dump_offset, true);
+            if !rows.is_empty() {
+                writeln!(handle, "{}", rows)?;
+            }
+        }
         Ok(())
     }
 
@@ -91,6 +192,14 @@ impl<'a, W: Write> Printer<W> for SimplePrinter<'a> {
         _line_number: usize,
         line_buffer: &[u8],
     ) -> Result<()> {
+        if self.config.hex_dump {
+            let rows = hex_dump_rows(&mut self.hex_dump_pending, line_buffer, &mut self.hex_dump_offset, false);
+            if !out_of_range && !rows.is_empty() {
+                writeln!(handle, "{}", rows)?;
+            }
+            return Ok(());
+        }
+
         if !out_of_range {
             if let Some(nonprintable_notation) = self.config.nonprintable_notation {
                 let line =
@@ -121,6 +230,8 @@ impl<'a> HighlighterFromSet<'a> {
 pub(crate) struct InteractivePrinter<'a> {
     colors: Colors,
     config: &'a Config<'a>,
+    assets: &'a HighlightingAssets,
+    theme: &'a Theme,
     panel_width: usize,
     ansi_style: AnsiStyle,
     content_type: Option<ContentType>,
@@ -128,6 +239,42 @@ pub(crate) struct InteractivePrinter<'a> {
     background_color_highlight: Option<Color>,
     line_number_width: usize,
     line_number_width_invalid_at: usize,
+    overlay_rules: Vec<&'a overlay::OverlayRule>,
+    color_level: ColorDepth,
+    /// The detected (or overridden) terminal background, used by
+    /// [`to_ansi_style`] to keep syntax-highlight foregrounds legible
+    /// against it; see [`terminal::normalize_lightness`].
+    mode: Mode,
+    pending_diff_line: Option<PendingDiffLine>,
+    /// `config.highlighted_lines` with any sed-style regex addresses and
+    /// `$`-relative endpoints resolved against this input's actual content;
+    /// see `LineRanges::resolve_patterns` and `LineRanges::resolve`. Plain
+    /// numeric configs are cloned through unchanged.
+    highlighted_lines: LineRanges,
+    /// Running absolute byte offset and leftover bytes not yet long enough
+    /// for a full row, for [`Config::hex_dump`] -- see
+    /// [`preprocessor::hex_dump_rows`].
+    hex_dump_offset: usize,
+    hex_dump_pending: Vec<u8>,
+    /// Line numbers (1-based, in this input's content) that differ from the
+    /// file's git `HEAD` revision, for the `changes` style component's
+    /// gutter marker. `None` if that component isn't enabled, the input
+    /// isn't a path git can diff, or the file has no changes.
+    #[cfg(feature = "git")]
+    changes: Option<std::collections::BTreeMap<usize, LineChange>>,
+}
+
+/// A buffered `-` line whose possible paired `+` line hasn't been seen yet.
+/// Held until the next [`InteractivePrinter::print_line`] call resolves
+/// whether it pairs up (in which case [`word_diff_ranges`] highlights the
+/// tokens that actually changed) or not (in which case it's flushed as-is).
+struct PendingDiffLine {
+    line_number: usize,
+    line: String,
+    /// Owned copy of this line's syntax-highlighting regions, since the
+    /// `Vec<(Style, &str)>` syntect hands back borrows from the line text
+    /// that was fed to the highlighter, which has already moved on.
+    regions: Vec<(Style, String)>,
 }
 
 impl<'a> InteractivePrinter<'a> {
@@ -143,8 +290,11 @@ impl<'a> InteractivePrinter<'a> {
 
         let background_color_highlight = theme.settings.line_highlight;
 
+        let color_level = ColorDepth::detect(config.true_color);
+        let mode = Mode::detect(config.background_mode);
+
         let colors = if config.colored_output {
-            Colors::colored(theme, config.true_color)
+            Colors::colored(theme, color_level, mode, config)
         } else {
             Colors::plain()
         };
@@ -157,45 +307,155 @@ impl<'a> InteractivePrinter<'a> {
             panel_width = 0;
         }
 
+        let mut syntax_in_set = None;
         let highlighter_from_set = if input.reader.content_type.as_ref().map_or(false, |c| {
-            c.is_binary() && config.nonprintable_notation.is_none()
+            c.is_binary() && config.nonprintable_notation.is_none() && !config.hex_dump
         }) {
             None
         } else {
             // Determine the type of syntax for highlighting
-            let syntax_in_set =
-                match assets.get_syntax(config.language, input, &config.syntax_mapping) {
-                    Ok(syntax_in_set) => syntax_in_set,
-                    Err(e) if e.downcast_ref::<SyntaxUndetected>().is_some() => {
-                        assets.get_fallback_syntax()
-                    }
-                    Err(e) => return Err(e),
-                };
+            let syntax = match assets.get_syntax(config.language, input, &config.syntax_mapping) {
+                Ok(syntax_in_set) => syntax_in_set,
+                Err(e) if e.downcast_ref::<SyntaxUndetected>().is_some() => {
+                    assets.get_fallback_syntax()
+                }
+                Err(e) => return Err(e),
+            };
+            syntax_in_set = Some(syntax);
 
-            Some(HighlighterFromSet::new(syntax_in_set, theme))
+            Some(HighlighterFromSet::new(syntax, theme))
+        };
+
+        let overlay_rules = if config.style_overlays.is_empty() {
+            Vec::new()
+        } else {
+            let syntax_name = syntax_in_set.map(|syntax_in_set| syntax_in_set.syntax.name.as_str());
+            let file_name = input
+                .description
+                .name
+                .as_ref()
+                .and_then(|name| name.to_str());
+            let candidates: Vec<&str> = syntax_name.into_iter().chain(file_name).collect();
+            config.style_overlays.rules_for(&candidates)
+        };
+
+        #[cfg(feature = "git")]
+        let changes = config
+            .style_components
+            .changes()
+            .then(|| input.path())
+            .flatten()
+            .and_then(crate::git_diff::line_changes);
+
+        let highlighted_lines = &config.highlighted_lines.0;
+        let highlighted_lines = if highlighted_lines.needs_resolve() {
+            let lines = input.reader.peek_all_lines_lossy()?;
+            let resolved = highlighted_lines.resolve_patterns(lines.iter().map(String::as_str));
+            resolved.resolve(lines.len())?
+        } else {
+            highlighted_lines.clone()
         };
 
         Ok(InteractivePrinter {
             panel_width,
             colors,
             config,
+            assets,
+            theme,
             content_type: input.reader.content_type.clone(),
             ansi_style: AnsiStyle::new(),
             highlighter_from_set,
             background_color_highlight,
             line_number_width: 4,
             line_number_width_invalid_at: 10000,
+            overlay_rules,
+            color_level,
+            mode,
+            pending_diff_line: None,
+            highlighted_lines,
+            hex_dump_offset: 0,
+            hex_dump_pending: Vec::new(),
+            #[cfg(feature = "git")]
+            changes,
         })
     }
 
     pub(crate) fn get_panel_width(config: &'a Config) -> usize {
-        if config.style_components.numbers() {
-            5
-        } else {
-            0
+        let mut width = if config.style_components.numbers() { 5 } else { 0 };
+        #[cfg(feature = "git")]
+        if config.style_components.changes() {
+            width += 2;
+        }
+        width
+    }
+
+    /// Re-detects the syntax from `path` (extracted from a `+++`/`--- ` diff
+    /// file marker by [`crate::controller::diff::file_marker_path`]) and
+    /// swaps `self.highlighter_from_set` to match, so the following hunk's
+    /// content is highlighted as the file it belongs to, not as the diff
+    /// format itself. Leaves the current highlighter in place if `path`'s
+    /// syntax can't be determined.
+    fn update_diff_syntax(&mut self, path: &std::path::Path) {
+        if let Ok(syntax_in_set) = self
+            .assets
+            .get_syntax_for_path(path, &self.config.syntax_mapping)
+        {
+            self.highlighter_from_set = Some(HighlighterFromSet::new(syntax_in_set, self.theme));
+        }
+    }
+
+    /// Style for the tokens [`word_diff_ranges`] marks as changed within an
+    /// added/removed diff line. Only `Added`/`Removed` have a word-diff
+    /// style; anything else (context lines, which are never word-diffed)
+    /// gets a plain `Style` back, which is harmless since the caller only
+    /// ever applies it over an empty range list in that case.
+    fn diff_word_style(&self, diff_kind: Option<DiffLineKind>) -> Style {
+        let background = match diff_kind {
+            Some(DiffLineKind::Added) => DIFF_WORD_ADDED_BACKGROUND,
+            Some(DiffLineKind::Removed) => DIFF_WORD_REMOVED_BACKGROUND,
+            _ => return Style::default(),
+        };
+        Style {
+            background: to_ansi_color(background, self.color_level),
+            is_bold: true,
+            ..Style::default()
         }
     }
 
+    /// Writes a [`NAVIGATION_MARKER`], for `less +/{marker}` (see
+    /// [`crate::output::OutputType`]) to land on with `n`/`N`. Emitted once
+    /// per file header and once per `--diff` hunk header, so navigation
+    /// moves between both files and hunks rather than only between files.
+    fn print_navigation_marker<W: Write>(&self, handle: OutputHandle<W>) -> Result<()> {
+        // Concealed (SGR 8) so it doesn't show up on screen, but still
+        // present in the byte stream for `less +/{marker}` to land on.
+        writeln!(
+            handle,
+            "{}{NAVIGATION_MARKER}{}",
+            Style::new().hidden().prefix(),
+            Style::new().hidden().suffix()
+        )?;
+        Ok(())
+    }
+
+    /// Renders a `--diff` hunk/file header or other diff metadata line as a
+    /// section separator: the usual decorations (line number/grid) are still
+    /// printed for column alignment, but `line`'s content is shown in the
+    /// header style rather than being syntax highlighted, setting it apart
+    /// from the code lines surrounding it.
+    fn print_diff_section_header<W: Write>(
+        &mut self,
+        handle: OutputHandle<W>,
+        line_number: usize,
+        line: &str,
+    ) -> Result<()> {
+        self.print_decorations(line_number, false, handle)?;
+        write!(handle, "{}", self.colors.header_value.prefix())?;
+        write!(handle, "{}", line.trim_end_matches(['\r', '\n']))?;
+        writeln!(handle, "{}", self.colors.header_value.suffix())?;
+        Ok(())
+    }
+
     fn print_horizontal_line_term<W: Write>(
         &self,
         handle: OutputHandle<W>,
@@ -279,6 +539,30 @@ impl<'a> InteractivePrinter<'a> {
         Ok(1)
     }
 
+    /// Writes the `changes` gutter marker for `line_number`: `+` for an
+    /// added line, `~` for a modified one, `_` for a line immediately after
+    /// a deletion, or a blank space if the line is unchanged (or this input
+    /// has no change data at all).
+    #[cfg(feature = "git")]
+    fn print_changes_marker<W: Write>(
+        &self,
+        line_number: usize,
+        handle: OutputHandle<W>,
+    ) -> io::Result<usize> {
+        let (marker, color) = match self
+            .changes
+            .as_ref()
+            .and_then(|changes| changes.get(&line_number))
+        {
+            Some(LineChange::Added) => ('+', self.colors.changes_added),
+            Some(LineChange::Modified) => ('~', self.colors.changes_modified),
+            Some(LineChange::Removed) => ('_', self.colors.changes_removed),
+            None => (' ', Style::default()),
+        };
+        write!(handle, "{}{}{}", color.prefix(), marker, color.suffix())?;
+        Ok(1)
+    }
+
     fn print_decorations<W: Write>(
         &mut self,
         line_number: usize,
@@ -297,10 +581,560 @@ impl<'a> InteractivePrinter<'a> {
                 write!(handle, " ")?;
                 len += 1;
             }
+            #[cfg(feature = "git")]
+            if self.config.style_components.changes() {
+                len += self.print_changes_marker(line_number, handle)?;
+                write!(handle, " ")?;
+                len += 1;
+            }
         }
         Ok(len)
     }
 
+    /// Writes `text`, already styled with `base_style`'s prefix/suffix by the
+    /// caller, with `overlay_ranges` (the caller's [`Self::overlay_matches`]
+    /// results, sliced and shifted to byte ranges local to `text`, see
+    /// [`Self::render_diff_line`]), `diff_word_ranges` (the tokens
+    /// [`word_diff_ranges`] found changed within a diff line, styled with
+    /// `diff_word_style`) and `match_ranges` (byte ranges local to `text`,
+    /// see [`Self::intersect_match_ranges`]) layered on top: every matched
+    /// byte range is wrapped in the matching style, and `base_style` is
+    /// re-asserted afterward so the overlay nests cleanly inside the
+    /// existing syntax-highlighting color. Later layers paint over earlier
+    /// ones wherever their matches overlap, with `match_ranges` (the
+    /// intra-line match highlight) layered last so it always wins. A no-op,
+    /// falling back to writing `text` verbatim, when nothing applies.
+    fn write_overlaid<W: Write>(
+        &self,
+        handle: OutputHandle<W>,
+        base_style: Style,
+        text: &str,
+        overlay_ranges: &[(usize, usize, Style)],
+        diff_word_ranges: &[(usize, usize)],
+        diff_word_style: Style,
+        match_ranges: &[(usize, usize)],
+    ) -> io::Result<()> {
+        if overlay_ranges.is_empty() && diff_word_ranges.is_empty() && match_ranges.is_empty() {
+            return write!(handle, "{}", text);
+        }
+
+        let mut canvas: Vec<Option<Style>> = vec![None; text.len()];
+        for &(start, end, style) in overlay_ranges {
+            canvas[start..end].fill(Some(style));
+        }
+        for &(start, end) in diff_word_ranges {
+            canvas[start..end].fill(Some(diff_word_style));
+        }
+        for &(start, end) in match_ranges {
+            canvas[start..end].fill(Some(self.colors.match_highlight));
+        }
+
+        let mut pos = 0;
+        while pos < text.len() {
+            let run_style = canvas[pos];
+            let mut end = pos + 1;
+            while end < text.len() && canvas[end] == run_style {
+                end += 1;
+            }
+            match run_style {
+                None => write!(handle, "{}", &text[pos..end])?,
+                Some(overlay_style) => write!(
+                    handle,
+                    "{}{}{}{}",
+                    overlay_style.prefix(),
+                    &text[pos..end],
+                    overlay_style.suffix(),
+                    base_style.prefix(),
+                )?,
+            }
+            pos = end;
+        }
+
+        Ok(())
+    }
+
+    /// Runs every [`Self::overlay_rules`] pattern against the full
+    /// reassembled `text` (a whole display line, not a syntax-highlighting
+    /// fragment of one) once, so `^`/`$` anchors see real line boundaries
+    /// and a match can't be missed just because syntax highlighting split
+    /// its bytes across two tokens. Returns the matched byte ranges (local
+    /// to `text`) with each rule's style, in registration order, so a
+    /// caller can slice them back onto the per-fragment output passed to
+    /// [`Self::write_overlaid`] -- see [`Self::render_diff_line`].
+    fn overlay_matches(&self, text: &str) -> Vec<(usize, usize, Style)> {
+        self.overlay_rules
+            .iter()
+            .flat_map(|rule| {
+                rule.pattern
+                    .find_iter(text)
+                    .map(move |m| (m.start(), m.end(), rule.style))
+            })
+            .collect()
+    }
+
+    /// Converts [`Self::overlay_matches`]' byte ranges (local to `text`)
+    /// into display-column ranges over the same text, for
+    /// [`Self::print_wrapped_char`], which tracks position by column rather
+    /// than byte offset. Match boundaries always fall on a char boundary
+    /// (they come from matching a compiled regex against valid UTF-8), so
+    /// each one has an exact corresponding column.
+    fn overlay_matches_to_columns(
+        text: &str,
+        overlay_matches: &[(usize, usize, Style)],
+    ) -> Vec<(usize, usize, Style)> {
+        if overlay_matches.is_empty() {
+            return Vec::new();
+        }
+
+        let mut offsets = Vec::with_capacity(text.len() + 1);
+        let mut byte = 0;
+        let mut col = 0;
+        for ch in text.chars() {
+            offsets.push((byte, col));
+            byte += ch.len_utf8();
+            col += ch.width().unwrap_or(0);
+        }
+        offsets.push((byte, col));
+
+        let col_of = |target: usize| -> usize {
+            match offsets.binary_search_by_key(&target, |&(byte, _)| byte) {
+                Ok(i) => offsets[i].1,
+                Err(i) => offsets[i.saturating_sub(1)].1,
+            }
+        };
+
+        overlay_matches
+            .iter()
+            .map(|&(start, end, style)| (col_of(start), col_of(end), style))
+            .collect()
+    }
+
+    /// Converts the portions of `ranges` (line-relative column ranges, by
+    /// Unicode display width, end-exclusive) that fall within `text` into
+    /// byte ranges local to `text`, suitable for [`Self::write_overlaid`].
+    /// `col` is the display column `text` starts at, and is advanced by
+    /// `text`'s total display width (each zero-width character counting as
+    /// one column) as a side effect, so it can be threaded across
+    /// successive chunks of the same line.
+    fn intersect_match_ranges(
+        text: &str,
+        col: &mut usize,
+        ranges: &[(usize, usize)],
+    ) -> Vec<(usize, usize)> {
+        let mut byte_ranges = Vec::new();
+        if ranges.is_empty() {
+            return byte_ranges;
+        }
+
+        let mut run_start: Option<usize> = None;
+        let mut byte_idx = 0;
+        for ch in text.chars() {
+            // Zero-width characters (combining marks, ...) still occupy one
+            // column slot here, so they can't freeze `col` in place.
+            let width = ch.width().unwrap_or(0).max(1);
+            let matched = ranges
+                .iter()
+                .any(|&(start, end)| *col < end && *col + width > start);
+            match (matched, run_start) {
+                (true, None) => run_start = Some(byte_idx),
+                (false, Some(start)) => {
+                    byte_ranges.push((start, byte_idx));
+                    run_start = None;
+                }
+                _ => {}
+            }
+            byte_idx += ch.len_utf8();
+            *col += width;
+        }
+        if let Some(start) = run_start {
+            byte_ranges.push((start, byte_idx));
+        }
+        byte_ranges
+    }
+
+    /// Flushes a buffered `-` line that never got paired with a following
+    /// `+` line (e.g. it's followed by another `-`, a context line, or the
+    /// hunk ends), rendering it plainly with no word-level diff highlight.
+    fn flush_pending_diff_line<W: Write>(&mut self, handle: OutputHandle<W>) -> Result<()> {
+        let Some(pending) = self.pending_diff_line.take() else {
+            return Ok(());
+        };
+        let regions: Vec<(Style, &str)> = pending
+            .regions
+            .iter()
+            .map(|(style, text)| (*style, text.as_str()))
+            .collect();
+        self.render_diff_line(
+            handle,
+            pending.line_number,
+            &pending.line,
+            Some(DiffLineKind::Removed),
+            &regions,
+            &[],
+        )
+    }
+
+    /// Renders one already-highlighted line's decorations and content,
+    /// tinting it with `diff_kind`'s background and, on top of that,
+    /// `diff_word_ranges` (the changed tokens a paired `-`/`+` line found via
+    /// [`word_diff_ranges`], empty for a line with no pairing).
+    #[allow(clippy::too_many_arguments)]
+    fn render_diff_line<W: Write>(
+        &mut self,
+        handle: OutputHandle<W>,
+        line_number: usize,
+        line: &str,
+        diff_kind: Option<DiffLineKind>,
+        regions: &[(Style, &str)],
+        diff_word_ranges: &[(usize, usize)],
+    ) -> Result<()> {
+        let mut cursor: usize = 0;
+        let mut cursor_max: usize = self.config.term_width;
+        let mut cursor_total: usize = 0;
+
+        // Line highlighting
+        let highlight_this_line =
+            self.highlighted_lines.check(line_number) == RangeCheckResult::InRange;
+
+        if highlight_this_line
+            && self
+                .config
+                .theme
+                .as_ref()
+                .map(|name| name == "ansi")
+                .unwrap_or(false)
+        {
+            self.ansi_style.update("^[4m");
+        }
+
+        let diff_background = match diff_kind {
+            Some(DiffLineKind::Added) => Some(DIFF_ADDED_BACKGROUND),
+            Some(DiffLineKind::Removed) => Some(DIFF_REMOVED_BACKGROUND),
+            _ => None,
+        };
+
+        let background_color = diff_background.or_else(|| {
+            self.background_color_highlight
+                .filter(|_| highlight_this_line)
+        });
+
+        let diff_word_style = self.diff_word_style(diff_kind);
+
+        // Line decorations.
+        cursor_max -= self.print_decorations(line_number, false, handle)?;
+
+        // Line contents.
+        let color_level = self.color_level;
+        let mode = self.mode;
+        let colored_output = self.config.colored_output;
+        let italics = self.config.use_italic_text;
+        let line_match_ranges = self.config.match_highlights.for_line(line_number);
+
+        // Reassemble the whole line's displayed text (tabs expanded, ANSI
+        // passthrough dropped, same as the real loops below) so
+        // `self.overlay_rules` can be matched against real line boundaries
+        // instead of one syntax-highlighting fragment at a time. Used by
+        // both render paths below: the `NoWrapping` path slices the result
+        // back onto each fragment by byte offset, the wrapping path
+        // converts it to display-column ranges via
+        // `Self::overlay_matches_to_columns`.
+        let full_text = {
+            let mut full_text = String::new();
+            let mut scratch_cursor = 0;
+            for &(_, region) in regions {
+                for chunk in vte_chunks(region) {
+                    if let (text, false) = chunk {
+                        let text_cow = self.preprocess(text, &mut scratch_cursor);
+                        let text_trimmed = text_cow.trim_end_matches(|c| c == '\r' || c == '\n');
+                        full_text.push_str(text_trimmed);
+                    }
+                }
+            }
+            full_text
+        };
+        let overlay_matches = self.overlay_matches(&full_text);
+
+        if self.config.wrapping_mode == WrappingMode::NoWrapping {
+            let mut display_col: usize = 0;
+            let mut text_offset: usize = 0;
+            let mut current_style = Style::default();
+            for &(style, region) in regions {
+                let ansi_iterator = vte_chunks(region);
+                for chunk in ansi_iterator {
+                    match chunk {
+                        // ANSI escape passthrough.
+                        (ansi, true) => {
+                            // `AnsiStyle::update` only understands CSI/SGR
+                            // sequences; OSC sequences (hyperlinks, etc.)
+                            // pass through untouched without updating the
+                            // tracked style.
+                            if ansi.starts_with("\x1b[") {
+                                self.ansi_style.update(ansi);
+                            }
+                            write!(handle, "{}", ansi)?;
+                        }
+
+                        // Regular text.
+                        (text, false) => {
+                            let text_cow = self.preprocess(text, &mut cursor_total);
+                            let text = text_cow.as_ref();
+                            let text_trimmed = text.trim_end_matches(|c| c == '\r' || c == '\n');
+
+                            if !text_trimmed.is_empty() {
+                                let style = to_ansi_style(
+                                    style,
+                                    color_level,
+                                    mode,
+                                    colored_output,
+                                    italics,
+                                    background_color,
+                                );
+                                // Computed from a copy of `display_col` so the
+                                // canonical cursor only advances once, via the
+                                // match-range lookup below.
+                                let mut diff_word_col = display_col;
+                                let diff_word_byte_ranges = Self::intersect_match_ranges(
+                                    text_trimmed,
+                                    &mut diff_word_col,
+                                    diff_word_ranges,
+                                );
+                                let match_ranges = Self::intersect_match_ranges(
+                                    text_trimmed,
+                                    &mut display_col,
+                                    line_match_ranges,
+                                );
+                                let fragment_start = text_offset;
+                                let fragment_end = fragment_start + text_trimmed.len();
+                                text_offset = fragment_end;
+                                let fragment_overlay_ranges: Vec<(usize, usize, Style)> =
+                                    overlay_matches
+                                        .iter()
+                                        .filter_map(|&(start, end, overlay_style)| {
+                                            let start = start.max(fragment_start);
+                                            let end = end.min(fragment_end);
+                                            (start < end).then(|| {
+                                                (
+                                                    start - fragment_start,
+                                                    end - fragment_start,
+                                                    overlay_style,
+                                                )
+                                            })
+                                        })
+                                        .collect();
+                                // Adjacent regions are usually both non-plain
+                                // (syntax-highlighted source is mostly
+                                // colored tokens), so jumping straight to a
+                                // full RESET + prefix between every one of
+                                // them wastes bytes; emit only the minimal
+                                // transition from the style still active on
+                                // the terminal.
+                                match Difference::between(current_style, style) {
+                                    Difference::NoDifference => {}
+                                    Difference::ExtraStyles(extra) => {
+                                        write!(handle, "{}", extra.prefix())?
+                                    }
+                                    Difference::Reset => {
+                                        write!(handle, "{}{}", RESET, style.prefix())?
+                                    }
+                                }
+                                current_style = style;
+                                write!(handle, "{}", &self.ansi_style)?;
+                                self.write_overlaid(
+                                    handle,
+                                    style,
+                                    text_trimmed,
+                                    &fragment_overlay_ranges,
+                                    &diff_word_byte_ranges,
+                                    diff_word_style,
+                                    &match_ranges,
+                                )?;
+                            }
+
+                            if text.len() != text_trimmed.len() {
+                                if let Some(background_color) = background_color {
+                                    let ansi_style = Style {
+                                        background: to_ansi_color(background_color, color_level),
+                                        ..Default::default()
+                                    };
+
+                                    if let Some(width) =
+                                        cursor_max.checked_sub(cursor_total).map(|width| width + 1)
+                                    {
+                                        write!(handle, "{}", ansi_style.prefix())?;
+                                        for _ in 0..width {
+                                            write!(handle, " ")?;
+                                        }
+                                        write!(handle, "{}", ansi_style.suffix())?;
+                                    }
+                                }
+                                write!(handle, "{}", &text[text_trimmed.len()..])?;
+                            }
+                        }
+                    }
+                }
+            }
+
+            if !current_style.is_plain() {
+                write!(handle, "{}", RESET)?;
+            }
+
+            if !self.config.style_components.plain() && line.bytes().next_back() != Some(b'\n') {
+                writeln!(handle)?;
+            }
+        } else {
+            let overlay_col_ranges = Self::overlay_matches_to_columns(&full_text, &overlay_matches);
+            let mut display_col: usize = 0;
+            let mut current_style = Style::default();
+            for &(style, region) in regions {
+                let ansi_iterator = vte_chunks(region);
+                for chunk in ansi_iterator {
+                    match chunk {
+                        // ANSI escape passthrough.
+                        (ansi, true) => {
+                            // `AnsiStyle::update` only understands CSI/SGR
+                            // sequences; OSC sequences (hyperlinks, etc.)
+                            // pass through untouched without updating the
+                            // tracked style.
+                            if ansi.starts_with("\x1b[") {
+                                self.ansi_style.update(ansi);
+                            }
+                            write!(handle, "{}", ansi)?;
+                        }
+
+                        // Regular text.
+                        (text, false) => {
+                            let text = self.preprocess(
+                                text.trim_end_matches(|c| c == '\r' || c == '\n'),
+                                &mut cursor_total,
+                            );
+
+                            let mut max_width = cursor_max - cursor;
+                            let mut current_width = 0;
+
+                            let style = to_ansi_style(
+                                style,
+                                color_level,
+                                mode,
+                                colored_output,
+                                italics,
+                                background_color,
+                            );
+                            // As in the `NoWrapping` branch above, emit only
+                            // the minimal transition from the style still
+                            // active on the terminal rather than a full
+                            // RESET + prefix for every region.
+                            match Difference::between(current_style, style) {
+                                Difference::NoDifference => {}
+                                Difference::ExtraStyles(extra) => {
+                                    write!(handle, "{}", extra.prefix())?
+                                }
+                                Difference::Reset => {
+                                    write!(handle, "{}{}", RESET, style.prefix())?
+                                }
+                            }
+                            current_style = style;
+                            write!(handle, "{}", &self.ansi_style)?;
+
+                            let mut active_overlay: Option<Style> = None;
+                            let word_wrap = self.config.wrapping_mode == WrappingMode::Word;
+                            for token in word_tokens(&text) {
+                                // In word-wrap mode, a word that doesn't fit in what's
+                                // left of this line is wrapped to a fresh line as a
+                                // whole, rather than mid-word, as long as it's not
+                                // itself too long to fit on a fresh line (in which
+                                // case it falls back to being character-wrapped by
+                                // the per-character check below, same as `Character`
+                                // mode).
+                                if word_wrap && !token.starts_with(char::is_whitespace) {
+                                    let token_width: usize =
+                                        token.chars().map(|c| c.width().unwrap_or(0)).sum();
+                                    if token_width <= cursor_max
+                                        && current_width + token_width > max_width
+                                    {
+                                        self.start_new_wrapped_line(
+                                            handle,
+                                            line_number,
+                                            cursor_max,
+                                            style,
+                                            &mut cursor,
+                                            &mut max_width,
+                                            &mut current_width,
+                                            &mut active_overlay,
+                                        )?;
+                                    }
+                                }
+
+                                for c in token.chars() {
+                                    // calculate the displayed width for next character
+                                    let cw = c.width().unwrap_or(0);
+                                    self.print_wrapped_char(
+                                        handle,
+                                        line_number,
+                                        cursor_max,
+                                        style,
+                                        c,
+                                        cw,
+                                        &mut cursor,
+                                        &mut max_width,
+                                        &mut current_width,
+                                        &mut display_col,
+                                        &mut active_overlay,
+                                        line_match_ranges,
+                                        diff_word_ranges,
+                                        diff_word_style,
+                                        &overlay_col_ranges,
+                                    )?;
+                                }
+                            }
+
+                            if let Some(overlay_style) = active_overlay {
+                                write!(handle, "{}", overlay_style.suffix())?;
+                            }
+
+                            // flush the buffer
+                            cursor += current_width;
+                        }
+                    }
+                }
+            }
+
+            if !current_style.is_plain() {
+                write!(handle, "{}", RESET)?;
+            }
+
+            if let Some(background_color) = background_color {
+                let ansi_style = Style {
+                    background: to_ansi_color(background_color, color_level),
+                    ..Default::default()
+                };
+
+                let width = cursor_max - cursor;
+                if width != 0 {
+                    write!(handle, "{}", ansi_style.prefix())?;
+                    for _ in 0..width {
+                        write!(handle, " ")?;
+                    }
+                    write!(handle, "{}", ansi_style.suffix())?;
+                }
+            }
+            writeln!(handle)?;
+        }
+
+        if highlight_this_line
+            && self
+                .config
+                .theme
+                .as_ref()
+                .map(|name| name == "ansi")
+                .unwrap_or(false)
+        {
+            self.ansi_style.update("^[24m");
+            write!(handle, "\x1B[24m")?;
+        }
+
+        Ok(())
+    }
+
     fn preprocess<'b>(&self, text: &'b str, cursor: &mut usize) -> Cow<'b, str> {
         if self.config.tab_width != 0 {
             expand_tabs(text, self.config.tab_width, cursor)
@@ -318,6 +1152,10 @@ impl<'a, W: Write> Printer<W> for InteractivePrinter<'a> {
         input: &OpenedInput,
         add_header_padding: bool,
     ) -> Result<()> {
+        if self.config.navigate {
+            self.print_navigation_marker(handle)?;
+        }
+
         if add_header_padding && self.config.style_components.rule() {
             self.print_horizontal_line_term(handle, self.colors.rule)?;
         }
@@ -388,19 +1226,17 @@ impl<'a, W: Write> Printer<W> for InteractivePrinter<'a> {
                     self.colors.header_value.suffix()
                 )?;
             }
-            write!(
-                handle,
-                "{}",
-                match self.content_type {
-                    Some(ContentType::Binary(_)) => "   <BINARY>",
-                    Some(ContentType::UTF_16LE) => "   <UTF-16LE>",
-                    Some(ContentType::UTF_16BE) => "   <UTF-16BE>",
-                    Some(ContentType::UTF_32LE) => "   <UTF-32LE>",
-                    Some(ContentType::UTF_32BE) => "   <UTF-32BE>",
-                    Some(ContentType::UTF_8) => "",
-                    None => "   <EMPTY>",
-                },
-            )?;
+            let content_type_label: String = match self.content_type {
+                Some(ContentType::Binary(_)) => "   <BINARY>".into(),
+                Some(ContentType::UTF_16LE) => "   <UTF-16LE>".into(),
+                Some(ContentType::UTF_16BE) => "   <UTF-16BE>".into(),
+                Some(ContentType::UTF_32LE) => "   <UTF-32LE>".into(),
+                Some(ContentType::UTF_32BE) => "   <UTF-32BE>".into(),
+                Some(ContentType::Other(encoding)) => format!("   <{}>", encoding.name()),
+                Some(ContentType::UTF_8) => "".into(),
+                None => "   <EMPTY>".into(),
+            };
+            write!(handle, "{content_type_label}")?;
             if let Some(ContentType::Binary(Some(ref binary_type))) = self.content_type {
                 writeln!(handle, " {}", binary_type)?;
             } else {
@@ -411,6 +1247,7 @@ impl<'a, W: Write> Printer<W> for InteractivePrinter<'a> {
         if self.config.style_components.grid() {
             if self.content_type.as_ref().map_or(false, |c| c.is_text())
                 || self.config.nonprintable_notation.is_some()
+                || self.config.hex_dump
             {
                 self.print_horizontal_line(handle, '┼')?;
             } else {
@@ -422,9 +1259,19 @@ impl<'a, W: Write> Printer<W> for InteractivePrinter<'a> {
     }
 
     fn print_footer(&mut self, handle: OutputHandle<W>, _input: &OpenedInput) -> Result<()> {
+        self.flush_pending_diff_line(handle)?;
+
+        if self.config.hex_dump {
+            let rows = hex_dump_rows(&mut self.hex_dump_pending, &[], &mut self.hex_dump_offset, true);
+            if !rows.is_empty() {
+                writeln!(handle, "{}", rows)?;
+            }
+        }
+
         if self.config.style_components.grid()
             && (self.content_type.as_ref().map_or(false, |c| c.is_text())
-                || self.config.nonprintable_notation.is_some())
+                || self.config.nonprintable_notation.is_some()
+                || self.config.hex_dump)
         {
             Ok(self.print_horizontal_line(handle, '┴')?)
         } else {
@@ -433,6 +1280,8 @@ impl<'a, W: Write> Printer<W> for InteractivePrinter<'a> {
     }
 
     fn print_snip(&mut self, handle: OutputHandle<W>) -> Result<()> {
+        self.flush_pending_diff_line(handle)?;
+
         write!(handle, "{}", self.colors.grid.prefix())?;
 
         let panel_text = " ...";
@@ -479,19 +1328,49 @@ impl<'a, W: Write> Printer<W> for InteractivePrinter<'a> {
         line_number: usize,
         line_buffer: &[u8],
     ) -> Result<()> {
-        let line = if let Some(nonprintable_notation) = self.config.nonprintable_notation {
+        let diff_kind = self.config.diff.then(|| classify_diff_line(line_buffer));
+
+        let line: Cow<str> = if self.config.hex_dump {
+            let mut rows = hex_dump_rows(&mut self.hex_dump_pending, line_buffer, &mut self.hex_dump_offset, false);
+            if rows.is_empty() {
+                return Ok(());
+            }
+            rows.push('\n');
+            rows.into()
+        } else if let Some(nonprintable_notation) = self.config.nonprintable_notation {
             replace_nonprintable(line_buffer, self.config.tab_width, nonprintable_notation).into()
         } else {
             match self
                 .content_type
                 .as_ref()
-                .and_then(|content_type| decode(line_buffer, content_type, line_number == 1))
+                .and_then(|content_type| decode_line(line_buffer, content_type, line_number == 1))
             {
                 Some(line) => line,
                 None => return Ok(()),
             }
         };
 
+        if let Some(kind) = diff_kind {
+            if kind == DiffLineKind::FileMarker {
+                if let Some(path) = file_marker_path(&line) {
+                    self.update_diff_syntax(path);
+                }
+            }
+            if matches!(
+                kind,
+                DiffLineKind::FileMarker | DiffLineKind::HunkHeader | DiffLineKind::Meta
+            ) {
+                self.flush_pending_diff_line(handle)?;
+                if out_of_range {
+                    return Ok(());
+                }
+                if kind == DiffLineKind::HunkHeader && self.config.navigate {
+                    self.print_navigation_marker(handle)?;
+                }
+                return self.print_diff_section_header(handle, line_number, &line);
+            }
+        }
+
         let regions = {
             let highlighter_from_set = match self.highlighter_from_set {
                 Some(ref mut highlighter_from_set) => highlighter_from_set,
@@ -515,197 +1394,259 @@ impl<'a, W: Write> Printer<W> for InteractivePrinter<'a> {
         };
 
         if out_of_range {
+            self.flush_pending_diff_line(handle)?;
             return Ok(());
         }
 
-        let mut cursor: usize = 0;
-        let mut cursor_max: usize = self.config.term_width;
-        let mut cursor_total: usize = 0;
-
-        // Line highlighting
-        let highlight_this_line =
-            self.config.highlighted_lines.0.check(line_number) == RangeCheckResult::InRange;
-
-        if highlight_this_line
-            && self
-                .config
-                .theme
-                .as_ref()
-                .map(|name| name == "ansi")
-                .unwrap_or(false)
-        {
-            self.ansi_style.update("^[4m");
+        // A `-` line is held back until we know whether the next line is its
+        // paired `+` (in which case the two get word-level diff highlight),
+        // since that can't be decided without a line of lookahead.
+        if diff_kind == Some(DiffLineKind::Removed) {
+            self.flush_pending_diff_line(handle)?;
+            let owned_regions: Vec<(Style, String)> = regions
+                .iter()
+                .map(|&(style, text)| (style, text.to_owned()))
+                .collect();
+            self.pending_diff_line = Some(PendingDiffLine {
+                line_number,
+                line: line.into_owned(),
+                regions: owned_regions,
+            });
+            return Ok(());
         }
 
-        let background_color = self
-            .background_color_highlight
-            .filter(|_| highlight_this_line);
-
-        // Line decorations.
-        cursor_max -= self.print_decorations(line_number, false, handle)?;
-
-        // Line contents.
-        let true_color = self.config.true_color;
-        let colored_output = self.config.colored_output;
-        let italics = self.config.use_italic_text;
-        if self.config.wrapping_mode == WrappingMode::NoWrapping {
-            for &(style, region) in &regions {
-                let ansi_iterator = AnsiCodeIterator::new(region);
-                for chunk in ansi_iterator {
-                    match chunk {
-                        // ANSI escape passthrough.
-                        (ansi, true) => {
-                            self.ansi_style.update(ansi);
-                            write!(handle, "{}", ansi)?;
-                        }
-
-                        // Regular text.
-                        (text, false) => {
-                            let text_cow = self.preprocess(text, &mut cursor_total);
-                            let text = text_cow.as_ref();
-                            let text_trimmed = text.trim_end_matches(|c| c == '\r' || c == '\n');
-
-                            if !text_trimmed.is_empty() {
-                                let style = to_ansi_style(
-                                    style,
-                                    true_color,
-                                    colored_output,
-                                    italics,
-                                    background_color,
-                                );
-                                write!(
-                                    handle,
-                                    "{}{}{}{}",
-                                    style.prefix(),
-                                    &self.ansi_style,
-                                    text_trimmed,
-                                    style.suffix()
-                                )?;
-                            }
-
-                            if text.len() != text_trimmed.len() {
-                                if let Some(background_color) = background_color {
-                                    let ansi_style = Style {
-                                        background: to_ansi_color(background_color, true_color),
-                                        ..Default::default()
-                                    };
-
-                                    if let Some(width) =
-                                        cursor_max.checked_sub(cursor_total).map(|width| width + 1)
-                                    {
-                                        write!(handle, "{}", ansi_style.prefix())?;
-                                        for _ in 0..width {
-                                            write!(handle, " ")?;
-                                        }
-                                        write!(handle, "{}", ansi_style.suffix())?;
-                                    }
-                                }
-                                write!(handle, "{}", &text[text_trimmed.len()..])?;
-                            }
-                        }
-                    }
+        if diff_kind == Some(DiffLineKind::Added) {
+            if let Some(pending) = self.pending_diff_line.take() {
+                // Skip the leading `-`/`+` marker column, which isn't part of
+                // either side's actual content, then shift the resulting
+                // ranges back to account for it.
+                let (mut removed_ranges, mut added_ranges) = word_diff_ranges(
+                    pending.line.get(1..).unwrap_or(&pending.line),
+                    line.get(1..).unwrap_or(&line),
+                );
+                for range in removed_ranges.iter_mut().chain(added_ranges.iter_mut()) {
+                    range.0 += 1;
+                    range.1 += 1;
                 }
-            }
 
-            if !self.config.style_components.plain() && line.bytes().next_back() != Some(b'\n') {
-                writeln!(handle)?;
+                let pending_regions: Vec<(Style, &str)> = pending
+                    .regions
+                    .iter()
+                    .map(|(style, text)| (*style, text.as_str()))
+                    .collect();
+                self.render_diff_line(
+                    handle,
+                    pending.line_number,
+                    &pending.line,
+                    Some(DiffLineKind::Removed),
+                    &pending_regions,
+                    &removed_ranges,
+                )?;
+                return self.render_diff_line(
+                    handle,
+                    line_number,
+                    &line,
+                    Some(DiffLineKind::Added),
+                    &regions,
+                    &added_ranges,
+                );
             }
         } else {
-            for &(style, region) in &regions {
-                let ansi_iterator = AnsiCodeIterator::new(region);
-                for chunk in ansi_iterator {
-                    match chunk {
-                        // ANSI escape passthrough.
-                        (ansi, true) => {
-                            self.ansi_style.update(ansi);
-                            write!(handle, "{}", ansi)?;
-                        }
-
-                        // Regular text.
-                        (text, false) => {
-                            let text = self.preprocess(
-                                text.trim_end_matches(|c| c == '\r' || c == '\n'),
-                                &mut cursor_total,
-                            );
-
-                            let mut max_width = cursor_max - cursor;
-                            let mut current_width = 0;
+            self.flush_pending_diff_line(handle)?;
+        }
 
-                            let style = to_ansi_style(
-                                style,
-                                true_color,
-                                colored_output,
-                                italics,
-                                background_color,
-                            );
-                            write!(handle, "{}{}", style.prefix(), &self.ansi_style)?;
+        self.render_diff_line(handle, line_number, &line, diff_kind, &regions, &[])
+    }
 
-                            for c in text.chars() {
-                                // calculate the displayed width for next character
-                                let cw = c.width().unwrap_or(0);
-                                current_width += cw;
+    /// Wraps to a new line: closes off `style` (and the active overlay
+    /// style, if one is open), prints continuation decorations, then
+    /// re-opens `style` (and the overlay) on the fresh line.
+    #[allow(clippy::too_many_arguments)]
+    fn start_new_wrapped_line(
+        &mut self,
+        handle: OutputHandle<W>,
+        line_number: usize,
+        cursor_max: usize,
+        style: Style,
+        cursor: &mut usize,
+        max_width: &mut usize,
+        current_width: &mut usize,
+        active_overlay: &mut Option<Style>,
+    ) -> Result<()> {
+        if let Some(overlay_style) = *active_overlay {
+            write!(handle, "{}", overlay_style.suffix())?;
+        }
+        writeln!(handle, "{}", style.suffix())?;
 
-                                // if next character cannot be printed on this line,
-                                // flush the buffer.
-                                if current_width > max_width {
-                                    // It wraps.
-                                    writeln!(handle, "{}", style.suffix())?;
+        self.print_decorations(line_number, true, handle)?;
 
-                                    self.print_decorations(line_number, true, handle)?;
+        write!(handle, "{}{}", style.prefix(), &self.ansi_style)?;
+        if let Some(overlay_style) = *active_overlay {
+            write!(handle, "{}", overlay_style.prefix())?;
+        }
 
-                                    write!(handle, "{}{}", style.prefix(), &self.ansi_style)?;
+        *cursor = 0;
+        *max_width = cursor_max;
+        *current_width = 0;
+        Ok(())
+    }
 
-                                    cursor = 0;
-                                    max_width = cursor_max;
-                                    current_width = cw;
-                                }
+    /// Writes a single character of wrapped text, wrapping to a new line
+    /// first if `c` doesn't fit within `max_width`, and toggling the active
+    /// overlay style around `display_col` as it enters/leaves
+    /// `line_match_ranges`, `diff_word_ranges`, or `overlay_col_ranges`
+    /// (see [`Self::overlay_matches_to_columns`]) — `line_match_ranges`
+    /// wins over `diff_word_ranges`, which wins over `overlay_col_ranges`,
+    /// when more than one applies to the same column, matching the
+    /// layering order [`Self::write_overlaid`] uses for the `NoWrapping`
+    /// path.
+    #[allow(clippy::too_many_arguments)]
+    fn print_wrapped_char(
+        &mut self,
+        handle: OutputHandle<W>,
+        line_number: usize,
+        cursor_max: usize,
+        style: Style,
+        c: char,
+        cw: usize,
+        cursor: &mut usize,
+        max_width: &mut usize,
+        current_width: &mut usize,
+        display_col: &mut usize,
+        active_overlay: &mut Option<Style>,
+        line_match_ranges: &[(usize, usize)],
+        diff_word_ranges: &[(usize, usize)],
+        diff_word_style: Style,
+        overlay_col_ranges: &[(usize, usize, Style)],
+    ) -> Result<()> {
+        if *current_width + cw > *max_width {
+            self.start_new_wrapped_line(
+                handle,
+                line_number,
+                cursor_max,
+                style,
+                cursor,
+                max_width,
+                current_width,
+                active_overlay,
+            )?;
+        }
 
-                                write!(handle, "{}", c)?;
-                            }
+        let in_range = |ranges: &[(usize, usize)]| {
+            ranges
+                .iter()
+                .any(|&(start, end)| *display_col < end && *display_col + cw.max(1) > start)
+        };
+        let desired_overlay = if in_range(line_match_ranges) {
+            Some(self.colors.match_highlight)
+        } else if in_range(diff_word_ranges) {
+            Some(diff_word_style)
+        } else {
+            overlay_col_ranges
+                .iter()
+                .rev()
+                .find(|&&(start, end, _)| {
+                    *display_col < end && *display_col + cw.max(1) > start
+                })
+                .map(|&(_, _, style)| style)
+        };
 
-                            // flush the buffer
-                            cursor += current_width;
-                            write!(handle, "{}", style.suffix())?;
-                        }
-                    }
-                }
+        if desired_overlay != *active_overlay {
+            if let Some(overlay_style) = *active_overlay {
+                write!(handle, "{}{}", overlay_style.suffix(), style.prefix())?;
             }
-
-            if let Some(background_color) = background_color {
-                let ansi_style = Style {
-                    background: to_ansi_color(background_color, true_color),
-                    ..Default::default()
-                };
-
-                let width = cursor_max - cursor;
-                if width != 0 {
-                    write!(handle, "{}", ansi_style.prefix())?;
-                    for _ in 0..width {
-                        write!(handle, " ")?;
-                    }
-                    write!(handle, "{}", ansi_style.suffix())?;
-                }
+            if let Some(overlay_style) = desired_overlay {
+                write!(handle, "{}", overlay_style.prefix())?;
             }
-            writeln!(handle)?;
-        }
-
-        if highlight_this_line
-            && self
-                .config
-                .theme
-                .as_ref()
-                .map(|name| name == "ansi")
-                .unwrap_or(false)
-        {
-            self.ansi_style.update("^[24m");
-            write!(handle, "\x1B[24m")?;
+            *active_overlay = desired_overlay;
         }
 
+        write!(handle, "{}", c)?;
+        *display_col += cw;
+        *current_width += cw;
         Ok(())
     }
 }
 
-const DEFAULT_GUTTER_COLOR: u8 = 238;
+/// Fallback gutter foreground color for dark backgrounds, used when the
+/// theme doesn't provide its own `gutter_foreground`.
+const DEFAULT_GUTTER_COLOR_DARK: u8 = 238;
+/// Fallback gutter foreground color for light backgrounds: darker than
+/// [`DEFAULT_GUTTER_COLOR_DARK`] so it stays readable against a light
+/// background instead of washing out.
+const DEFAULT_GUTTER_COLOR_LIGHT: u8 = 235;
+
+fn default_gutter_color(mode: Mode) -> u8 {
+    match mode {
+        Mode::Dark => DEFAULT_GUTTER_COLOR_DARK,
+        Mode::Light => DEFAULT_GUTTER_COLOR_LIGHT,
+    }
+}
+
+/// Background tint for added (`+`) lines in `--diff` mode. A fixed,
+/// theme-independent color (unlike `background_color_highlight`, which comes
+/// from the active theme) so added/removed lines stay visually consistent
+/// across themes.
+const DIFF_ADDED_BACKGROUND: Color = Color {
+    r: 40,
+    g: 90,
+    b: 40,
+    a: 0xff,
+};
+/// Background tint for removed (`-`) lines in `--diff` mode. See
+/// [`DIFF_ADDED_BACKGROUND`].
+const DIFF_REMOVED_BACKGROUND: Color = Color {
+    r: 90,
+    g: 40,
+    b: 40,
+    a: 0xff,
+};
+
+/// Background for the exact tokens [`word_diff_ranges`] marks as changed
+/// within an added line, brighter than [`DIFF_ADDED_BACKGROUND`] so an edit
+/// stands out against the rest of the line instead of it being solid green.
+const DIFF_WORD_ADDED_BACKGROUND: Color = Color {
+    r: 60,
+    g: 150,
+    b: 60,
+    a: 0xff,
+};
+/// Background for changed tokens within a removed line. See
+/// [`DIFF_WORD_ADDED_BACKGROUND`].
+const DIFF_WORD_REMOVED_BACKGROUND: Color = Color {
+    r: 150,
+    g: 60,
+    b: 60,
+    a: 0xff,
+};
+
+/// Foreground for the `changes` gutter's `+`/`~`/`_` markers. Like
+/// [`DIFF_ADDED_BACKGROUND`] and friends, these are fixed, theme-independent
+/// colors rather than pulled from the active theme: syntect's `Theme` has no
+/// notion of a VCS gutter color to draw from.
+#[cfg(feature = "git")]
+const CHANGES_ADDED_COLOR: Color = Color {
+    r: 90,
+    g: 200,
+    b: 90,
+    a: 0xff,
+};
+/// Foreground for the `~` modified-line marker. See [`CHANGES_ADDED_COLOR`].
+#[cfg(feature = "git")]
+const CHANGES_MODIFIED_COLOR: Color = Color {
+    r: 220,
+    g: 180,
+    b: 60,
+    a: 0xff,
+};
+/// Foreground for the `_` removed-line marker. See [`CHANGES_ADDED_COLOR`].
+#[cfg(feature = "git")]
+const CHANGES_REMOVED_COLOR: Color = Color {
+    r: 220,
+    g: 90,
+    b: 90,
+    a: 0xff,
+};
 
 #[derive(Debug, Default)]
 pub(crate) struct Colors {
@@ -713,6 +1654,13 @@ pub(crate) struct Colors {
     pub rule: Style,
     pub header_value: Style,
     pub line_number: Style,
+    pub match_highlight: Style,
+    #[cfg(feature = "git")]
+    pub changes_added: Style,
+    #[cfg(feature = "git")]
+    pub changes_modified: Style,
+    #[cfg(feature = "git")]
+    pub changes_removed: Style,
 }
 
 impl Colors {
@@ -720,25 +1668,51 @@ impl Colors {
         Colors::default()
     }
 
-    fn colored(theme: &Theme, true_color: bool) -> Self {
+    fn colored(theme: &Theme, color_level: ColorDepth, mode: Mode, config: &Config) -> Self {
         let gutter_style = Style {
             foreground: match theme.settings.gutter_foreground {
                 // If the theme provides a gutter foreground color, use it.
                 // Note: It might be the special value #00000001, in which case
                 // to_ansi_color returns None and we use an empty Style
                 // (resulting in the terminal's default foreground color).
-                Some(c) => to_ansi_color(c, true_color),
-                // Otherwise, use a specific fallback color.
-                None => Some(TermColor::Fixed(DEFAULT_GUTTER_COLOR)),
+                Some(c) => to_ansi_color(c, color_level),
+                // Otherwise, use a fallback color suited to the detected background.
+                None => Some(TermColor::Fixed(default_gutter_color(mode))),
             },
             ..Style::default()
         };
 
+        let grid = config
+            .grid_color
+            .map_or(gutter_style, |spec| spec.to_style(color_level));
+        let line_number = config
+            .line_number_color
+            .map_or(gutter_style, |spec| spec.to_style(color_level));
+        let header_value = config
+            .header_color
+            .map_or_else(|| Style::new().bold(), |spec| spec.to_style(color_level));
+
         Colors {
-            grid: gutter_style,
-            rule: gutter_style,
-            header_value: Style::new().bold(),
-            line_number: gutter_style,
+            grid,
+            rule: grid,
+            header_value,
+            line_number,
+            match_highlight: Style::new().bold().reverse(),
+            #[cfg(feature = "git")]
+            changes_added: Style {
+                foreground: to_ansi_color(CHANGES_ADDED_COLOR, color_level),
+                ..Style::default()
+            },
+            #[cfg(feature = "git")]
+            changes_modified: Style {
+                foreground: to_ansi_color(CHANGES_MODIFIED_COLOR, color_level),
+                ..Style::default()
+            },
+            #[cfg(feature = "git")]
+            changes_removed: Style {
+                foreground: to_ansi_color(CHANGES_REMOVED_COLOR, color_level),
+                ..Style::default()
+            },
         }
     }
 }
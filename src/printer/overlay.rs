@@ -0,0 +1,70 @@
+#[allow(unused_imports)]
+use zwrite::{write, writeln};
+
+use globset::{Glob, GlobMatcher};
+use nu_ansi_term::Style;
+use regex::Regex;
+
+/// A single overlay rule: every match of `pattern` within an applicable line
+/// gets `style` layered on top of whatever style syntect already assigned to
+/// that byte range.
+#[derive(Debug, Clone)]
+pub struct OverlayRule {
+    pub pattern: Regex,
+    pub style: Style,
+}
+
+impl OverlayRule {
+    pub fn new(pattern: Regex, style: Style) -> Self {
+        OverlayRule { pattern, style }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct OverlayTarget {
+    matcher: GlobMatcher,
+    rules: Vec<OverlayRule>,
+}
+
+/// Maps a syntax name (e.g. `Rust`) or filename glob (e.g. `*.log`) to the
+/// [`OverlayRule`]s that should run over lines of a matching file, on top of
+/// (and without disturbing) ordinary syntax highlighting. Built up with
+/// [`Self::add`], then queried per-file with [`Self::rules_for`].
+#[derive(Debug, Clone, Default)]
+pub struct StyleOverlays {
+    targets: Vec<OverlayTarget>,
+}
+
+impl StyleOverlays {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `rules` to run on lines of any file whose syntax name or
+    /// file name matches the glob `target`.
+    pub fn add(&mut self, target: Glob, rules: Vec<OverlayRule>) {
+        self.targets.push(OverlayTarget {
+            matcher: target.compile_matcher(),
+            rules,
+        });
+    }
+
+    /// Returns the overlay rules that apply to a file, identified by
+    /// `candidates` (its syntax name, file name, ...), in registration
+    /// order.
+    pub fn rules_for(&self, candidates: &[&str]) -> Vec<&OverlayRule> {
+        self.targets
+            .iter()
+            .filter(|target| {
+                candidates
+                    .iter()
+                    .any(|candidate| target.matcher.is_match(candidate))
+            })
+            .flat_map(|target| target.rules.iter())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.iter().all(|target| target.rules.is_empty())
+    }
+}
@@ -14,6 +14,16 @@ pub enum NonprintableNotation {
 
     /// Use unicode notation (␇, ␊, ␀, ..)
     Unicode,
+
+    /// Use hex escapes (\x07, \x0A, \x00, ..), like `printf %q`/C string
+    /// literals -- invalid UTF-8 bytes render this way under every notation
+    /// except `Octal`, so this makes that the uniform scheme rather than a
+    /// mix.
+    Hex,
+
+    /// Use octal escapes (\007, \012, \000, ..), like `printf %q`/C string
+    /// literals.
+    Octal,
 }
 
 /// Expand tabs
@@ -45,6 +55,58 @@ pub(crate) fn expand_tabs<'a>(mut text: &'a str, width: usize, cursor: &mut usiz
     }
 }
 
+/// The number of bytes shown per row in [`hex_dump_rows`]'s output, matching
+/// the classic `hexdump -C`/`xxd` layout.
+const HEX_DUMP_WIDTH: usize = 16;
+
+/// Formats one canonical hex-dump row: an 8-digit hex `offset`, up to
+/// [`HEX_DUMP_WIDTH`] space-separated hex byte pairs (grouped 8+8, short rows
+/// padded with blanks so the ASCII gutter still lines up), then that gutter
+/// itself, where each byte shows as its own ASCII character if printable or
+/// `.` otherwise.
+fn hex_dump_row(offset: usize, bytes: &[u8]) -> String {
+    let mut row = String::new();
+    write!(row, "{offset:08x}  ").unwrap();
+    for i in 0..HEX_DUMP_WIDTH {
+        match bytes.get(i) {
+            Some(byte) => write!(row, "{byte:02x} ").unwrap(),
+            None => row.push_str("   "),
+        }
+        if i == 7 {
+            row.push(' ');
+        }
+    }
+    row.push('|');
+    for &byte in bytes {
+        row.push(if matches!(byte, 0x20..=0x7e) { byte as char } else { '.' });
+    }
+    row.push('|');
+    row
+}
+
+/// Renders as many complete [`HEX_DUMP_WIDTH`]-byte rows as `pending`
+/// (extended with `new_bytes`) now holds, draining the consumed bytes out of
+/// `pending` and advancing `offset` past them -- fewer than `HEX_DUMP_WIDTH`
+/// leftover bytes stay buffered in `pending` for the next call. Pass
+/// `flush: true` once there's no more input, to emit those leftovers as one
+/// final short row instead of holding them back forever. Returns the empty
+/// string if there's nothing to show yet.
+pub(crate) fn hex_dump_rows(pending: &mut Vec<u8>, new_bytes: &[u8], offset: &mut usize, flush: bool) -> String {
+    pending.extend_from_slice(new_bytes);
+
+    let mut rows = String::new();
+    while pending.len() >= HEX_DUMP_WIDTH || (flush && !pending.is_empty()) {
+        let take = pending.len().min(HEX_DUMP_WIDTH);
+        let row: Vec<u8> = pending.drain(..take).collect();
+        if !rows.is_empty() {
+            rows.push('\n');
+        }
+        rows.push_str(&hex_dump_row(*offset, &row));
+        *offset += take;
+    }
+    rows
+}
+
 pub(crate) fn replace_nonprintable(
     input: &[u8],
     tab_width: usize,
@@ -74,6 +136,8 @@ pub(crate) fn replace_nonprintable(
                     output.extend_from_slice(match nonprintable_notation {
                         NonprintableNotation::Caret => &['^', 'J', '\x0A'],
                         NonprintableNotation::Unicode => &['␊', '\x0A'],
+                        NonprintableNotation::Hex => &['\\', 'x', '0', 'A', '\x0A'],
+                        NonprintableNotation::Octal => &['\\', '0', '1', '2', '\x0A'],
                     });
                     before_size = output.len();
                 }
@@ -92,12 +156,18 @@ pub(crate) fn replace_nonprintable(
                             let replacement_symbol = char::from_u32(0x2400 + c).unwrap();
                             output.push(replacement_symbol);
                         }
+
+                        NonprintableNotation::Hex => output.extend(format_compact!("\\x{c:02X}").chars()),
+
+                        NonprintableNotation::Octal => output.extend(format_compact!("\\{c:03o}").chars()),
                     }
                 }
                 // delete
                 '\x7F' => match nonprintable_notation {
                     NonprintableNotation::Caret => output.extend_from_slice(&['^', '?']),
                     NonprintableNotation::Unicode => output.push('\u{2421}'),
+                    NonprintableNotation::Hex => output.extend("\\x7F".chars()),
+                    NonprintableNotation::Octal => output.extend("\\177".chars()),
                 },
                 // printable ASCII
                 c if c.is_ascii_alphanumeric()
@@ -112,8 +182,13 @@ pub(crate) fn replace_nonprintable(
             line_idx += output.len() - before_size;
         }
         for byte in chunk.invalid() {
-            output.extend(format_compact!("\\x{byte:02X}").chars());
-            line_idx += 6;
+            if nonprintable_notation == NonprintableNotation::Octal {
+                output.extend(format_compact!("\\{byte:03o}").chars());
+                line_idx += 4;
+            } else {
+                output.extend(format_compact!("\\x{byte:02X}").chars());
+                line_idx += 6;
+            }
         }
     }
 
@@ -0,0 +1,69 @@
+use std::io::Write;
+
+use console::AnsiCodeIterator;
+use unicode_width::UnicodeWidthChar;
+
+use crate::error::*;
+
+/// Pads `row` (which may already contain ANSI escapes) with spaces until its
+/// visible width reaches `width`. Escape sequences don't count toward the
+/// padding and are left untouched; a row already at or past `width` is
+/// returned as-is rather than being truncated, mirroring how a single-column
+/// render can occasionally run a character or two past `term_width` too.
+fn pad_to_width(row: &str, width: usize) -> String {
+    let visible_width: usize = AnsiCodeIterator::new(row)
+        .filter(|&(_, is_ansi)| !is_ansi)
+        .flat_map(|(chunk, _)| chunk.chars())
+        .map(|c| c.width().unwrap_or(0))
+        .sum();
+
+    let mut padded = row.to_owned();
+    if let Some(missing) = width.checked_sub(visible_width) {
+        padded.extend(std::iter::repeat(' ').take(missing));
+    }
+    padded
+}
+
+/// Zips two already-rendered columns (`left`/`right`, each the complete
+/// output of rendering an [`crate::input::Input`] through the ordinary
+/// single-column pipeline at half width, as produced by
+/// [`crate::controller::Controller`]'s `--side-by-side` path) into a
+/// single side-by-side view: every row is padded out to `left_width`/
+/// `right_width` and joined with a grid separator (when `grid` is set), and
+/// whichever column has fewer rows is padded out with blank ones so both
+/// sides end together. Zipping by physical row this way only lines up
+/// logical lines between the two columns because
+/// [`crate::controller::Controller::run_side_by_side`] renders both halves
+/// with wrapping forced off — with wrapping on, a line wrapping to extra
+/// rows on one side but not the other would desync every row after it.
+pub(crate) fn combine<W: Write>(
+    left: &[u8],
+    right: &[u8],
+    left_width: usize,
+    right_width: usize,
+    grid: bool,
+    handle: &mut W,
+) -> Result<()> {
+    let left = String::from_utf8_lossy(left);
+    let right = String::from_utf8_lossy(right);
+    let mut left_rows = left.lines();
+    let mut right_rows = right.lines();
+
+    loop {
+        let left_row = left_rows.next();
+        let right_row = right_rows.next();
+        if left_row.is_none() && right_row.is_none() {
+            break;
+        }
+
+        let left_row = pad_to_width(left_row.unwrap_or(""), left_width);
+        let right_row = pad_to_width(right_row.unwrap_or(""), right_width);
+        if grid {
+            writeln!(handle, "{} │ {}", left_row, right_row)?;
+        } else {
+            writeln!(handle, "{} {}", left_row, right_row)?;
+        }
+    }
+
+    Ok(())
+}
@@ -48,6 +48,7 @@ pub enum StyleComponent {
     HeaderFilename,
     LineNumbers,
     Snip,
+    Changes,
     Full,
     Plain,
 }
@@ -96,11 +97,13 @@ impl StyleComponent {
             }
             StyleComponent::LineNumbers => &[StyleComponent::LineNumbers],
             StyleComponent::Snip => &[StyleComponent::Snip],
+            StyleComponent::Changes => &[StyleComponent::Changes],
             StyleComponent::Full => &[
                 StyleComponent::Grid,
                 StyleComponent::HeaderFilename,
                 StyleComponent::LineNumbers,
                 StyleComponent::Snip,
+                StyleComponent::Changes,
             ],
             StyleComponent::Plain => &[],
         }
@@ -119,6 +122,7 @@ impl FromStr for StyleComponent {
             "header-filename" => Ok(StyleComponent::HeaderFilename),
             "numbers" => Ok(StyleComponent::LineNumbers),
             "snip" => Ok(StyleComponent::Snip),
+            "changes" => Ok(StyleComponent::Changes),
             // for backward compatibility, default is to full
             "full" | "default" => Ok(StyleComponent::Full),
             "plain" => Ok(StyleComponent::Plain),
@@ -127,12 +131,61 @@ impl FromStr for StyleComponent {
     }
 }
 
+/// Whether a [`SignedStyleComponent`] adds to or removes from the set of
+/// components being built up, e.g. the `-` in `--style=full,-grid`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Sign {
+    Add,
+    Remove,
+}
+
+/// One entry of a `--style` spec: a [`StyleComponent`] together with
+/// whether it's added to or removed from the accumulated set, e.g. the
+/// `-grid` in `--style=full,-grid`. Parsed by a leading `+`/`-`; a bare
+/// component with neither prefix parses as [`Sign::Add`], matching the
+/// pre-existing union-only behavior.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedStyleComponent {
+    pub sign: Sign,
+    pub component: StyleComponent,
+}
+
+impl SignedStyleComponent {
+    pub fn add(component: StyleComponent) -> Self {
+        SignedStyleComponent {
+            sign: Sign::Add,
+            component,
+        }
+    }
+}
+
+impl From<StyleComponent> for SignedStyleComponent {
+    fn from(component: StyleComponent) -> Self {
+        SignedStyleComponent::add(component)
+    }
+}
+
+impl FromStr for SignedStyleComponent {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (sign, rest) = match s.strip_prefix('-') {
+            Some(rest) => (Sign::Remove, rest),
+            None => (Sign::Add, s.strip_prefix('+').unwrap_or(s)),
+        };
+        Ok(SignedStyleComponent {
+            sign,
+            component: rest.parse()?,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(transparent)]
-pub struct StyleComponents(Vec<StyleComponent>);
+pub struct StyleComponents(Vec<SignedStyleComponent>);
 
 impl StyleComponents {
-    pub fn new(components: Vec<StyleComponent>) -> Self {
+    pub fn new(components: Vec<SignedStyleComponent>) -> Self {
         StyleComponents(components)
     }
 
@@ -141,17 +194,27 @@ impl StyleComponents {
     }
 
     pub fn full() -> Self {
-        Self::new(vec![StyleComponent::Full])
+        Self::new(vec![SignedStyleComponent::add(StyleComponent::Full)])
     }
 
+    /// Expands `auto`/`full`/`plain` and applies each entry's `+`/`-` in
+    /// order, so e.g. `full,-grid,+rule` starts from `full`'s expansion,
+    /// removes `grid`, then adds `rule`. The grid/rule conflict check runs
+    /// only after every delta has been applied, since an earlier delta may
+    /// have resolved it (as `-grid` does above).
     pub fn consolidate(self, interactive: bool) -> Result<ConsolidatedStyleComponents> {
-        let components: BTreeSet<_> = self
-            .0
-            .into_iter()
-            .flat_map(|component| component.components(interactive))
-            .copied()
-            .map(Into::into)
-            .collect();
+        let mut components: BTreeSet<StyleComponentWrapper> = BTreeSet::new();
+        for signed in self.0 {
+            let expansion = signed.component.components(interactive);
+            match signed.sign {
+                Sign::Add => components.extend(expansion.iter().copied().map(Into::into)),
+                Sign::Remove => {
+                    for &component in expansion {
+                        components.remove(&component.into());
+                    }
+                }
+            }
+        }
         if components.contains(&StyleComponent::Grid.into())
             && components.contains(&StyleComponent::Rule.into())
         {
@@ -164,7 +227,7 @@ impl StyleComponents {
 
 impl Default for StyleComponents {
     fn default() -> Self {
-        StyleComponents(vec![StyleComponent::Auto])
+        StyleComponents(vec![SignedStyleComponent::add(StyleComponent::Auto)])
     }
 }
 
@@ -196,6 +259,10 @@ impl ConsolidatedStyleComponents {
         self.0.contains(&StyleComponent::Snip.into())
     }
 
+    pub fn changes(&self) -> bool {
+        self.0.contains(&StyleComponent::Changes.into())
+    }
+
     pub fn plain(&self) -> bool {
         self.0.is_empty()
     }
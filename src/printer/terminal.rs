@@ -0,0 +1,286 @@
+use nu_ansi_term::{Color as AnsiColor, ColorLevel, Style};
+use syntect::highlighting::{Color, FontStyle, Style as SyntectStyle};
+use terminfo::{capability::MaxColors, Database};
+
+use crate::error::{Error, Result};
+
+/// How richly the output terminal can render color, from coarsest to finest.
+/// Threaded through [`to_ansi_color`]/[`to_ansi_style`] (and
+/// [`super::Colors::colored`]) instead of a plain "true color or not" bool,
+/// so terminals are matched to the closest palette they actually support,
+/// rather than either full RGB or a hardcoded 16 colors.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ColorDepth {
+    /// The original 8 ANSI colors.
+    Ansi8,
+    /// The 8 colors above plus their 8 "bright" variants.
+    Ansi16,
+    /// The xterm 256-color palette (a 6×6×6 color cube plus a grayscale ramp).
+    Ansi256,
+    /// 24-bit RGB.
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// `true_color` is the existing `--true-color`/`$COLORTERM=truecolor`
+    /// signal. When it isn't set, the current terminal's `max_colors`
+    /// terminfo capability (`Co`/`colors`) decides the tier; terminals with
+    /// no readable terminfo entry (e.g. `$TERM` unset, as in tests) are
+    /// assumed to support the basic 16.
+    pub(crate) fn detect(true_color: bool) -> Self {
+        if true_color {
+            return ColorDepth::TrueColor;
+        }
+        match max_colors() {
+            Some(n) if n >= 256 => ColorDepth::Ansi256,
+            Some(n) if n >= 16 => ColorDepth::Ansi16,
+            Some(_) => ColorDepth::Ansi8,
+            None => ColorDepth::Ansi16,
+        }
+    }
+
+    /// Converts to the equivalent [`nu_ansi_term::ColorLevel`], so theme and
+    /// `ColorSpec` colors can be downsampled via [`AnsiColor::downgrade`]
+    /// instead of bat keeping its own copy of that conversion.
+    fn to_color_level(self) -> ColorLevel {
+        match self {
+            ColorDepth::Ansi8 => ColorLevel::Ansi8,
+            ColorDepth::Ansi16 => ColorLevel::Ansi16,
+            ColorDepth::Ansi256 => ColorLevel::Ansi256,
+            ColorDepth::TrueColor => ColorLevel::TrueColor,
+        }
+    }
+}
+
+fn max_colors() -> Option<i32> {
+    let database = Database::from_env().ok()?;
+    database.get::<MaxColors>().map(|cap| cap.0)
+}
+
+/// Whether the terminal's background is light or dark, used to pick a
+/// fallback gutter (grid/line-number) foreground color that stays legible
+/// when the theme doesn't supply its own `gutter_foreground`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Light,
+    Dark,
+}
+
+impl Mode {
+    /// `override_mode` is the explicit `--light`/`--dark` flag. Without it,
+    /// asks the terminal directly via an OSC 11 query
+    /// ([`crate::assets::query_terminal_background`]); only if that gets no
+    /// reply in time (not a terminal, or a terminal that doesn't answer OSC
+    /// 11) does it fall back to sniffing `$COLORFGBG` (set by many
+    /// terminals/tmux as `fg;bg`, where `bg` is a basic ANSI color index; 7
+    /// and 15 are light backgrounds). Terminals that answer neither are
+    /// assumed dark, matching bat's historical default.
+    pub(crate) fn detect(override_mode: Option<Mode>) -> Self {
+        if let Some(mode) = override_mode {
+            return mode;
+        }
+        if let Some(is_dark) = crate::assets::query_terminal_background() {
+            return if is_dark { Mode::Dark } else { Mode::Light };
+        }
+        let is_light = std::env::var("COLORFGBG")
+            .ok()
+            .and_then(|value| value.rsplit(';').next().map(str::to_owned))
+            .and_then(|bg| bg.parse::<u8>().ok())
+            .is_some_and(|bg| matches!(bg, 7 | 15));
+        if is_light {
+            Mode::Light
+        } else {
+            Mode::Dark
+        }
+    }
+}
+
+/// Converts a syntect theme color to the closest representable
+/// [`AnsiColor`] at `color_level`, via [`AnsiColor::downgrade`].
+///
+/// Themes encode "no color, use the terminal's default" as the special
+/// `#00000001` sentinel (syntect has no literal way to express "none");
+/// that sentinel maps to `None` regardless of `color_level`.
+pub(crate) fn to_ansi_color(color: Color, color_level: ColorDepth) -> Option<AnsiColor> {
+    if color.r == 0 && color.g == 0 && color.b == 0 && color.a == 1 {
+        return None;
+    }
+    Some(AnsiColor::Rgb(color.r, color.g, color.b).downgrade(color_level.to_color_level()))
+}
+
+/// Converts a syntect region style to an [`nu_ansi_term::Style`], honoring
+/// `colored_output` (a hard off switch), `use_italics`, and an optional
+/// `background_override` (used for whole-line highlighting, which takes
+/// precedence over the region's own background). The foreground color is
+/// nudged via [`normalize_lightness`] to stay legible against `mode`'s
+/// background.
+pub(crate) fn to_ansi_style(
+    style: SyntectStyle,
+    color_level: ColorDepth,
+    mode: Mode,
+    colored_output: bool,
+    use_italics: bool,
+    background_override: Option<Color>,
+) -> Style {
+    if !colored_output {
+        return Style::default();
+    }
+
+    let mut ansi_style = Style::new();
+    if style.font_style.contains(FontStyle::BOLD) {
+        ansi_style = ansi_style.bold();
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        ansi_style = ansi_style.underline();
+    }
+    if use_italics && style.font_style.contains(FontStyle::ITALIC) {
+        ansi_style = ansi_style.italic();
+    }
+
+    ansi_style.foreground = to_ansi_color(style.foreground, color_level)
+        .map(|color| normalize_lightness(color, mode));
+    ansi_style.background =
+        to_ansi_color(background_override.unwrap_or(style.background), color_level);
+
+    ansi_style
+}
+
+/// Nudges a foreground color's lightness away from `mode`'s background so
+/// pale syntax-highlight colors don't wash out on a light terminal (or dark
+/// ones on a dark terminal): capped around `0.6` lightness on
+/// [`Mode::Light`], floored around `0.4` on [`Mode::Dark`]. See
+/// [`AnsiColor::with_lightness`].
+pub(crate) fn normalize_lightness(color: AnsiColor, mode: Mode) -> AnsiColor {
+    let target = match mode {
+        Mode::Light => 0.6,
+        Mode::Dark => 0.4,
+    };
+    color.with_lightness(target)
+}
+
+/// A color as written in a `--grid-color`/`--line-number-color`/
+/// `--header-color`-style flag or config value: an RGB hex code
+/// (`#rrggbb`, downgraded like any theme color via [`to_ansi_color`]), one
+/// of the 16 standard ANSI color names (optionally `bright-`-prefixed,
+/// mapping directly to an ANSI code regardless of `ColorDepth` since every
+/// terminal that understands color at all understands those 16), or a
+/// literal xterm 256-color palette index (`0..=255`, downgraded the same
+/// way as an RGB hex code below `Ansi256`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ColorValue {
+    /// A standard ANSI color index, 0..=15.
+    Named(u8),
+    Rgb(u8, u8, u8),
+    /// A literal xterm 256-color palette index.
+    Fixed(u8),
+}
+
+/// A parsed `--grid-color`-style flag: a [`ColorValue`] plus any of the
+/// `bold`/`italic`/`underline` attributes, so components aren't stuck with
+/// bat's hardcoded `bold()`-only styling.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ColorSpec {
+    value: ColorValue,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl ColorSpec {
+    pub(crate) fn to_style(self, color_level: ColorDepth) -> Style {
+        let foreground = Some(match self.value {
+            ColorValue::Named(index) => AnsiColor::Fixed(index),
+            ColorValue::Rgb(r, g, b) => to_ansi_color(Color { r, g, b, a: 255 }, color_level)
+                .unwrap_or(AnsiColor::Default),
+            ColorValue::Fixed(index) => {
+                AnsiColor::Fixed(index).downgrade(color_level.to_color_level())
+            }
+        });
+
+        let mut style = Style {
+            foreground,
+            ..Style::default()
+        };
+        if self.bold {
+            style = style.bold();
+        }
+        if self.italic {
+            style = style.italic();
+        }
+        if self.underline {
+            style = style.underline();
+        }
+        style
+    }
+}
+
+/// Parses a comma-separated color spec like `#0e7c0e`, `bright-red,bold`, or
+/// `green,italic,underline`: exactly one color token (a `#rrggbb` hex code,
+/// an ANSI color name, or a literal xterm 256-color index like `166`) plus
+/// any number of `bold`/`italic`/`underline` attribute tokens, in any order.
+pub fn parse_color_spec(spec: &str) -> Result<ColorSpec> {
+    let mut value = None;
+    let mut bold = false;
+    let mut italic = false;
+    let mut underline = false;
+
+    for token in spec.split(',') {
+        match token.trim() {
+            "bold" => bold = true,
+            "italic" => italic = true,
+            "underline" => underline = true,
+            token if value.is_none() => value = Some(parse_color_value(token)?),
+            token => {
+                return Err(Error::msg(format!(
+                    "invalid color spec '{spec}': unexpected second color '{token}'"
+                )))
+            }
+        }
+    }
+
+    let value =
+        value.ok_or_else(|| Error::msg(format!("invalid color spec '{spec}': no color given")))?;
+    Ok(ColorSpec {
+        value,
+        bold,
+        italic,
+        underline,
+    })
+}
+
+fn parse_color_value(token: &str) -> Result<ColorValue> {
+    if let Some(hex) = token.strip_prefix('#') {
+        let channel = |range| {
+            u8::from_str_radix(hex.get(range).unwrap_or(""), 16)
+                .map_err(|_| Error::msg(format!("invalid hex color '{token}'")))
+        };
+        if hex.len() != 6 {
+            return Err(Error::msg(format!("invalid hex color '{token}'")));
+        }
+        return Ok(ColorValue::Rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?));
+    }
+
+    if token.bytes().all(|b| b.is_ascii_digit()) && !token.is_empty() {
+        return token
+            .parse()
+            .map(ColorValue::Fixed)
+            .map_err(|_| Error::msg(format!("invalid xterm color index '{token}'")));
+    }
+
+    let (name, offset) = match token.strip_prefix("bright-") {
+        Some(name) => (name, 8),
+        None => (token, 0),
+    };
+    let index = match name {
+        "black" => 0,
+        "red" => 1,
+        "green" => 2,
+        "yellow" => 3,
+        "blue" => 4,
+        "purple" | "magenta" => 5,
+        "cyan" => 6,
+        "white" => 7,
+        _ => return Err(Error::msg(format!("unknown color name '{token}'"))),
+    };
+    Ok(ColorValue::Named(index + offset))
+}
@@ -58,6 +58,11 @@ struct Attributes {
     /// OFF: ^[24m
     underline: ConfigString,
 
+    /// Kitty-style underline shape, the `4:n` SGR sub-parameter
+    /// (0 none, 1 single, 2 double, 3 curly, 4 dotted, 5 dashed).
+    /// OFF: ^[24m
+    underline_style: ConfigString,
+
     /// ON:  ^[3m
     /// OFF: ^[23m
     italic: ConfigString,
@@ -101,17 +106,47 @@ impl Attributes {
         self.bold.clear();
         self.dim.clear();
         self.underline.clear();
+        self.underline_style.clear();
         self.italic.clear();
         self.strike.clear();
     }
 
     fn update_with_sgr(&mut self, parameters: &str) -> bool {
-        let mut iter = parameters
-            .split(';')
-            .map(str::parse)
-            .map(Result::unwrap_or_default); // Treat errors as 0.
+        // `4:n` is a colon sub-parameter (Kitty-style underline shapes), not
+        // a plain `;`-separated SGR code, so each token is tokenized before
+        // integer parsing; everything else behaves as before (unparsable
+        // tokens become `0`, i.e. a reset).
+        enum Token {
+            Code(u16),
+            UnderlineShape(Option<u8>),
+        }
+
+        let mut iter = parameters.split(';').map(|token| match token.strip_prefix("4:") {
+            Some(shape) => Token::UnderlineShape(shape.parse().ok()),
+            None => Token::Code(token.parse().unwrap_or_default()),
+        });
+
+        while let Some(token) = iter.next() {
+            let p = match token {
+                Token::UnderlineShape(shape) => {
+                    self.underline_style.clear();
+                    match shape {
+                        // `4:0` is the explicit "no underline" shape; like
+                        // plain `24`, it needs to clear `self.underline`
+                        // too, not just the shape sub-parameter, or a
+                        // terminal using `4:0` to turn underlining off
+                        // leaves us stuck thinking it's still underlined.
+                        Some(0) => self.underline.clear(),
+                        Some(shape @ 1..=5) => {
+                            write!(self.underline_style, "\x1B[4:{shape}m").unwrap();
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                Token::Code(p) => p,
+            };
 
-        while let Some(p) = iter.next() {
             match p {
                 0 => self.sgr_reset(),
                 1 => {
@@ -131,22 +166,25 @@ impl Attributes {
                     write!(self.underline, "\x1B[{parameters}m").unwrap();
                 }
                 23 => self.italic.clear(),
-                24 => self.underline.clear(),
+                24 => {
+                    self.underline.clear();
+                    self.underline_style.clear();
+                }
                 22 => {
                     self.bold.clear();
                     self.dim.clear();
                 }
                 30..=39 | 90..=97 | 100..=107 => {
                     self.foreground.clear();
-                    Self::parse_color(&mut self.foreground, p, &mut iter);
+                    Self::parse_color(&mut self.foreground, p, &mut iter.by_ref().map(Self::token_code));
                 }
                 40..=49 => {
                     self.background.clear();
-                    Self::parse_color(&mut self.background, p, &mut iter);
+                    Self::parse_color(&mut self.background, p, &mut iter.by_ref().map(Self::token_code));
                 }
                 58..=59 => {
                     self.underlined.clear();
-                    Self::parse_color(&mut self.underlined, p, &mut iter);
+                    Self::parse_color(&mut self.underlined, p, &mut iter.by_ref().map(Self::token_code));
                 }
                 _ => {
                     // Unsupported SGR sequence.
@@ -158,6 +196,15 @@ impl Attributes {
         true
     }
 
+    /// A `4:n` sub-parameter never shows up as a color sub-code in practice;
+    /// treated as `0` here, same as any other unparsable token.
+    fn token_code(token: Token) -> u16 {
+        match token {
+            Token::Code(p) => p,
+            Token::UnderlineShape(_) => 0,
+        }
+    }
+
     fn update_with_csi(&mut self, finalizer: u8, sequence: &str) -> bool {
         if finalizer == b'm' {
             self.update_with_sgr(sequence)
@@ -211,7 +258,7 @@ impl Display for Attributes {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{}{}{}{}{}{}{}{}{}",
+            "{}{}{}{}{}{}{}{}{}{}",
             self.foreground,
             self.background,
             self.underlined,
@@ -219,8 +266,35 @@ impl Display for Attributes {
             self.bold,
             self.dim,
             self.underline,
+            self.underline_style,
             self.italic,
             self.strike,
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn underline_shape_zero_clears_underline() {
+        let mut style = AnsiStyle::new();
+        assert!(style.update("\x1B[4m"));
+        assert!(style.to_string().contains("\x1B[4m"));
+
+        assert!(style.update("\x1B[4:0m"));
+        assert_eq!(style.to_string(), "");
+    }
+
+    #[test]
+    fn underline_shape_other_than_zero_keeps_underline() {
+        let mut style = AnsiStyle::new();
+        assert!(style.update("\x1B[4m"));
+        assert!(style.update("\x1B[4:3m"));
+
+        let rendered = style.to_string();
+        assert!(rendered.contains("\x1B[4m"));
+        assert!(rendered.contains("\x1B[4:3m"));
+    }
+}
@@ -1,11 +1,229 @@
-use std::{ffi::OsString, path::Path};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
 
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, Anchored, Input, MatchKind, StartKind};
 use globset::{Candidate, Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use once_cell::sync::Lazy;
 use os_str_bytes::RawOsString;
+use regex::bytes::RegexSet;
+use regex::Regex;
+use regex::RegexSet as PathRegexSet;
+use serde::{Deserialize, Serialize};
 
+use crate::config::leak_config_string;
 use crate::error::Result;
 
+/// Upper bound on the number of suffix-stripping passes `strip_ignored_suffixes`
+/// will perform on a single file name, so a degenerate ignored-suffix entry
+/// (e.g. an empty one) can't turn the loop unbounded.
+const MAX_IGNORED_SUFFIX_PASSES: usize = 8;
+
+/// Well-known shebang interpreters, mapped to the syntax they select. The
+/// interpreter name has already had its path and any trailing version number
+/// stripped (`/usr/bin/env python3` -> `python`).
+const SHEBANG_INTERPRETERS: &[(&str, &str)] = &[
+    ("python", "Python"),
+    ("perl", "Perl"),
+    ("ruby", "Ruby"),
+    ("node", "JavaScript"),
+    ("bash", "Bourne Again Shell (bash)"),
+    ("sh", "Bourne Again Shell (bash)"),
+    ("zsh", "Bourne Again Shell (bash)"),
+    ("fish", "fish"),
+];
+
+/// `vim: set ft=rust:` / `vim: ft=rust` / `vi: ft=rust`-style modelines.
+static VIM_MODELINE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b(?:vim?|ex):.*\bft=([A-Za-z0-9_+-]+)").unwrap());
+
+/// Emacs `-*- mode: Rust -*-`-style modelines.
+static EMACS_MODELINE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)-\*-.*?\bmode:\s*([A-Za-z0-9_+-]+).*?-\*-").unwrap());
+
+/// How many bytes of buffered content [`sniff_content`] scores; content
+/// beyond this is ignored, so a large file can't make an already
+/// last-resort lookup expensive.
+const CONTENT_SNIFF_BYTE_LIMIT: usize = 8192;
+
+/// Minimum score (see [`sniff_content`]) a candidate syntax must reach to be
+/// reported; below this the signal is too weak to prefer over plain text.
+const CONTENT_SNIFF_THRESHOLD: u32 = 4;
+
+/// A TOML `[section]` or `[[array.of.tables]]` header line.
+static TOML_SECTION_HEADER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^[ \t]*\[{1,2}[A-Za-z0-9_.-]+\]{1,2}[ \t]*$").unwrap());
+/// A TOML/YAML-style `key = value` assignment line.
+static TOML_ASSIGNMENT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?m)^[ \t]*[A-Za-z0-9_.-]+[ \t]*=[ \t]*\S"#).unwrap());
+/// A YAML `key:` or `key: value` line.
+static YAML_KEY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^[ \t]*[A-Za-z0-9_-]+:([ \t]|$)").unwrap());
+/// A JSON quoted object key followed by `:`.
+static JSON_KEY: Lazy<Regex> = Lazy::new(|| Regex::new(r#""(?:[^"\\]|\\.)*"\s*:"#).unwrap());
+/// Common SQL statement/clause keywords.
+static SQL_KEYWORD: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b(?:select|insert|update|delete|create|alter|drop|from|where|into|values|join|table)\b")
+        .unwrap()
+});
+
+/// Scores `content` against a handful of lightweight structural signatures --
+/// an XML/HTML declaration, balanced and quoted-key JSON, a TOML `[section]`
+/// header plus `key = value` lines, a YAML `---` document start or `key:`
+/// indentation, and SQL keyword density -- and returns the best-scoring
+/// syntax, if any clears [`CONTENT_SNIFF_THRESHOLD`]. Used by
+/// [`SyntaxMapping::resolve_by_content_sniff`] as a last resort once
+/// path/extension and first-line detection have both given up.
+fn sniff_content(content: &str) -> Option<MappingTarget<'static>> {
+    let mut sample_end = content.len().min(CONTENT_SNIFF_BYTE_LIMIT);
+    while sample_end > 0 && !content.is_char_boundary(sample_end) {
+        sample_end -= 1;
+    }
+    let sample = &content[..sample_end];
+    let trimmed = sample.trim_start();
+
+    let mut candidates: Vec<(&'static str, u32)> = Vec::new();
+
+    if trimmed.starts_with("<?xml") {
+        candidates.push(("XML", 10));
+    }
+    if trimmed
+        .get(..14)
+        .is_some_and(|s| s.eq_ignore_ascii_case("<!doctype html"))
+    {
+        candidates.push(("HTML", 10));
+    }
+
+    if matches!(
+        (trimmed.chars().next(), trimmed.trim_end().chars().last()),
+        (Some('{'), Some('}')) | (Some('['), Some(']'))
+    ) && JSON_KEY.is_match(sample)
+    {
+        candidates.push(("JSON", 8));
+    }
+
+    let toml_sections = TOML_SECTION_HEADER.find_iter(sample).count() as u32;
+    let toml_assignments = TOML_ASSIGNMENT.find_iter(sample).count() as u32;
+    if toml_sections > 0 && toml_assignments > 0 {
+        candidates.push(("TOML", (toml_sections * 3 + toml_assignments).min(10)));
+    }
+
+    if trimmed.starts_with("---") {
+        candidates.push(("YAML", 8));
+    } else {
+        let yaml_keys = YAML_KEY.find_iter(sample).count() as u32;
+        if yaml_keys >= 2 {
+            candidates.push(("YAML", yaml_keys.min(10)));
+        }
+    }
+
+    let sql_hits = SQL_KEYWORD.find_iter(sample).count() as u32;
+    if sql_hits >= 3 {
+        candidates.push(("SQL", sql_hits.min(10)));
+    }
+
+    candidates
+        .into_iter()
+        .max_by_key(|&(_, score)| score)
+        .filter(|&(_, score)| score >= CONTENT_SNIFF_THRESHOLD)
+        .map(|(syntax, _)| MappingTarget::MapTo(syntax))
+}
+
+/// Known editor-modeline filetype names, mapped to the syntax they select.
+/// Unrecognized filetype names fall through (we can't fabricate a `&'a str`
+/// out of file content, so only names we already know statically resolve).
+const MODELINE_FILETYPES: &[(&str, &str)] = &[
+    ("python", "Python"),
+    ("rust", "Rust"),
+    ("c", "C"),
+    ("cpp", "C++"),
+    ("sh", "Bourne Again Shell (bash)"),
+    ("ruby", "Ruby"),
+    ("perl", "Perl"),
+    ("javascript", "JavaScript"),
+    ("json", "JSON"),
+    ("yaml", "YAML"),
+    ("toml", "TOML"),
+    ("html", "HTML"),
+    ("css", "CSS"),
+    ("make", "Makefile"),
+    ("lua", "Lua"),
+];
+
+/// Expands a single `{a,b,c}` brace-alternation group in a glob pattern into
+/// one pattern per alternative (e.g. `*.{c,h,cc}` -> `*.c`, `*.h`, `*.cc`).
+/// Patterns without a brace group are returned unchanged. Only one group is
+/// supported, which covers the common "extension list" use case: a second
+/// group is left untouched, and a nested group is not recognized (the first
+/// `}` closes the outer group).
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(start) = pattern.find('{') else {
+        return vec![pattern.to_owned()];
+    };
+    let Some(rel_end) = pattern[start..].find('}') else {
+        return vec![pattern.to_owned()];
+    };
+    let end = start + rel_end;
+    let prefix = &pattern[..start];
+    let suffix = &pattern[end + 1..];
+    pattern[start + 1..end]
+        .split(',')
+        .map(|alt| format!("{prefix}{alt}{suffix}"))
+        .collect()
+}
+
+/// Wildcard characters that mark a glob segment as non-literal, used by
+/// [`glob_specificity`].
+const GLOB_WILDCARD_CHARS: [char; 4] = ['*', '?', '[', '{'];
+
+/// A rough measure of how specific a glob pattern is, used to rank
+/// competing rules against each other: the number of fully literal
+/// (non-wildcard) path segments, weighted well above the length of the
+/// longest leading literal prefix (which only breaks ties between patterns
+/// with the same number of literal segments). A directory-anchored rule
+/// like `**/.ssh/config` outranks a generic extension rule like `*.conf`.
+fn glob_specificity(pattern: &str) -> u32 {
+    let literal_prefix_len = pattern.find(GLOB_WILDCARD_CHARS).unwrap_or(pattern.len());
+    let literal_segments = pattern
+        .split('/')
+        .filter(|segment| !segment.is_empty() && !segment.contains(GLOB_WILDCARD_CHARS))
+        .count();
+    (literal_segments as u32) * 4096 + literal_prefix_len.min(4095) as u32
+}
+
+/// Same idea as [`glob_specificity`], but for a path regex rule: the number
+/// of characters that aren't regex metacharacters, as a proxy for how
+/// narrowly the pattern is anchored to a specific path.
+fn regex_specificity(pattern: &str) -> u32 {
+    const META_CHARS: [char; 13] = [
+        '\\', '.', '*', '+', '?', '(', ')', '[', ']', '{', '}', '|', '^',
+    ];
+    pattern
+        .chars()
+        .filter(|c| !META_CHARS.contains(c))
+        .count()
+        .min(4095) as u32
+}
+
+/// Packs a rule's specificity and declaration order into a single sort key:
+/// higher specificity always wins, and among equally specific rules, the
+/// one declared later (the larger `order`) wins, matching the historical
+/// last-rule-wins behavior for rules the heuristic can't tell apart.
+fn rank(specificity: u32, order: u32) -> u64 {
+    (u64::from(specificity) << 32) | u64::from(order)
+}
+
+/// Anchors a path regex `pattern` to match the whole path unless it already
+/// is, so it behaves like a glob rule (matches the whole candidate) rather
+/// than matching anywhere within it.
+fn anchor_path_regex(pattern: &str) -> String {
+    let prefix = if pattern.starts_with('^') { "" } else { "^" };
+    let suffix = if pattern.ends_with('$') { "" } else { "$" };
+    format!("{prefix}(?:{pattern}){suffix}")
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum MappingTarget<'a> {
     /// For mapping a path to a specific syntax.
@@ -28,25 +246,90 @@ pub enum MappingTarget<'a> {
 #[derive(Debug, Clone)]
 pub struct SyntaxMapping<'a> {
     targets: Vec<MappingTarget<'a>>,
+    /// Parallel to `targets`: the original glob source text of the rule at
+    /// this index, for reporting which rule matched (see
+    /// [`MatchedRule::pattern`]).
+    patterns: Vec<String>,
+    /// Parallel to `targets`: whether the rule at this index is a negation
+    /// (`!glob`), which cancels any match selected so far instead of mapping
+    /// to a syntax.
+    negated: Vec<bool>,
+    /// Parallel to `targets`: this rule's rank (specificity and declaration
+    /// order packed together, see [`rank`]), used to pick a winner among
+    /// several matching rules.
+    rank: Vec<u64>,
     globset: GlobSet,
+    /// Anchored regex rules over the full path, e.g. `^/etc/nginx/.*\.conf$`,
+    /// for mappings a glob can't express. Parallel to `path_regex_targets`,
+    /// `path_regex_negated`, and `path_regex_rank`, the same way `globset`
+    /// is parallel to `targets`/`negated`/`rank`.
+    path_regexes: PathRegexSet,
+    path_regex_targets: Vec<MappingTarget<'a>>,
+    /// Parallel to `path_regex_targets`: the original (pre-anchor) regex
+    /// source text, for reporting which rule matched.
+    path_regex_patterns: Vec<String>,
+    path_regex_negated: Vec<bool>,
+    path_regex_rank: Vec<u64>,
     ignored_suffixes: AhoCorasick,
+    first_line_targets: Vec<MappingTarget<'a>>,
+    first_line_patterns: RegexSet,
+    /// Whether [`Self::resolve_by_content_sniff`] is allowed to guess a
+    /// syntax from multi-line content signatures. See
+    /// [`SyntaxMappingBuilder::content_sniffing`].
+    content_sniffing: bool,
 }
 
 impl<'a> SyntaxMapping<'a> {
     pub fn new(
-        mapping: impl IntoIterator<Item = (Glob, MappingTarget<'a>)>,
+        mapping: impl IntoIterator<Item = (String, Glob, bool, MappingTarget<'a>, u32, u32)>,
+        path_regex_mapping: impl IntoIterator<Item = (String, bool, MappingTarget<'a>, u32, u32)>,
         ignored_suffixes: impl IntoIterator<Item = String>,
+        first_line_mapping: impl IntoIterator<Item = (String, MappingTarget<'a>)>,
+        content_sniffing: bool,
     ) -> Result<Self> {
         let mut builder = GlobSetBuilder::new();
+        let mut targets = Vec::new();
+        let mut patterns = Vec::new();
+        let mut negated = Vec::new();
+        let mut ranks = Vec::new();
+        for (pattern, glob, is_negated, target, specificity, order) in mapping {
+            builder.add(glob);
+            targets.push(target);
+            patterns.push(pattern);
+            negated.push(is_negated);
+            ranks.push(rank(specificity, order));
+        }
+
+        let mut anchored_path_regex_patterns = Vec::new();
+        let mut path_regex_patterns = Vec::new();
+        let mut path_regex_targets = Vec::new();
+        let mut path_regex_negated = Vec::new();
+        let mut path_regex_rank = Vec::new();
+        for (pattern, is_negated, target, specificity, order) in path_regex_mapping {
+            anchored_path_regex_patterns.push(anchor_path_regex(&pattern));
+            path_regex_patterns.push(pattern);
+            path_regex_targets.push(target);
+            path_regex_negated.push(is_negated);
+            path_regex_rank.push(rank(specificity, order));
+        }
+
+        let mut first_line_targets = Vec::new();
+        let mut first_line_patterns = Vec::new();
+        for (pattern, target) in first_line_mapping {
+            first_line_patterns.push(pattern);
+            first_line_targets.push(target);
+        }
         Ok(SyntaxMapping {
-            targets: mapping
-                .into_iter()
-                .map(|(glob, target)| {
-                    builder.add(glob);
-                    target
-                })
-                .collect(),
+            targets,
+            patterns,
+            negated,
+            rank: ranks,
             globset: builder.build()?,
+            path_regexes: PathRegexSet::new(anchored_path_regex_patterns)?,
+            path_regex_targets,
+            path_regex_patterns,
+            path_regex_negated,
+            path_regex_rank,
             ignored_suffixes: AhoCorasickBuilder::new()
                 .ascii_case_insensitive(true)
                 .match_kind(MatchKind::LeftmostLongest)
@@ -56,100 +339,510 @@ impl<'a> SyntaxMapping<'a> {
                     v.reverse();
                     v
                 }))?,
+            first_line_targets,
+            first_line_patterns: RegexSet::new(first_line_patterns)?,
+            content_sniffing,
         })
     }
 
+    /// The default mapping, built from only the builtin globs and ignored
+    /// suffixes (no user-provided rules).
+    pub fn builtin() -> Self {
+        SyntaxMappingBuilder::new()
+            .with_builtin()
+            .build()
+            .expect("builtin syntax mapping is valid")
+    }
+
     pub(crate) fn get_syntax_for(&self, path: impl AsRef<Path>) -> Option<MappingTarget> {
-        let candidate_path = Candidate::new(path.as_ref());
-        let candidate_filename = Path::new(path.as_ref()).file_name().map(Candidate::new);
+        let path = path.as_ref();
+        let candidate_path = Candidate::new(path);
+        let candidate_filename = path.file_name().map(Candidate::new);
         let path_matches = self.globset.matches_candidate(&candidate_path);
         let name_matches = candidate_filename
             .as_ref()
             .map(|candidate_filename| self.globset.matches_candidate(candidate_filename))
             .unwrap_or_default();
-        path_matches
+
+        let mut glob_indices: Vec<usize> = path_matches.into_iter().chain(name_matches).collect();
+        glob_indices.sort_unstable();
+        glob_indices.dedup();
+
+        let path_regex_indices = self.path_regexes.matches(&path.to_string_lossy());
+
+        // Every matching rule (glob or path regex) is ranked by specificity:
+        // a more specific rule (e.g. a directory-anchored glob or regex)
+        // wins over a more generic one (e.g. a bare extension glob)
+        // regardless of declaration order; ties are broken by declaration
+        // order, latest wins, matching the historical last-rule-wins
+        // behavior for rules the heuristic can't otherwise distinguish. A
+        // matching negation rule cancels whatever was selected by a
+        // less-specific rule instead of mapping to a syntax, so the caller
+        // falls back to content- or extension-based detection for that path.
+        let mut matches: Vec<(u64, bool, MappingTarget)> = glob_indices
             .into_iter()
-            .chain(name_matches)
-            .max()
-            .map(|i| self.targets[i])
+            .map(|i| (self.rank[i], self.negated[i], self.targets[i]))
+            .chain(path_regex_indices.into_iter().map(|i| {
+                (
+                    self.path_regex_rank[i],
+                    self.path_regex_negated[i],
+                    self.path_regex_targets[i],
+                )
+            }))
+            .collect();
+        matches.sort_by_key(|&(rank, ..)| rank);
+
+        let mut selection = None;
+        for (_, negated, target) in matches {
+            selection = (!negated).then_some(target);
+        }
+        selection
     }
 
+    /// Resolves a `MapToUnknown`/`MapExtensionToUnknown` result by inspecting
+    /// the first line of the file's content: recognizes shebang lines
+    /// (`#!/usr/bin/env python`), vim/emacs modelines, and finally falls back
+    /// to the configured first-line regex patterns (last match wins).
+    pub fn resolve_unknown(&self, first_line: &[u8]) -> Option<MappingTarget> {
+        if let Some(target) = resolve_shebang(first_line) {
+            return Some(target);
+        }
+        if let Some(target) = resolve_modeline(first_line) {
+            return Some(target);
+        }
+        self.first_line_patterns
+            .matches(first_line)
+            .into_iter()
+            .last()
+            .map(|i| self.first_line_targets[i])
+    }
+
+    /// A further last-resort fallback beyond [`Self::resolve_unknown`]:
+    /// scores a buffered sample of the file's content (not just its first
+    /// line) against a handful of lightweight structural signatures -- see
+    /// [`sniff_content`] -- and returns the best-scoring syntax, if any is
+    /// confident enough. Returns `None` immediately if this mapping was
+    /// built with [`SyntaxMappingBuilder::content_sniffing`]`(false)`.
+    pub fn resolve_by_content_sniff(&self, content: &str) -> Option<MappingTarget> {
+        self.content_sniffing.then(|| sniff_content(content)).flatten()
+    }
+
+    /// Repeatedly strips a single leftmost-longest ignored suffix from the
+    /// end of `file_name`, so that layered suffixes (e.g. `app.min.js.gz`
+    /// with both `.gz` and `.min` ignored) are peeled one at a time rather
+    /// than relying on a single pass to see through the whole stack. Bounded
+    /// by `MAX_IGNORED_SUFFIX_PASSES` so a pathological (e.g. empty)
+    /// ignored-suffix entry can't loop forever.
     pub(crate) fn strip_ignored_suffixes(&self, file_name: OsString) -> OsString {
         let file_name = RawOsString::new(file_name);
         let mut bytes = file_name.into_raw_vec();
         bytes.reverse();
-        let ignored_len: usize = self
-            .ignored_suffixes
-            .find_iter(Input::new(&bytes).anchored(Anchored::Yes))
-            .map(|m| m.len())
-            .sum();
+
+        for _ in 0..MAX_IGNORED_SUFFIX_PASSES {
+            let Some(m) = self
+                .ignored_suffixes
+                .find(Input::new(&bytes).anchored(Anchored::Yes))
+            else {
+                break;
+            };
+            if m.len() == 0 {
+                break;
+            }
+            bytes.drain(..m.len());
+        }
+
         bytes.reverse();
-        bytes.truncate(bytes.len() - ignored_len);
         RawOsString::assert_from_raw_vec(bytes).into_os_string()
     }
+
+    /// A read-only trace of how `get_syntax_for` would resolve `path` once its
+    /// ignored suffixes are stripped: every
+    /// glob or path-regex rule that matched (ranked by specificity, lowest
+    /// first, so printing them in order shows exactly how `target` was
+    /// reached -- including negations that canceled a less-specific match),
+    /// the file name after ignored-suffix stripping, and the final chosen
+    /// target. Intended for diagnosing unexpected syntax selections, not for
+    /// the hot matching path.
+    pub fn explain_syntax_for(&self, path: impl AsRef<Path>) -> SyntaxResolution<'a> {
+        let stripped_path: PathBuf = self
+            .strip_ignored_suffixes(path.as_ref().as_os_str().to_owned())
+            .into();
+        let stripped_file_name = stripped_path.file_name().map(OsString::from).unwrap_or_default();
+
+        let candidate_path = Candidate::new(&stripped_path);
+        let candidate_filename = stripped_path.file_name().map(Candidate::new);
+        let path_matches = self.globset.matches_candidate(&candidate_path);
+        let name_matches = candidate_filename
+            .as_ref()
+            .map(|candidate_filename| self.globset.matches_candidate(candidate_filename))
+            .unwrap_or_default();
+
+        let mut glob_indices: Vec<usize> = path_matches.into_iter().chain(name_matches).collect();
+        glob_indices.sort_unstable();
+        glob_indices.dedup();
+
+        let path_regex_indices = self
+            .path_regexes
+            .matches(&stripped_path.to_string_lossy());
+
+        let mut matched_rules: Vec<MatchedRule> = glob_indices
+            .into_iter()
+            .map(|i| MatchedRule {
+                kind: MatchedRuleKind::Glob,
+                rule_index: i,
+                pattern: self.patterns[i].clone(),
+                specificity: (self.rank[i] >> 32) as u32,
+                negated: self.negated[i],
+                target: self.targets[i],
+            })
+            .chain(path_regex_indices.into_iter().map(|i| MatchedRule {
+                kind: MatchedRuleKind::PathRegex,
+                rule_index: i,
+                pattern: self.path_regex_patterns[i].clone(),
+                specificity: (self.path_regex_rank[i] >> 32) as u32,
+                negated: self.path_regex_negated[i],
+                target: self.path_regex_targets[i],
+            }))
+            .collect();
+        matched_rules.sort_by_key(|rule| self.rank_of(rule));
+
+        let mut target = None;
+        for rule in &matched_rules {
+            target = (!rule.negated).then_some(rule.target);
+        }
+
+        SyntaxResolution {
+            stripped_file_name,
+            matched_rules,
+            target,
+        }
+    }
+
+    fn rank_of(&self, rule: &MatchedRule<'_>) -> u64 {
+        match rule.kind {
+            MatchedRuleKind::Glob => self.rank[rule.rule_index],
+            MatchedRuleKind::PathRegex => self.path_regex_rank[rule.rule_index],
+        }
+    }
+}
+
+/// Which of a [`SyntaxMapping`]'s two rule sets a [`MatchedRule`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchedRuleKind {
+    Glob,
+    PathRegex,
+}
+
+/// One rule that matched during `explain_syntax_for`, ranked by specificity
+/// against the others (see [`SyntaxResolution::matched_rules`]).
+#[derive(Debug, Clone)]
+pub struct MatchedRule<'a> {
+    pub kind: MatchedRuleKind,
+    /// Index into the declaration order of rules of this `kind` (builtins
+    /// first, then user rules).
+    pub rule_index: usize,
+    /// The original glob or (pre-anchor) regex source text of this rule, for
+    /// reporting which specific pattern matched.
+    pub pattern: String,
+    /// This rule's specificity score; see [`SyntaxMappingBuilder::map_syntax_with_priority`].
+    pub specificity: u32,
+    /// Whether this is a negation rule (`!glob`), which cancels `target`
+    /// rather than selecting it.
+    pub negated: bool,
+    pub target: MappingTarget<'a>,
+}
+
+/// The result of [`SyntaxMapping::explain_syntax_for`].
+#[derive(Debug, Clone)]
+pub struct SyntaxResolution<'a> {
+    /// The file name with any configured ignored suffix already stripped
+    /// off, as it was actually matched against.
+    pub stripped_file_name: OsString,
+    /// Every rule that matched the path or its file name, ranked by
+    /// specificity (least specific first), the same order they're applied
+    /// in to reach `target`.
+    pub matched_rules: Vec<MatchedRule<'a>>,
+    /// The final target after applying `matched_rules` in order (the same
+    /// result `get_syntax_for` returns for the suffix-stripped path).
+    pub target: Option<MappingTarget<'a>>,
+}
+
+/// Recognizes a `#!/usr/bin/env python3`-style shebang line, following a
+/// leading `env` invocation to the real interpreter, and looks the basename
+/// up (with path and trailing version number stripped) in
+/// `SHEBANG_INTERPRETERS`.
+fn resolve_shebang(first_line: &[u8]) -> Option<MappingTarget<'static>> {
+    let line = std::str::from_utf8(first_line).ok()?;
+    let rest = line.strip_prefix("#!")?;
+    let mut parts = rest.split_whitespace();
+    let interpreter = parts.next()?;
+    let mut name = interpreter.rsplit('/').next().unwrap_or(interpreter);
+    if name == "env" {
+        name = parts.next()?;
+    }
+    let name = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    SHEBANG_INTERPRETERS
+        .iter()
+        .find(|(interpreter, _)| *interpreter == name)
+        .map(|(_, syntax)| MappingTarget::MapTo(syntax))
+}
+
+/// Extracts the declared filetype/mode token from a Vim (`vim: set ft=rust:`)
+/// or Emacs (`-*- mode: Rust -*-`) modeline found anywhere in `text`, without
+/// resolving it to a syntax name.
+pub(crate) fn extract_modeline_filetype(text: &str) -> Option<&str> {
+    VIM_MODELINE
+        .captures(text)
+        .or_else(|| EMACS_MODELINE.captures(text))?
+        .get(1)
+        .map(|m| m.as_str())
+}
+
+/// Recognizes vim (`vim: set ft=rust:`) and Emacs (`-*- mode: Rust -*-`)
+/// modelines, mapping the declared filetype to a syntax via
+/// `MODELINE_FILETYPES`.
+fn resolve_modeline(first_line: &[u8]) -> Option<MappingTarget<'static>> {
+    let line = std::str::from_utf8(first_line).ok()?;
+    let filetype = extract_modeline_filetype(line)?;
+    MODELINE_FILETYPES
+        .iter()
+        .find(|(ft, _)| ft.eq_ignore_ascii_case(filetype))
+        .map(|(_, syntax)| MappingTarget::MapTo(syntax))
 }
 
 impl<'a> Default for SyntaxMapping<'a> {
     fn default() -> Self {
         let patterns: [&[u8]; 0] = [];
+        let no_str_patterns: [&str; 0] = [];
         SyntaxMapping {
             targets: Default::default(),
+            patterns: Default::default(),
+            negated: Default::default(),
+            rank: Default::default(),
             globset: Default::default(),
+            path_regexes: PathRegexSet::new(no_str_patterns).unwrap(),
+            path_regex_targets: Default::default(),
+            path_regex_patterns: Default::default(),
+            path_regex_negated: Default::default(),
+            path_regex_rank: Default::default(),
             ignored_suffixes: AhoCorasick::new(patterns).unwrap(),
+            first_line_targets: Default::default(),
+            first_line_patterns: RegexSet::empty(),
+            content_sniffing: true,
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct SyntaxMappingBuilder<'a> {
-    pub mapping: Vec<(Glob, MappingTarget<'a>)>,
+    pub mapping: Vec<(String, Glob, bool, MappingTarget<'a>, u32, u32)>,
+    /// Anchored-regex rules over the full path, for mappings a glob can't
+    /// express. See [`SyntaxMappingBuilder::map_path_regex`].
+    pub path_regex_mapping: Vec<(String, bool, MappingTarget<'a>, u32, u32)>,
     pub ignored_suffixes: Vec<String>,
+    /// Named glob sets (ripgrep-style `--type-add`), keyed by name, each
+    /// holding the (already brace-expanded) glob patterns it contains.
+    /// Referenced from `map_syntax` via `@name`.
+    pub type_sets: HashMap<String, Vec<String>>,
+    /// First-line regex patterns used to resolve `MapToUnknown`/
+    /// `MapExtensionToUnknown`, tried in declared order (last match wins).
+    pub first_line_mapping: Vec<(String, MappingTarget<'a>)>,
+    /// Shared declaration-order counter for `mapping` and
+    /// `path_regex_mapping`, so rules from either set break specificity ties
+    /// against each other by declaration order, not just within their own
+    /// set.
+    next_order: u32,
+    /// See [`Self::content_sniffing`].
+    content_sniffing: bool,
 }
 
 impl<'a> SyntaxMappingBuilder<'a> {
     pub fn new() -> Self {
         SyntaxMappingBuilder {
             mapping: Vec::new(),
+            path_regex_mapping: Vec::new(),
             ignored_suffixes: Vec::new(),
+            type_sets: HashMap::new(),
+            first_line_mapping: Vec::new(),
+            next_order: 0,
+            content_sniffing: true,
         }
     }
 
+    fn next_order(&mut self) -> u32 {
+        let order = self.next_order;
+        self.next_order += 1;
+        order
+    }
+
     pub fn with_builtin(mut self) -> Self {
         use MappingTarget::*;
-        self.mapping.extend(
-            include!("../assets/syntax_mapping.ron")
-                .into_iter()
-                .map(|(s, t)| {
-                    (
-                        GlobBuilder::new(s)
-                            .case_insensitive(true)
-                            .literal_separator(true)
-                            .build()
-                            .expect("invalid builtin syntax mapping"),
-                        t,
-                    )
-                }),
-        );
+        for (pattern, target) in include!("../assets/syntax_mapping.ron").into_iter() {
+            for pattern in expand_braces(pattern) {
+                let order = self.next_order();
+                self.mapping.push((
+                    pattern.clone(),
+                    GlobBuilder::new(&pattern)
+                        .case_insensitive(true)
+                        .literal_separator(true)
+                        .build()
+                        .expect("invalid builtin syntax mapping"),
+                    false,
+                    target,
+                    glob_specificity(&pattern),
+                    order,
+                ));
+            }
+        }
         self.ignored_suffixes.extend(
             include!("../assets/ignored_suffixes.ron")
                 .into_iter()
                 .map(|s| s.to_owned()),
         );
+        self.first_line_mapping.extend(
+            include!("../assets/first_line_patterns.ron")
+                .into_iter()
+                .map(|(pattern, t): (&str, _)| (pattern.to_owned(), t)),
+        );
         self
     }
 
     pub fn build(self) -> Result<SyntaxMapping<'a>> {
-        SyntaxMapping::new(self.mapping, self.ignored_suffixes)
+        SyntaxMapping::new(
+            self.mapping,
+            self.path_regex_mapping,
+            self.ignored_suffixes,
+            self.first_line_mapping,
+            self.content_sniffing,
+        )
     }
 
-    pub fn map_syntax(mut self, glob: &'_ str, target: MappingTarget<'a>) -> Result<Self> {
-        self.mapping.push((
-            GlobBuilder::new(glob)
-                .case_insensitive(true)
-                .literal_separator(true)
-                .build()?,
-            target,
-        ));
+    /// Defines (or extends, if already defined) a named set of globs that can
+    /// later be referenced from `map_syntax` as `@name`, mirroring ripgrep's
+    /// `--type-add`. Each pattern may itself use brace alternation
+    /// (`*.{c,h,cc}`).
+    pub fn define_type(mut self, name: &str, globs: &[&str]) -> Self {
+        let entry = self.type_sets.entry(name.to_owned()).or_default();
+        entry.extend(globs.iter().flat_map(|glob| expand_braces(glob)));
+        self
+    }
+
+    /// Adds a mapping rule for `glob`. A leading `!` (e.g. `!pf.conf`) makes
+    /// this a negation rule: when it's the highest-ranked matching rule for a
+    /// path, it cancels any syntax selected by a less specific rule instead
+    /// of assigning one, letting narrower rules carve exceptions out of
+    /// broad globs (`*.conf` → ini, but `!pf.conf`). `target` is ignored for
+    /// negations.
+    ///
+    /// `glob` may use brace alternation (`*.{c,h,cc}`), or be `@name` to
+    /// reference a set of globs previously registered with `define_type`.
+    ///
+    /// Competing rules (including path-regex rules from
+    /// [`map_path_regex`](Self::map_path_regex)) are ranked by an
+    /// automatically computed specificity score, so a directory-anchored
+    /// glob like `**/.ssh/config` wins over a generic extension glob like
+    /// `*.conf` regardless of which was added first; ties break by
+    /// declaration order, latest wins. Use
+    /// [`map_syntax_with_priority`](Self::map_syntax_with_priority) to
+    /// override the score explicitly.
+    pub fn map_syntax(self, glob: &'_ str, target: MappingTarget<'a>) -> Result<Self> {
+        self.map_syntax_impl(glob, target, None)
+    }
+
+    /// Like [`map_syntax`](Self::map_syntax), but ranks this rule with an
+    /// explicit `priority` instead of the automatically computed specificity
+    /// score, for cases the heuristic gets wrong.
+    pub fn map_syntax_with_priority(
+        self,
+        glob: &'_ str,
+        target: MappingTarget<'a>,
+        priority: u32,
+    ) -> Result<Self> {
+        self.map_syntax_impl(glob, target, Some(priority))
+    }
+
+    fn map_syntax_impl(
+        mut self,
+        glob: &'_ str,
+        target: MappingTarget<'a>,
+        priority: Option<u32>,
+    ) -> Result<Self> {
+        let (negated, glob) = match glob.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, glob),
+        };
+        let patterns = if let Some(name) = glob.strip_prefix('@') {
+            self.type_sets
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("no such syntax mapping type set: `{name}`"))?
+                .clone()
+        } else {
+            expand_braces(glob)
+        };
+        for pattern in patterns {
+            let specificity = priority.unwrap_or_else(|| glob_specificity(&pattern));
+            let order = self.next_order();
+            self.mapping.push((
+                pattern.clone(),
+                GlobBuilder::new(&pattern)
+                    .case_insensitive(true)
+                    .literal_separator(true)
+                    .build()?,
+                negated,
+                target,
+                specificity,
+                order,
+            ));
+        }
+        Ok(self)
+    }
+
+    /// Adds an anchored regex rule matched against the full (absolute,
+    /// ignored-suffix-stripped) path, for mappings a glob can't express --
+    /// e.g. "a file named `config` anywhere under a `.ssh` directory". The
+    /// pattern is anchored to match the whole path automatically if it isn't
+    /// already. A leading `!` makes it a negation rule, same as
+    /// [`map_syntax`](Self::map_syntax).
+    ///
+    /// Participates in the same specificity-ranked resolution as glob rules;
+    /// specificity is estimated from the pattern's literal (non-metacharacter)
+    /// content. Use
+    /// [`map_path_regex_with_priority`](Self::map_path_regex_with_priority)
+    /// to override the estimate explicitly.
+    pub fn map_path_regex(self, pattern: &str, target: MappingTarget<'a>) -> Result<Self> {
+        self.map_path_regex_impl(pattern, target, None)
+    }
+
+    /// Like [`map_path_regex`](Self::map_path_regex), but ranks this rule
+    /// with an explicit `priority` instead of the automatically estimated
+    /// specificity.
+    pub fn map_path_regex_with_priority(
+        self,
+        pattern: &str,
+        target: MappingTarget<'a>,
+        priority: u32,
+    ) -> Result<Self> {
+        self.map_path_regex_impl(pattern, target, Some(priority))
+    }
+
+    fn map_path_regex_impl(
+        mut self,
+        pattern: &str,
+        target: MappingTarget<'a>,
+        priority: Option<u32>,
+    ) -> Result<Self> {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        // Validated eagerly so a malformed pattern is reported here, at
+        // config/CLI-parse time, rather than when `RegexSet::new` builds the
+        // combined automaton in `SyntaxMapping::new`.
+        Regex::new(pattern)?;
+        let specificity = priority.unwrap_or_else(|| regex_specificity(pattern));
+        let order = self.next_order();
+        self.path_regex_mapping
+            .push((pattern.to_owned(), negated, target, specificity, order));
         Ok(self)
     }
 
@@ -157,6 +850,23 @@ impl<'a> SyntaxMappingBuilder<'a> {
         self.ignored_suffixes.push(suffix);
         self
     }
+
+    /// Registers a first-line regex `pattern` that, when it matches the
+    /// first line of a file whose extension/name mapped to `MapToUnknown` or
+    /// `MapExtensionToUnknown`, resolves it to `target`. Declaration order
+    /// matters: the last matching pattern wins.
+    pub fn map_first_line(mut self, pattern: &str, target: MappingTarget<'a>) -> Self {
+        self.first_line_mapping.push((pattern.to_owned(), target));
+        self
+    }
+
+    /// Enables or disables [`SyntaxMapping::resolve_by_content_sniff`]'s
+    /// multi-line content-signature fallback (XML/HTML declarations,
+    /// JSON/TOML/YAML structure, SQL keyword density). Enabled by default.
+    pub fn content_sniffing(mut self, enabled: bool) -> Self {
+        self.content_sniffing = enabled;
+        self
+    }
 }
 
 impl<'a> Default for SyntaxMappingBuilder<'a> {
@@ -164,3 +874,245 @@ impl<'a> Default for SyntaxMappingBuilder<'a> {
         Self::new()
     }
 }
+
+/// The (de)serializable counterpart to [`MappingTarget`], used by
+/// [`SyntaxMappingConfig`]. `MapTo` owns its syntax name so that it can be
+/// deserialized from a config file without borrowing from it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MappingTargetConfig {
+    MapTo(String),
+    MapToUnknown,
+    MapExtensionToUnknown,
+}
+
+/// The declarative, (de)serializable form of a [`SyntaxMapping`], as stored
+/// in `Config` and loaded from the config file's `map-syntax`/
+/// `ignored-suffix` settings. Call [`SyntaxMappingConfig::consolidate`] to
+/// validate the globs and compile it into the `SyntaxMapping` actually used
+/// for matching.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyntaxMappingConfig {
+    #[serde(default = "SyntaxMappingConfig::default_use_builtins")]
+    pub use_builtins: bool,
+    #[serde(default)]
+    pub mapped_syntaxes: Vec<(String, MappingTargetConfig)>,
+    #[serde(default)]
+    pub ignored_suffixes: Vec<String>,
+}
+
+impl SyntaxMappingConfig {
+    fn default_use_builtins() -> bool {
+        true
+    }
+
+    pub fn map_syntax(&mut self, glob: impl Into<String>, target: MappingTargetConfig) {
+        self.mapped_syntaxes.push((glob.into(), target));
+    }
+
+    pub fn ignore_suffix(&mut self, suffix: impl Into<String>) {
+        self.ignored_suffixes.push(suffix.into());
+    }
+
+    pub fn use_builtins(&mut self, yes: bool) {
+        self.use_builtins = yes;
+    }
+
+    /// Validates the configured globs and compiles this into the
+    /// `SyntaxMapping` used for actual path/extension matching. A malformed
+    /// glob is reported here, at config-load time, rather than panicking
+    /// later when matching against a path.
+    pub fn consolidate(self) -> Result<SyntaxMapping<'static>> {
+        let mut builder = SyntaxMappingBuilder::new();
+        if self.use_builtins {
+            builder = builder.with_builtin();
+        }
+        for (glob, target) in self.mapped_syntaxes {
+            let target = match target {
+                MappingTargetConfig::MapTo(name) => MappingTarget::MapTo(leak_config_string(name)),
+                MappingTargetConfig::MapToUnknown => MappingTarget::MapToUnknown,
+                MappingTargetConfig::MapExtensionToUnknown => MappingTarget::MapExtensionToUnknown,
+            };
+            builder = builder.map_syntax(&glob, target)?;
+        }
+        for suffix in self.ignored_suffixes {
+            builder = builder.ignored_suffix(suffix);
+        }
+        builder.build()
+    }
+}
+
+impl Default for SyntaxMappingConfig {
+    fn default() -> Self {
+        SyntaxMappingConfig {
+            use_builtins: Self::default_use_builtins(),
+            mapped_syntaxes: Vec::new(),
+            ignored_suffixes: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brace_expansion() {
+        assert_eq!(expand_braces("*.{c,h,cc}"), vec!["*.c", "*.h", "*.cc"]);
+        assert_eq!(expand_braces("*.rs"), vec!["*.rs"]);
+    }
+
+    #[test]
+    fn shebang_resolution() {
+        assert_eq!(
+            resolve_shebang(b"#!/usr/bin/env python3"),
+            Some(MappingTarget::MapTo("Python"))
+        );
+        assert_eq!(
+            resolve_shebang(b"#!/bin/bash"),
+            Some(MappingTarget::MapTo("Bourne Again Shell (bash)"))
+        );
+        assert_eq!(resolve_shebang(b"no shebang here"), None);
+    }
+
+    #[test]
+    fn modeline_resolution() {
+        assert_eq!(
+            resolve_modeline(b"// vim: set ft=rust:"),
+            Some(MappingTarget::MapTo("Rust"))
+        );
+        assert_eq!(
+            resolve_modeline(b"/* -*- mode: Python -*- */"),
+            Some(MappingTarget::MapTo("Python"))
+        );
+        assert_eq!(resolve_modeline(b"nothing special"), None);
+    }
+
+    #[test]
+    fn no_duplicate_extensions_across_type_sets() {
+        let builder = SyntaxMappingBuilder::new()
+            .define_type("c", &["*.c", "*.h"])
+            .define_type("cpp", &["*.cc", "*.hh", "*.{cpp,hpp}"]);
+
+        let mut seen = std::collections::HashSet::new();
+        for globs in builder.type_sets.values() {
+            for glob in globs {
+                assert!(
+                    seen.insert(glob),
+                    "glob \"{glob}\" appears in two named type sets"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn glob_specificity_favors_literal_segments() {
+        assert!(glob_specificity("**/.ssh/config") > glob_specificity("*.conf"));
+        assert!(glob_specificity("pf.conf") > glob_specificity("*.conf"));
+    }
+
+    #[test]
+    fn more_specific_rule_wins_regardless_of_declaration_order() {
+        // A directory-anchored rule declared *before* a generic extension
+        // rule should still win, because it's more specific -- the opposite
+        // of the plain last-rule-wins behavior for equally specific rules.
+        let mapping = SyntaxMappingBuilder::new()
+            .map_syntax("**/.ssh/*.conf", MappingTarget::MapTo("SSH Config"))
+            .unwrap()
+            .map_syntax("*.conf", MappingTarget::MapTo("INI"))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            mapping.get_syntax_for("/home/user/.ssh/ssh.conf"),
+            Some(MappingTarget::MapTo("SSH Config"))
+        );
+        assert_eq!(
+            mapping.get_syntax_for("/etc/app.conf"),
+            Some(MappingTarget::MapTo("INI"))
+        );
+    }
+
+    #[test]
+    fn path_regex_rule_matches_full_path() {
+        let mapping = SyntaxMappingBuilder::new()
+            .map_path_regex(r"/etc/nginx/.*\.conf", MappingTarget::MapTo("nginx"))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            mapping.get_syntax_for("/etc/nginx/sites-enabled/default.conf"),
+            Some(MappingTarget::MapTo("nginx"))
+        );
+        assert_eq!(mapping.get_syntax_for("/etc/other/default.conf"), None);
+    }
+
+    #[test]
+    fn explicit_priority_overrides_automatic_specificity() {
+        // Without the override, "**/app/*.conf" (one literal segment) would
+        // outrank the bare "*.conf" glob (zero literal segments).
+        let mapping = SyntaxMappingBuilder::new()
+            .map_syntax("**/app/*.conf", MappingTarget::MapTo("App Config"))
+            .unwrap()
+            .map_syntax_with_priority("*.conf", MappingTarget::MapTo("INI"), u32::MAX)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            mapping.get_syntax_for("/srv/app/settings.conf"),
+            Some(MappingTarget::MapTo("INI"))
+        );
+    }
+
+    #[test]
+    fn content_sniff_recognizes_structural_signatures() {
+        assert_eq!(
+            sniff_content("<?xml version=\"1.0\"?>\n<root/>\n"),
+            Some(MappingTarget::MapTo("XML"))
+        );
+        assert_eq!(
+            sniff_content("<!DOCTYPE html>\n<html></html>\n"),
+            Some(MappingTarget::MapTo("HTML"))
+        );
+        assert_eq!(
+            sniff_content("{\n  \"name\": \"bat\",\n  \"version\": \"1.0\"\n}\n"),
+            Some(MappingTarget::MapTo("JSON"))
+        );
+        assert_eq!(
+            sniff_content("[package]\nname = \"bat\"\nversion = \"1.0\"\n"),
+            Some(MappingTarget::MapTo("TOML"))
+        );
+        assert_eq!(
+            sniff_content("---\nname: bat\nversion: 1.0\n"),
+            Some(MappingTarget::MapTo("YAML"))
+        );
+        assert_eq!(
+            sniff_content(
+                "SELECT id, name FROM users WHERE active = 1;\n\
+                 INSERT INTO logs (msg) VALUES ('hi');\n"
+            ),
+            Some(MappingTarget::MapTo("SQL"))
+        );
+        assert_eq!(sniff_content("just some plain prose, nothing special"), None);
+    }
+
+    #[test]
+    fn content_sniffing_can_be_disabled() {
+        let mapping = SyntaxMappingBuilder::new().build().unwrap();
+        assert_eq!(
+            mapping.resolve_by_content_sniff("---\nname: bat\nversion: 1.0\n"),
+            Some(MappingTarget::MapTo("YAML"))
+        );
+
+        let mapping = SyntaxMappingBuilder::new()
+            .content_sniffing(false)
+            .build()
+            .unwrap();
+        assert_eq!(
+            mapping.resolve_by_content_sniff("---\nname: bat\nversion: 1.0\n"),
+            None
+        );
+    }
+}
@@ -9,11 +9,127 @@ use syn::{
     parse_macro_input, parse_quote, parse_quote_spanned, parse_str, Error, Expr, Ident, Lit,
 };
 
+/// Identifies a single argument slot: either a positional argument from the
+/// macro's argument list, or a captured variable referenced by name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ArgKey {
+    Pos(usize),
+    Name(String),
+}
+
+/// A count (width or precision) embedded in a format spec: either a literal
+/// number or a `$`-reference to another argument.
+#[derive(Debug, Clone)]
+enum Count {
+    Literal(String),
+    Arg(ArgKey),
+}
+
 #[derive(Debug, Clone)]
 enum FmtArg {
     String(String),
-    Display(Expr),
-    Format(String, Option<Expr>),
+    /// Bare `{}`/`{ident}`/`{0}` placeholder with no spec: fast `Display` path.
+    Display(ArgKey),
+    /// Placeholder with a spec (possibly with `$`-referenced width/precision):
+    /// forwarded to `std::format_args!` so alignment/fill/precision apply.
+    Format { primary: ArgKey, spec: String, refs: Vec<ArgKey> },
+}
+
+fn parse_arg_key(s: &str) -> Option<ArgKey> {
+    if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+        s.parse::<usize>().ok().map(ArgKey::Pos)
+    } else if parse_str::<Ident>(s).is_ok() {
+        Some(ArgKey::Name(s.to_string()))
+    } else {
+        None
+    }
+}
+
+/// Consumes a leading `count` (digits) or `argument '$'` (digits or ident
+/// followed by `$`) from the front of `s`, per the `std::fmt` grammar.
+fn take_count_or_arg(s: &str) -> (Option<Count>, &str) {
+    let digits_len = s.bytes().take_while(|b| b.is_ascii_digit()).count();
+    if digits_len > 0 {
+        if s[digits_len..].starts_with('$') {
+            let key = ArgKey::Pos(s[..digits_len].parse().unwrap());
+            return (Some(Count::Arg(key)), &s[digits_len + 1..]);
+        }
+        return (Some(Count::Literal(s[..digits_len].to_string())), &s[digits_len..]);
+    }
+    let ident_len = s
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .map(char::len_utf8)
+        .sum::<usize>();
+    if ident_len > 0 && s[ident_len..].starts_with('$') {
+        if let Some(key) = parse_arg_key(&s[..ident_len]) {
+            return (Some(Count::Arg(key)), &s[ident_len + 1..]);
+        }
+    }
+    (None, s)
+}
+
+/// Parses the spec text following the `:` in a placeholder (e.g. `>10.2`,
+/// `1$.2$x`, `width$`), rewriting any `$`-referenced width/precision into a
+/// local positional reference (arg 0 is always the primary value) and
+/// returning the referenced args in the order they must be appended to the
+/// generated `std::format_args!` call.
+fn parse_spec(spec: &str) -> (String, Vec<ArgKey>) {
+    let mut rest = spec;
+    let mut out = String::new();
+
+    let chars: Vec<char> = rest.chars().collect();
+    if chars.len() >= 2 && matches!(chars[1], '<' | '^' | '>') {
+        out.push(chars[0]);
+        out.push(chars[1]);
+        rest = &rest[chars[0].len_utf8() + chars[1].len_utf8()..];
+    } else if chars.first().is_some_and(|c| matches!(c, '<' | '^' | '>')) {
+        out.push(chars[0]);
+        rest = &rest[chars[0].len_utf8()..];
+    }
+    if let Some(c @ ('+' | '-')) = rest.chars().next() {
+        out.push(c);
+        rest = &rest[1..];
+    }
+    if let Some(r) = rest.strip_prefix('#') {
+        out.push('#');
+        rest = r;
+    }
+    if rest.starts_with('0') && rest[1..].chars().next().is_some_and(|c| c.is_ascii_digit()) {
+        out.push('0');
+        rest = &rest[1..];
+    }
+
+    let mut refs = Vec::new();
+    let (width, after_width) = take_count_or_arg(rest);
+    rest = after_width;
+    if let Some(count) = width {
+        match count {
+            Count::Literal(s) => out.push_str(&s),
+            Count::Arg(key) => {
+                refs.push(key);
+                out.push_str(&refs.len().to_string());
+                out.push('$');
+            }
+        }
+    }
+    if let Some(r) = rest.strip_prefix('.') {
+        out.push('.');
+        let (precision, after_precision) = take_count_or_arg(r);
+        rest = after_precision;
+        if let Some(count) = precision {
+            match count {
+                Count::Literal(s) => out.push_str(&s),
+                Count::Arg(key) => {
+                    refs.push(key);
+                    out.push_str(&refs.len().to_string());
+                    out.push('$');
+                }
+            }
+        }
+    }
+    out.push_str(rest);
+    (out, refs)
 }
 
 fn write_impl(tokens: proc_macro::TokenStream, ln: bool) -> proc_macro::TokenStream {
@@ -53,17 +169,18 @@ fn write_impl(tokens: proc_macro::TokenStream, ln: bool) -> proc_macro::TokenStr
     .to_compile_error()
     .into();
     let fmt_str = fmt_str.value();
+    let pos_args: Vec<Expr> = args.collect();
 
     let mut iter = fmt_str.chars().peekable();
     let mut in_brace = false;
     let mut current_string = String::new();
-    let mut fmt_args = Vec::new();
+    let mut raw_items: Vec<Result<String, String>> = Vec::new();
     while let Some(c) = iter.next() {
         if c == '{' {
             if iter.next_if_eq(&'{').is_none() {
                 if !in_brace {
                     if !current_string.is_empty() {
-                        fmt_args.push(FmtArg::String(mem::take(&mut current_string)));
+                        raw_items.push(Ok(mem::take(&mut current_string)));
                     }
                     in_brace = true;
                     continue;
@@ -73,26 +190,7 @@ fn write_impl(tokens: proc_macro::TokenStream, ln: bool) -> proc_macro::TokenStr
             }
         } else if c == '}' {
             if in_brace {
-                let pat = mem::take(&mut current_string);
-                if pat.is_empty() {
-                    fmt_args.push(FmtArg::Display(match args.next() {
-                        Some(e) => e,
-                        None => return mismatch_args,
-                    }))
-                } else if let Ok(ident) = parse_str::<Ident>(&pat) {
-                    let e: Expr = parse_quote_spanned! { sspan => #ident };
-                    fmt_args.push(FmtArg::Display(e));
-                } else if pat.starts_with(':') {
-                    fmt_args.push(FmtArg::Format(
-                        pat,
-                        match args.next() {
-                            e @ Some(_) => e,
-                            None => return mismatch_args,
-                        },
-                    ))
-                } else {
-                    fmt_args.push(FmtArg::Format(pat, None))
-                }
+                raw_items.push(Err(mem::take(&mut current_string)));
                 in_brace = false;
                 continue;
             } else {
@@ -103,55 +201,134 @@ fn write_impl(tokens: proc_macro::TokenStream, ln: bool) -> proc_macro::TokenStr
         }
         current_string.push(c);
     }
+    if in_brace {
+        return invalid_fmt_str;
+    }
     if !current_string.is_empty() {
-        fmt_args.push(FmtArg::String(current_string));
+        raw_items.push(Ok(current_string));
     }
-    if args.next().is_some() {
-        return mismatch_args;
+
+    let mut auto_counter = 0usize;
+    let mut fmt_args = Vec::with_capacity(raw_items.len());
+    for item in raw_items {
+        let pat = match item {
+            Ok(s) => {
+                fmt_args.push(FmtArg::String(s));
+                continue;
+            }
+            Err(pat) => pat,
+        };
+        let (arg_part, spec_part) = match pat.split_once(':') {
+            Some((a, s)) => (a, Some(s)),
+            None => (pat.as_str(), None),
+        };
+        let key = if arg_part.is_empty() {
+            let key = ArgKey::Pos(auto_counter);
+            auto_counter += 1;
+            key
+        } else {
+            match parse_arg_key(arg_part) {
+                Some(key) => key,
+                None => return invalid_fmt_str,
+            }
+        };
+        match spec_part {
+            None => fmt_args.push(FmtArg::Display(key)),
+            Some(spec) => {
+                let (spec, refs) = parse_spec(spec);
+                fmt_args.push(FmtArg::Format { primary: key, spec, refs });
+            }
+        }
     }
 
-    let arg_names: Vec<_> = fmt_args
-        .iter()
-        .enumerate()
-        .map(|(i, _)| format_ident!("_{}", i))
-        .collect();
+    // Assign each *distinct* referenced argument a single helper parameter,
+    // in order of first appearance, so that side-effecting expressions are
+    // evaluated exactly once even if referenced from multiple placeholders.
+    fn note_key(key: &ArgKey, arg_order: &mut Vec<ArgKey>) {
+        if !arg_order.contains(key) {
+            arg_order.push(key.clone());
+        }
+    }
+    let mut arg_order: Vec<ArgKey> = Vec::new();
+    for fmt_arg in &fmt_args {
+        match fmt_arg {
+            FmtArg::String(_) => {}
+            FmtArg::Display(key) => note_key(key, &mut arg_order),
+            FmtArg::Format { primary, refs, .. } => {
+                note_key(primary, &mut arg_order);
+                for key in refs {
+                    note_key(key, &mut arg_order);
+                }
+            }
+        }
+    }
 
-    let mut body: TokenStream = arg_names
-        .iter()
-        .cloned()
-        .zip(fmt_args.iter())
-        .map(|(a, fmt_arg)| match fmt_arg {
-            FmtArg::String(_) => quote! { self.write_str(#a)?; },
-            FmtArg::Display(_) => quote! { self.write_str(#a)?; },
-            FmtArg::Format(_, _) => quote! { self.write_fmt(#a)?; },
-        })
-        .collect();
+    let mut used_pos = vec![false; pos_args.len()];
+    let mut arg_idents = Vec::with_capacity(arg_order.len());
+    let mut arg_sources = Vec::with_capacity(arg_order.len());
+    for key in &arg_order {
+        let source: Expr = match key {
+            ArgKey::Pos(i) => {
+                let Some(e) = pos_args.get(*i) else {
+                    return mismatch_args;
+                };
+                used_pos[*i] = true;
+                e.clone()
+            }
+            ArgKey::Name(name) => {
+                let ident: Ident = match parse_str(name) {
+                    Ok(ident) => ident,
+                    Err(_) => return invalid_fmt_str,
+                };
+                parse_quote_spanned! { sspan => #ident }
+            }
+        };
+        arg_idents.push(format_ident!("_arg{}", arg_idents.len()));
+        arg_sources.push(source);
+    }
+    if used_pos.contains(&false) {
+        return mismatch_args;
+    }
+    let key_ident = |key: &ArgKey| -> Ident {
+        let idx = arg_order.iter().position(|k| k == key).unwrap();
+        arg_idents[idx].clone()
+    };
 
-    let fn_args: TokenStream = arg_names
+    let arg_bindings: TokenStream = arg_idents
         .iter()
-        .zip(fmt_args.iter())
-        .map(|(a, fmt_arg)| match fmt_arg {
-            FmtArg::String(_) | FmtArg::Display(_) => quote! { #a: &str, },
-            FmtArg::Format(_, _) => quote! { #a: std::fmt::Arguments, },
-        })
+        .zip(arg_sources.iter())
+        .map(|(ident, src)| quote! { let #ident = &(#src); })
         .collect();
 
-    let relay: TokenStream = arg_names.into_iter().map(|a| quote! { #a, }).collect();
-
-    let call: TokenStream = fmt_args
-        .into_iter()
-        .map(|fmt_arg| match fmt_arg {
-            FmtArg::String(s) => quote! { #s, },
-            FmtArg::Display(e) => quote! { (#e).to_compact_string().as_str(), },
-            FmtArg::Format(mut f, e) => {
-                f = format!("{{{}}}", f);
-                match e {
-                    Some(e) => quote! { std::format_args!(#f, (#e)), },
-                    None => quote! { std::format_args!(#f), },
-                }
+    let seg_names: Vec<_> = (0..fmt_args.len()).map(|i| format_ident!("_{}", i)).collect();
+    let relay: TokenStream = seg_names.iter().map(|a| quote! { #a, }).collect();
+
+    let mut body: TokenStream = TokenStream::new();
+    let mut fn_args: TokenStream = TokenStream::new();
+    let mut call: TokenStream = TokenStream::new();
+    for (seg, fmt_arg) in seg_names.iter().zip(fmt_args.into_iter()) {
+        match fmt_arg {
+            FmtArg::String(s) => {
+                body.extend(quote! { self.write_str(#seg)?; });
+                fn_args.extend(quote! { #seg: &str, });
+                call.extend(quote! { #s, });
             }
-        })
-        .collect();
+            FmtArg::Display(key) => {
+                let arg = key_ident(&key);
+                body.extend(quote! { self.write_str(#seg)?; });
+                fn_args.extend(quote! { #seg: &str, });
+                call.extend(quote! { (*#arg).to_compact_string().as_str(), });
+            }
+            FmtArg::Format { primary, spec, refs } => {
+                let f = format!("{{:{}}}", spec);
+                let primary_arg = key_ident(&primary);
+                let ref_args: Vec<_> = refs.iter().map(|k| key_ident(k)).collect();
+                body.extend(quote! { self.write_fmt(#seg)?; });
+                fn_args.extend(quote! { #seg: std::fmt::Arguments, });
+                call.extend(quote! { std::format_args!(#f, *#primary_arg #(, *#ref_args)*), });
+            }
+        }
+    }
 
     if ln {
         body.extend(quote! {
@@ -196,6 +373,7 @@ fn write_impl(tokens: proc_macro::TokenStream, ln: bool) -> proc_macro::TokenStr
                     }
                 }
             }
+            #arg_bindings
             (#writer).__run(#call)
         }
     };